@@ -0,0 +1,393 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "native")]
+use crate::registry::{self, RegistryOptions};
+use crate::values::ResolvedValue;
+
+/// The manifest file name Spin looks for in an application directory.
+pub const DEFAULT_MANIFEST_FILE: &str = "spin.toml";
+
+/// The parts of an application that are relevant to its configuration.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AppInfo {
+    /// The application name from the manifest.
+    pub name: String,
+    pub variables: Vec<VariableInfo>,
+    pub components: Vec<ComponentInfo>,
+    pub triggers: Vec<TriggerInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VariableInfo {
+    pub name: String,
+    pub default_value: Option<String>,
+    pub required: bool,
+    pub secret: bool,
+    /// The value the variable will take, if known.
+    pub value: Option<ResolvedValue>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ComponentInfo {
+    pub id: String,
+    /// Component variable names and the templates that define them.
+    pub variables: Vec<(String, String)>,
+    pub allowed_outbound_hosts: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TriggerInfo {
+    pub trigger_type: String,
+    /// The trigger ID, or `None` for the application-level settings of the trigger type.
+    pub id: Option<String>,
+    /// String-valued settings, keyed by their dotted path within the trigger config.
+    pub settings: Vec<(String, String)>,
+}
+
+/// Checks that a name is one Spin accepts for a variable.
+pub fn validate_variable_name(name: &str) -> Result<(), String> {
+    spin_expressions::Key::new(name).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Whether a variable name suggests that its value is a secret, such as
+/// `db_password` or `api_key`.
+pub fn looks_secret(name: &str) -> bool {
+    const SECRET_WORDS: &[&str] = &["password", "passwd", "pwd", "secret", "token", "key", "apikey", "credential", "credentials", "private"];
+    name.to_ascii_lowercase().split(['_', '-']).any(|word| SECRET_WORDS.contains(&word))
+}
+
+impl TriggerInfo {
+    pub fn display_name(&self) -> String {
+        match &self.id {
+            Some(id) => format!("{} ({id})", self.trigger_type),
+            None => format!("{} (application)", self.trigger_type),
+        }
+    }
+}
+
+/// Reads an application from a manifest file. Legacy version 1 manifests are
+/// normalized to the version 2 model by Spin's manifest loader, so both give the
+/// same [`AppInfo`].
+#[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+pub async fn app_from_toml(path: impl AsRef<Path>) -> anyhow::Result<AppInfo> {
+    use anyhow::Context;
+
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read manifest {}", path.display()))?;
+    app_from_toml_str(&text)
+}
+
+/// Reads an application from manifest text, for callers without a file system,
+/// such as web tooling.
+///
+/// If the manifest is a newer version than Spin's loader understands, only the
+/// variables and components are read, straight from the TOML, with a warning.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn app_from_toml_str(text: &str) -> anyhow::Result<AppInfo> {
+    match spin_manifest::manifest_from_str(text) {
+        Ok(manifest) => Ok(app_from_manifest(manifest)),
+        Err(e) => match newer_manifest_version(text) {
+            Some(version) => {
+                tracing::warn!(%version, error = %e, "manifest version is newer than this tool understands. Reading variables and components only, so trigger settings are not shown");
+                app_from_newer_manifest(text)
+            }
+            None => Err(e.into()),
+        },
+    }
+}
+
+/// The manifest version, if it is newer than version 2.
+fn newer_manifest_version(text: &str) -> Option<i64> {
+    let table: toml::Table = toml::from_str(text).ok()?;
+    table.get("spin_manifest_version")?.as_integer().filter(|version| *version > 2)
+}
+
+/// Reads what it can of a manifest, assuming it keeps the version 2 layout of
+/// variables and components. Settings that aren't recognized are ignored. Tables
+/// keep the manifest's order, since the `toml` crate is built with `preserve_order`.
+fn app_from_newer_manifest(text: &str) -> anyhow::Result<AppInfo> {
+    let table: toml::Table = toml::from_str(text)?;
+    let tables = |item: Option<&toml::Value>| item.and_then(|v| v.as_table()).cloned().unwrap_or_default();
+    let string = |table: &toml::Table, key: &str| table.get(key).and_then(|v| v.as_str()).map(|s| s.to_owned());
+    let flag = |table: &toml::Table, key: &str| table.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let name = tables(table.get("application")).get("name").and_then(|n| n.as_str()).unwrap_or_default().to_owned();
+
+    let variables = tables(table.get("variables")).into_iter().map(|(name, variable)| {
+        let variable = variable.as_table().cloned().unwrap_or_default();
+        VariableInfo {
+            name,
+            default_value: string(&variable, "default"),
+            required: flag(&variable, "required"),
+            secret: flag(&variable, "secret"),
+            value: None,
+        }
+    }).collect();
+
+    let components = tables(table.get("component")).into_iter().map(|(id, component)| {
+        let component = component.as_table().cloned().unwrap_or_default();
+        ComponentInfo {
+            variables: tables(component.get("variables")).iter().filter_map(|(name, template)| Some((name.clone(), template.as_str()?.to_owned()))).collect(),
+            allowed_outbound_hosts: component.get("allowed_outbound_hosts").and_then(|hosts| hosts.as_array())
+                .map(|hosts| hosts.iter().filter_map(|h| h.as_str()).map(|h| h.to_owned()).collect())
+                .unwrap_or_default(),
+            id,
+        }
+    }).collect();
+
+    Ok(AppInfo { name, variables, components, triggers: vec![] })
+}
+
+fn app_from_manifest(manifest: spin_manifest::schema::v2::AppManifest) -> AppInfo {
+    let name = manifest.application.name;
+    let variables = manifest.variables.into_iter().map(|(name, variable)| VariableInfo {
+        name: name.to_string(),
+        default_value: variable.default,
+        required: variable.required,
+        secret: variable.secret,
+        value: None,
+    }).collect();
+    let components = manifest.components.into_iter().map(|(id, component)| ComponentInfo {
+        id: id.to_string(),
+        variables: component.variables.into_iter().map(|(name, template)| (name.to_string(), template)).collect(),
+        allowed_outbound_hosts: component.allowed_outbound_hosts,
+    }).collect();
+
+    let mut triggers: Vec<_> = manifest.application.trigger_global_configs.into_iter().map(|(trigger_type, config)| TriggerInfo {
+        trigger_type,
+        id: None,
+        settings: toml_string_settings(&config),
+    }).collect();
+    for (trigger_type, type_triggers) in manifest.triggers {
+        triggers.extend(type_triggers.into_iter().map(|trigger| TriggerInfo {
+            trigger_type: trigger_type.clone(),
+            id: Some(trigger.id),
+            settings: toml_string_settings(&trigger.config),
+        }));
+    }
+
+    AppInfo { name, variables, components, triggers }
+}
+
+#[cfg(feature = "native")]
+#[tracing::instrument(level = "info", skip(options))]
+pub async fn app_from_registry(reference: &str, options: &RegistryOptions) -> anyhow::Result<AppInfo> {
+    if !options.ca_certs.is_empty() {
+        // Spin's registry client builds its own TLS configuration, without extra CAs
+        return app_from_registry_config(reference, options).await;
+    }
+
+    let working_dir = oci_working_dir(reference)?;
+
+    let locked_app = registry::with_retries(options, &format!("Pulling {reference}"), || async {
+        let mut client = spin_oci::Client::new(options.insecure, None).await?;
+        spin_oci::OciLoader::new(&working_dir).load_app(&mut client, reference).await
+    }).await?;
+
+    Ok(app_from_locked(locked_app))
+}
+
+/// Reads an application from the config of its registry image, which is its locked
+/// form, without pulling the components. This goes through a registry client that
+/// trusts the extra CAs, anonymously, as when listing tags.
+#[cfg(feature = "native")]
+async fn app_from_registry_config(reference: &str, options: &RegistryOptions) -> anyhow::Result<AppInfo> {
+    use anyhow::Context;
+
+    let parsed: oci_distribution::Reference = reference.parse().with_context(|| format!("'{reference}' is not a valid registry reference"))?;
+    let client = registry::client(options)?;
+    let config = registry::with_retries(options, &format!("Pulling {reference}"), || async {
+        let (_, _, config) = client.pull_manifest_and_config(&parsed, &oci_distribution::secrets::RegistryAuth::Anonymous).await?;
+        Ok(config)
+    }).await?;
+    let locked_app = serde_json::from_str(&config).with_context(|| format!("{reference} is not a Spin application"))?;
+    Ok(app_from_locked(locked_app))
+}
+
+/// Reads an application from its locked form, as stored in registries.
+#[cfg(feature = "native")]
+pub(crate) fn app_from_locked(locked_app: spin_locked_app::locked::LockedApp) -> AppInfo {
+    let name = locked_app.metadata.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_owned();
+    // Locked applications keep variables in hash maps, so the declaration order is lost.
+    // Sort them so that output is the same from run to run.
+    let mut variables: Vec<_> = locked_app.variables.into_iter().map(|(name, variable)| VariableInfo {
+        name,
+        required: variable.default.is_none(),
+        default_value: variable.default,
+        secret: variable.secret,
+        value: None,
+    }).collect();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let components = locked_app.components.into_iter().map(|component| ComponentInfo {
+        allowed_outbound_hosts: component.metadata.get("allowed_outbound_hosts")
+            .and_then(|hosts| serde_json::from_value(hosts.clone()).ok())
+            .unwrap_or_default(),
+        variables: component.config.into_iter().collect::<std::collections::BTreeMap<_, _>>().into_iter().collect(),
+        id: component.id,
+    }).collect();
+
+    let global_trigger_configs = locked_app.metadata.get("triggers").and_then(|t| t.as_object());
+    let mut triggers: Vec<_> = global_trigger_configs.into_iter().flatten().map(|(trigger_type, config)| TriggerInfo {
+        trigger_type: trigger_type.clone(),
+        id: None,
+        settings: json_string_settings(config),
+    }).collect();
+    triggers.extend(locked_app.triggers.into_iter().map(|trigger| TriggerInfo {
+        settings: json_string_settings(&trigger.trigger_config),
+        trigger_type: trigger.trigger_type,
+        id: Some(trigger.id),
+    }));
+
+    AppInfo { name, variables, components, triggers }
+}
+
+fn toml_string_settings(table: &toml::Table) -> Vec<(String, String)> {
+    fn collect(path: String, value: &toml::Value, settings: &mut Vec<(String, String)>) {
+        match value {
+            toml::Value::String(s) => settings.push((path, s.clone())),
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    collect(format!("{path}.{key}"), value, settings);
+                }
+            }
+            toml::Value::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    collect(format!("{path}[{index}]"), value, settings);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut settings = vec![];
+    for (key, value) in table {
+        collect(key.clone(), value, &mut settings);
+    }
+    settings
+}
+
+/// The references for every tag in the repository of a registry reference, as
+/// listed by the registry.
+#[cfg(feature = "native")]
+#[tracing::instrument(level = "info", skip(options))]
+pub async fn registry_tag_references(reference: &str, options: &RegistryOptions) -> anyhow::Result<Vec<(String, String)>> {
+    use anyhow::Context;
+
+    let parsed: oci_distribution::Reference = reference.parse().with_context(|| format!("'{reference}' is not a valid registry reference"))?;
+    let client = registry::client(options)?;
+    let repository = format!("{}/{}", parsed.registry(), parsed.repository());
+    let response = registry::with_retries(options, &format!("Listing tags for {repository}"), || async {
+        Ok(client.list_tags(&parsed, &oci_distribution::secrets::RegistryAuth::Anonymous, None, None).await?)
+    }).await?;
+    Ok(response.tags.into_iter().map(|tag| {
+        let tag_reference = format!("{repository}:{tag}");
+        (tag, tag_reference)
+    }).collect())
+}
+
+/// The directory the OCI loader unpacks a reference into. This persists across runs,
+/// so that repeatedly loading the same reference (as in CI or watch loops) doesn't
+/// unpack it again each time. It is keyed by the reference, so pinning a digest
+/// (`@sha256:...`) makes it content-addressed.
+#[cfg(feature = "native")]
+fn oci_working_dir(reference: &str) -> anyhow::Result<PathBuf> {
+    use anyhow::Context;
+    use sha2::Digest;
+
+    let base = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+    let key = hex::encode(sha2::Sha256::digest(reference.as_bytes()));
+    let dir = base.join("spin-variables").join("oci").join(key);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create working directory {}", dir.display()))?;
+    Ok(dir)
+}
+
+#[cfg(feature = "native")]
+fn json_string_settings(config: &serde_json::Value) -> Vec<(String, String)> {
+    fn collect(path: String, value: &serde_json::Value, settings: &mut Vec<(String, String)>) {
+        match value {
+            serde_json::Value::String(s) => settings.push((path, s.clone())),
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    collect(format!("{path}.{key}"), value, settings);
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    collect(format!("{path}[{index}]"), value, settings);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut settings = vec![];
+    if let Some(object) = config.as_object() {
+        for (key, value) in object {
+            collect(key.clone(), value, &mut settings);
+        }
+    }
+    settings
+}
+
+pub enum AppSource {
+    File(PathBuf),
+    Registry(String),
+    /// An OCI layout directory, given as `oci:<DIR>[:<TAG>]`.
+    OciLayout { path: PathBuf, tag: Option<String> },
+    /// A tar archive of an OCI layout, given as `oci-archive:<FILE>[:<TAG>]`.
+    OciArchive { path: PathBuf, tag: Option<String> },
+}
+
+#[cfg(feature = "native")]
+pub fn infer_app_source(provided: &Option<String>) -> anyhow::Result<AppSource> {
+    match provided {
+        None => Ok(AppSource::File(DEFAULT_MANIFEST_FILE.into())),
+        Some(provided) if provided.starts_with("oci-archive:") => {
+            let (path, tag) = layout_path_and_tag(&provided["oci-archive:".len()..]);
+            Ok(AppSource::OciArchive { path, tag })
+        }
+        Some(provided) if provided.starts_with("oci:") => {
+            let (path, tag) = layout_path_and_tag(&provided["oci:".len()..]);
+            Ok(AppSource::OciLayout { path, tag })
+        }
+        Some(provided) if spin_oci::is_probably_oci_reference(provided) => Ok(AppSource::Registry(provided.clone())),
+        Some(provided) => Ok(AppSource::File(spin_common::paths::resolve_manifest_file_path(provided)?)),
+    }
+}
+
+/// Splits `<PATH>[:<TAG>]`, as used by skopeo for OCI layouts. A path which exists
+/// as given is taken to have no tag, even if it contains a colon.
+#[cfg(feature = "native")]
+fn layout_path_and_tag(text: &str) -> (PathBuf, Option<String>) {
+    match text.rsplit_once(':') {
+        Some((path, tag)) if !Path::new(text).exists() && !tag.contains('/') => (path.into(), Some(tag.to_owned())),
+        _ => (text.into(), None),
+    }
+}
+
+/// Finds the Spin manifests in a directory tree, for working across a workspace
+/// of applications.
+pub fn find_manifests(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    const SKIP_DIRS: &[&str] = &[".git", ".spin", "target", "node_modules"];
+
+    let mut manifests = vec![];
+    let entries = walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !(e.file_type().is_dir() && e.file_name().to_str().is_some_and(|n| SKIP_DIRS.contains(&n))));
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.file_name() == DEFAULT_MANIFEST_FILE {
+            manifests.push(entry.into_path());
+        }
+    }
+    Ok(manifests)
+}
+
+pub fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
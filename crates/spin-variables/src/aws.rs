@@ -0,0 +1,219 @@
+//! Looking up variable values in AWS Secrets Manager or SSM Parameter Store. This
+//! goes through the `aws` command line tool, so that all its credential sources
+//! (profiles, SSO, instance roles and so on) work.
+//!
+//! Each variable is read from the secret or parameter named by the prefix followed
+//! by the variable name, such as `/my-app/api_key` for the prefix `/my-app/`.
+
+use std::sync::Mutex;
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+use tokio::process::Command;
+
+use crate::providers::{ProviderChain, ProviderLabel};
+
+#[derive(Clone, Copy, Debug)]
+pub enum AwsService {
+    SecretsManager,
+    ParameterStore,
+}
+
+impl std::fmt::Display for AwsService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SecretsManager => f.write_str("AWS Secrets Manager"),
+            Self::ParameterStore => f.write_str("AWS SSM Parameter Store"),
+        }
+    }
+}
+
+/// The identity and region to run `aws` with. Unset options fall back to the
+/// usual AWS configuration, such as `AWS_PROFILE` and `AWS_REGION`.
+#[derive(Clone, Debug, Default)]
+pub struct AwsOptions {
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    /// A role to assume, using the profile's credentials, so that values are read
+    /// with the same identity as the deployment.
+    pub role_arn: Option<String>,
+}
+
+impl AwsOptions {
+    fn command(&self, credentials: Option<&RoleCredentials>) -> Command {
+        let mut command = Command::new("aws");
+        match credentials {
+            // The role's credentials only take effect without a profile
+            Some(credentials) => {
+                command
+                    .env_remove("AWS_PROFILE")
+                    .env("AWS_ACCESS_KEY_ID", &credentials.access_key_id)
+                    .env("AWS_SECRET_ACCESS_KEY", &credentials.secret_access_key)
+                    .env("AWS_SESSION_TOKEN", &credentials.session_token);
+            }
+            None => {
+                if let Some(profile) = &self.profile {
+                    command.args(["--profile", profile]);
+                }
+            }
+        }
+        if let Some(region) = &self.region {
+            command.args(["--region", region]);
+        }
+        command.args(["--output", "json"]);
+        command
+    }
+
+    async fn assume_role(&self, role_arn: &str) -> anyhow::Result<RoleCredentials> {
+        let output = self.command(None)
+            .args(["sts", "assume-role", "--role-arn", role_arn, "--role-session-name", "spin-variables", "--query", "Credentials"])
+            .output()
+            .await
+            .context("Failed to run aws. Is the AWS CLI installed and on your PATH?")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to assume role {role_arn}: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        serde_json::from_slice(&output.stdout).with_context(|| format!("Unexpected output from aws assuming role {role_arn}"))
+    }
+}
+
+/// Temporary credentials from assuming a role.
+#[derive(Clone, serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RoleCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+}
+
+impl std::fmt::Debug for RoleCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RoleCredentials").field("access_key_id", &self.access_key_id).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub struct AwsProvider {
+    service: AwsService,
+    prefix: String,
+    options: AwsOptions,
+    /// The assumed role's credentials, once they have been needed.
+    credentials: Mutex<Option<RoleCredentials>>,
+}
+
+impl AwsProvider {
+    pub fn new(service: AwsService, prefix: &str, options: AwsOptions) -> Self {
+        Self {
+            service,
+            prefix: prefix.to_owned(),
+            options,
+            credentials: Mutex::new(None),
+        }
+    }
+
+    async fn credentials(&self) -> anyhow::Result<Option<RoleCredentials>> {
+        let Some(role_arn) = &self.options.role_arn else {
+            return Ok(None);
+        };
+        let cached = self.credentials.lock().unwrap().clone();
+        if cached.is_some() {
+            return Ok(cached);
+        }
+        let credentials = self.options.assume_role(role_arn).await?;
+        *self.credentials.lock().unwrap() = Some(credentials.clone());
+        Ok(Some(credentials))
+    }
+
+    fn command(&self, name: &str, credentials: Option<&RoleCredentials>) -> Command {
+        let mut command = self.options.command(credentials);
+        match self.service {
+            AwsService::SecretsManager => command.args(["secretsmanager", "get-secret-value", "--secret-id", name, "--query", "SecretString"]),
+            AwsService::ParameterStore => command.args(["ssm", "get-parameter", "--name", name, "--with-decryption", "--query", "Parameter.Value"]),
+        };
+        command
+    }
+
+    pub fn add_to(self, chain: &mut ProviderChain) {
+        let label = match self.prefix.as_str() {
+            "" => self.service.to_string(),
+            prefix => format!("{} ({prefix}*)", self.service),
+        };
+        chain.push(ProviderLabel::Named(label), self);
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for AwsProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        let name = format!("{}{}", self.prefix, key.as_str());
+        let credentials = self.credentials().await?;
+        let output = self.command(&name, credentials.as_ref()).output().await.context("Failed to run aws. Is the AWS CLI installed and on your PATH?")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("ResourceNotFoundException") || stderr.contains("ParameterNotFound") {
+                return Ok(None);
+            }
+            anyhow::bail!("Failed to read '{name}' from {}: {}", self.service, stderr.trim());
+        }
+        // A secret stored as binary has no SecretString
+        let value: Option<String> = serde_json::from_slice(&output.stdout).with_context(|| format!("Unexpected output from aws reading '{name}'"))?;
+        value.map(Some).with_context(|| format!("'{name}' in {} is binary, not a string", self.service))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        command.as_std().get_args().map(|a| a.to_string_lossy().into_owned()).collect()
+    }
+
+    fn env(command: &Command) -> Vec<(String, Option<String>)> {
+        command.as_std().get_envs()
+            .map(|(k, v)| (k.to_string_lossy().into_owned(), v.map(|v| v.to_string_lossy().into_owned())))
+            .collect()
+    }
+
+    #[test]
+    fn services_are_read_with_their_own_commands() {
+        let provider = AwsProvider::new(AwsService::SecretsManager, "my-app/", AwsOptions::default());
+        assert_eq!(
+            args(&provider.command("my-app/api_key", None)),
+            ["--output", "json", "secretsmanager", "get-secret-value", "--secret-id", "my-app/api_key", "--query", "SecretString"],
+        );
+
+        let provider = AwsProvider::new(AwsService::ParameterStore, "/my-app/", AwsOptions::default());
+        assert_eq!(
+            args(&provider.command("/my-app/api_key", None)),
+            ["--output", "json", "ssm", "get-parameter", "--name", "/my-app/api_key", "--with-decryption", "--query", "Parameter.Value"],
+        );
+    }
+
+    #[test]
+    fn profile_and_region_are_passed_on() {
+        let options = AwsOptions { profile: Some("staging".to_owned()), region: Some("eu-west-1".to_owned()), role_arn: None };
+        let command = options.command(None);
+        assert_eq!(args(&command), ["--profile", "staging", "--region", "eu-west-1", "--output", "json"]);
+        assert!(env(&command).is_empty());
+    }
+
+    #[test]
+    fn role_credentials_replace_the_profile() {
+        let options = AwsOptions { profile: Some("staging".to_owned()), region: None, role_arn: Some("arn:aws:iam::123456789012:role/deploy".to_owned()) };
+        let credentials = RoleCredentials {
+            access_key_id: "AKIAEXAMPLE".to_owned(),
+            secret_access_key: "s3cret".to_owned(),
+            session_token: "session".to_owned(),
+        };
+        let command = options.command(Some(&credentials));
+        assert_eq!(args(&command), ["--output", "json"]);
+
+        let env = env(&command);
+        assert!(env.contains(&("AWS_PROFILE".to_owned(), None)));
+        assert!(env.contains(&("AWS_ACCESS_KEY_ID".to_owned(), Some("AKIAEXAMPLE".to_owned()))));
+        assert!(env.contains(&("AWS_SECRET_ACCESS_KEY".to_owned(), Some("s3cret".to_owned()))));
+        assert!(env.contains(&("AWS_SESSION_TOKEN".to_owned(), Some("session".to_owned()))));
+        assert!(!format!("{credentials:?}").contains("s3cret"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Looking up variable values in Azure Key Vault, configured the same way as Spin's
+//! `azure_key_vault` variables provider.
+//!
+//! Each variable is read from the secret with the same name. Key Vault secret names
+//! can't contain underscores, so variables whose names do are never found there.
+//!
+//! The provider authenticates with the client credentials in the runtime config. If
+//! there are none, it picks a credential from the environment as the Azure SDKs do:
+//! client credentials or workload identity federation from the `AZURE_*` variables,
+//! or else the managed identity of the Azure resource it is running on.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::providers::{ProviderChain, ProviderLabel};
+
+const KEY_VAULT_API_VERSION: &str = "7.4";
+
+/// The instance metadata endpoint that hands out managed identity tokens on Azure VMs.
+const IMDS_TOKEN_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+
+const JWT_BEARER_ASSERTION: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// The settings of an `azure_key_vault` variables provider in a runtime config file.
+#[derive(Clone, serde::Deserialize)]
+pub struct AzureKeyVaultConfig {
+    pub vault_url: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub tenant_id: Option<String>,
+    #[serde(default)]
+    pub authority_host: AuthorityHost,
+}
+
+/// The Azure cloud to authenticate against.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+pub enum AuthorityHost {
+    #[default]
+    AzurePublicCloud,
+    AzureChina,
+    AzureGermany,
+    AzureGovernment,
+}
+
+impl AuthorityHost {
+    fn url(self) -> &'static str {
+        match self {
+            Self::AzurePublicCloud => "https://login.microsoftonline.com",
+            Self::AzureChina => "https://login.chinacloudapi.cn",
+            Self::AzureGermany => "https://login.microsoftonline.de",
+            Self::AzureGovernment => "https://login.microsoftonline.us",
+        }
+    }
+}
+
+/// How the provider gets a token for Key Vault.
+#[derive(Debug, PartialEq)]
+enum Credential {
+    ClientSecret { tenant_id: String, client_id: String, client_secret: String },
+    /// Workload identity federation, as on AKS, exchanging the token in the file
+    /// for an Entra ID one.
+    WorkloadIdentity { tenant_id: String, client_id: String, token_file: String },
+    /// The managed identity of the Azure resource the tool is running on: the
+    /// user-assigned identity with the client ID if there is one, or else the
+    /// system-assigned identity.
+    ManagedIdentity { client_id: Option<String> },
+}
+
+impl Credential {
+    /// Picks a credential from the runtime config fields, or from the environment if
+    /// the runtime config has none.
+    fn select(config: &AzureKeyVaultConfig, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let from_config = config.tenant_id.is_some() || config.client_id.is_some() || config.client_secret.is_some();
+        let (tenant_id, client_id, client_secret, token_file) = if from_config {
+            (config.tenant_id.clone(), config.client_id.clone(), config.client_secret.clone(), None)
+        } else {
+            (env("AZURE_TENANT_ID"), env("AZURE_CLIENT_ID"), env("AZURE_CLIENT_SECRET"), env("AZURE_FEDERATED_TOKEN_FILE"))
+        };
+        let source = if from_config { "the runtime config" } else { "the environment" };
+
+        match (tenant_id, client_id, client_secret, token_file) {
+            (Some(tenant_id), Some(client_id), Some(client_secret), _) => Ok(Self::ClientSecret { tenant_id, client_id, client_secret }),
+            (Some(tenant_id), Some(client_id), None, Some(token_file)) => Ok(Self::WorkloadIdentity { tenant_id, client_id, token_file }),
+            (_, _, Some(_), _) => anyhow::bail!("Azure Key Vault {} has a client secret in {source} but not both the tenant and client IDs", config.vault_url),
+            (_, _, None, Some(_)) => anyhow::bail!("Azure Key Vault {} has a federated token file but not both AZURE_TENANT_ID and AZURE_CLIENT_ID", config.vault_url),
+            (_, client_id, None, None) => Ok(Self::ManagedIdentity { client_id }),
+        }
+    }
+}
+
+pub struct AzureKeyVaultProvider {
+    client: reqwest::Client,
+    vault_url: String,
+    authority_host: AuthorityHost,
+    credential: Credential,
+    token: Mutex<Option<String>>,
+}
+
+impl AzureKeyVaultProvider {
+    pub fn new(config: AzureKeyVaultConfig) -> anyhow::Result<Self> {
+        let credential = Credential::select(&config, |name| std::env::var(name).ok())?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            vault_url: config.vault_url.trim_end_matches('/').to_owned(),
+            authority_host: config.authority_host,
+            credential,
+            token: Mutex::new(None),
+        })
+    }
+
+    pub fn add_to(self, chain: &mut ProviderChain) {
+        chain.push(ProviderLabel::Named(format!("Azure Key Vault {}", self.vault_url)), self);
+    }
+
+    /// The resource that tokens are requested for, which depends on the cloud the
+    /// vault is in: `https://vault.azure.net` for `my-vault.vault.azure.net`.
+    fn resource(&self) -> anyhow::Result<String> {
+        let url = url::Url::parse(&self.vault_url).with_context(|| format!("Invalid vault_url '{}'", self.vault_url))?;
+        let host = url.host_str().unwrap_or_default();
+        let Some((_, domain)) = host.split_once('.') else {
+            anyhow::bail!("Invalid vault_url '{}': expected a host such as my-vault.vault.azure.net", self.vault_url);
+        };
+        Ok(format!("https://{domain}"))
+    }
+
+    async fn token(&self) -> anyhow::Result<String> {
+        let cached = self.token.lock().unwrap().clone();
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+        let token = self.fetch_token().await.with_context(|| format!("Failed to authenticate to Azure Key Vault {}", self.vault_url))?;
+        *self.token.lock().unwrap() = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn fetch_token(&self) -> anyhow::Result<String> {
+        let resource = self.resource()?;
+        let scope = format!("{resource}/.default");
+        let request = match &self.credential {
+            Credential::ClientSecret { tenant_id, client_id, client_secret } => self.client
+                .post(format!("{}/{tenant_id}/oauth2/v2.0/token", self.authority_host.url()))
+                .form(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", client_id.as_str()),
+                    ("client_secret", client_secret.as_str()),
+                    ("scope", scope.as_str()),
+                ]),
+            Credential::WorkloadIdentity { tenant_id, client_id, token_file } => {
+                let assertion = std::fs::read_to_string(token_file).with_context(|| format!("Failed to read federated token file {token_file}"))?;
+                self.client
+                    .post(format!("{}/{tenant_id}/oauth2/v2.0/token", self.authority_host.url()))
+                    .form(&[
+                        ("grant_type", "client_credentials"),
+                        ("client_id", client_id.as_str()),
+                        ("client_assertion_type", JWT_BEARER_ASSERTION),
+                        ("client_assertion", assertion.trim()),
+                        ("scope", scope.as_str()),
+                    ])
+            }
+            Credential::ManagedIdentity { client_id } => {
+                let mut query = vec![("resource", resource.as_str())];
+                query.extend(client_id.as_deref().map(|id| ("client_id", id)));
+                // App Service, Functions and Container Apps have their own endpoint
+                match (std::env::var("IDENTITY_ENDPOINT"), std::env::var("IDENTITY_HEADER")) {
+                    (Ok(endpoint), Ok(header)) => self.client
+                        .get(endpoint)
+                        .query(&[("api-version", "2019-08-01")])
+                        .query(&query)
+                        .header("X-IDENTITY-HEADER", header),
+                    _ => self.client
+                        .get(IMDS_TOKEN_ENDPOINT)
+                        .query(&[("api-version", "2018-02-01")])
+                        .query(&query)
+                        .header("Metadata", "true")
+                        // Off Azure nothing answers, so don't wait for the usual connect timeout
+                        .timeout(Duration::from_secs(5)),
+                }
+            }
+        };
+        let text = request.send().await?.error_for_status()?.text().await?;
+        let response: TokenResponse = serde_json::from_str(&text).context("Unexpected token response")?;
+        Ok(response.access_token)
+    }
+}
+
+impl std::fmt::Debug for AzureKeyVaultProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AzureKeyVaultProvider").field("vault_url", &self.vault_url).finish_non_exhaustive()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct SecretBundle {
+    value: String,
+}
+
+#[async_trait::async_trait]
+impl Provider for AzureKeyVaultProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        if key.as_str().contains('_') {
+            return Ok(None);
+        }
+        let token = self.token().await?;
+        let url = format!("{}/secrets/{}?api-version={KEY_VAULT_API_VERSION}", self.vault_url, key.as_str());
+        let response = self.client.get(url).bearer_auth(token).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let text = response.error_for_status()?.text().await?;
+        let secret: SecretBundle = serde_json::from_str(&text).context("Unexpected response from Key Vault")?;
+        Ok(Some(secret.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> AzureKeyVaultConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn reads_spin_runtime_config_shape() {
+        let provider = AzureKeyVaultProvider::new(config(r#"
+            type = "azure_key_vault"
+            vault_url = "https://my-vault.vault.azure.cn/"
+            client_id = "id"
+            client_secret = "secret"
+            tenant_id = "tenant"
+            authority_host = "AzureChina"
+        "#)).unwrap();
+        assert!(matches!(provider.credential, Credential::ClientSecret { .. }));
+        assert!(matches!(provider.authority_host, AuthorityHost::AzureChina));
+        assert_eq!(provider.resource().unwrap(), "https://vault.azure.cn");
+        assert!(!format!("{provider:?}").contains("secret"));
+    }
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn selects_credentials_from_the_environment() {
+        let no_credentials = config(r#"vault_url = "https://my-vault.vault.azure.net""#);
+        assert!(matches!(AzureKeyVaultProvider::new(no_credentials.clone()).unwrap().authority_host, AuthorityHost::AzurePublicCloud));
+
+        let select = |vars: &[(&str, &str)]| Credential::select(&no_credentials, env(vars));
+        assert_eq!(select(&[]).unwrap(), Credential::ManagedIdentity { client_id: None });
+        assert_eq!(select(&[("AZURE_CLIENT_ID", "id")]).unwrap(), Credential::ManagedIdentity { client_id: Some("id".to_owned()) });
+        assert!(matches!(
+            select(&[("AZURE_TENANT_ID", "tenant"), ("AZURE_CLIENT_ID", "id"), ("AZURE_FEDERATED_TOKEN_FILE", "/var/run/token")]).unwrap(),
+            Credential::WorkloadIdentity { token_file, .. } if token_file == "/var/run/token"
+        ));
+        assert!(matches!(
+            select(&[("AZURE_TENANT_ID", "tenant"), ("AZURE_CLIENT_ID", "id"), ("AZURE_CLIENT_SECRET", "secret")]).unwrap(),
+            Credential::ClientSecret { .. }
+        ));
+        assert!(select(&[("AZURE_CLIENT_SECRET", "secret")]).is_err());
+        assert!(select(&[("AZURE_FEDERATED_TOKEN_FILE", "/var/run/token")]).is_err());
+    }
+
+    #[test]
+    fn runtime_config_credentials_win() {
+        let user_assigned = config(r#"
+            vault_url = "https://my-vault.vault.azure.net"
+            client_id = "id"
+        "#);
+        let environment = [("AZURE_TENANT_ID", "tenant"), ("AZURE_CLIENT_ID", "other"), ("AZURE_CLIENT_SECRET", "secret")];
+        assert_eq!(Credential::select(&user_assigned, env(&environment)).unwrap(), Credential::ManagedIdentity { client_id: Some("id".to_owned()) });
+
+        let partial = config(r#"
+            vault_url = "https://my-vault.vault.azure.net"
+            client_secret = "secret"
+        "#);
+        assert!(Credential::select(&partial, env(&environment)).is_err());
+    }
+}
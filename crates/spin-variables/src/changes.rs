@@ -0,0 +1,214 @@
+use crate::app::VariableInfo;
+use crate::format::{self, FormatContext};
+
+/// A difference between two versions of an application's variables.
+pub enum Change<'a> {
+    Added(&'a VariableInfo),
+    Removed(&'a VariableInfo),
+    Modified {
+        before: &'a VariableInfo,
+        after: &'a VariableInfo,
+    },
+}
+
+impl Change<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Added(v) | Self::Removed(v) => &v.name,
+            Self::Modified { after, .. } => &after.name,
+        }
+    }
+}
+
+impl std::fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(variable) => write!(f, "+ {}: {}", variable.name, describe(variable)),
+            Self::Removed(variable) => write!(f, "- {}", variable.name),
+            Self::Modified { before, after } => write!(f, "~ {}: {} -> {}", after.name, describe(before), describe(after)),
+        }
+    }
+}
+
+/// A one-line summary of a variable's declaration and value. Secret values are
+/// not shown.
+fn describe(variable: &VariableInfo) -> String {
+    let mut description = if variable.required { "required".to_owned() } else { "optional".to_owned() };
+    if variable.secret {
+        description.push_str(", secret");
+    }
+    match &variable.value {
+        Some(_) if variable.secret => description.push_str(", value set"),
+        Some(resolved) => description.push_str(&format!(", value '{}' from {}", resolved.value, resolved.source)),
+        None => description.push_str(", no value"),
+    }
+    description
+}
+
+/// The differences between two sets of variables, in the order of `after` with
+/// removals last.
+pub fn changes<'a>(before: &'a [VariableInfo], after: &'a [VariableInfo]) -> Vec<Change<'a>> {
+    let mut changes = vec![];
+    for variable in after {
+        match before.iter().find(|v| v.name == variable.name) {
+            None => changes.push(Change::Added(variable)),
+            Some(previous) if previous != variable => changes.push(Change::Modified { before: previous, after: variable }),
+            Some(_) => (),
+        }
+    }
+    changes.extend(before.iter().filter(|v| !after.iter().any(|a| a.name == v.name)).map(Change::Removed));
+    changes
+}
+
+/// The differences as an RFC 6902 JSON Patch that turns the `-o json` document of
+/// `before` into that of `after`, with entries formatted for the context. Paths are
+/// indexes into the `variables` array: removals come first, from the end of the
+/// array so that earlier indexes stay valid, then replacements, then additions at
+/// the end.
+pub fn json_patch(before: &[VariableInfo], after: &[VariableInfo], context: &FormatContext) -> anyhow::Result<serde_json::Value> {
+    let mut operations = vec![];
+    let mut remaining: Vec<&VariableInfo> = before.iter().collect();
+    for (index, variable) in before.iter().enumerate().rev() {
+        if !after.iter().any(|v| v.name == variable.name) {
+            operations.push(serde_json::json!({ "op": "remove", "path": format!("/variables/{index}") }));
+            remaining.remove(index);
+        }
+    }
+    for variable in after {
+        match remaining.iter().position(|v| v.name == variable.name) {
+            Some(index) if remaining[index] != variable => {
+                operations.push(serde_json::json!({ "op": "replace", "path": format!("/variables/{index}"), "value": format::json_entry(variable, context)? }));
+            }
+            Some(_) => (),
+            None => operations.push(serde_json::json!({ "op": "add", "path": "/variables/-", "value": format::json_entry(variable, context)? })),
+        }
+    }
+    Ok(serde_json::Value::Array(operations))
+}
+
+/// The changes as a JSON document listing added, removed and modified variables.
+pub fn json_diff(changes: &[Change]) -> serde_json::Value {
+    let mut added = vec![];
+    let mut removed = vec![];
+    let mut modified = vec![];
+    for change in changes {
+        match change {
+            Change::Added(variable) => added.push(serde_json::json!(variable)),
+            Change::Removed(variable) => removed.push(serde_json::json!(variable)),
+            Change::Modified { before, after } => modified.push(serde_json::json!({ "name": after.name, "before": before, "after": after })),
+        }
+    }
+    serde_json::json!({ "added": added, "removed": removed, "modified": modified })
+}
+
+/// A one-line summary of a variable's declaration, such as `required, secret` or
+/// `optional, default 'info'`.
+pub fn describe_declaration(variable: &VariableInfo) -> String {
+    let mut description = if variable.required { "required".to_owned() } else { "optional".to_owned() };
+    if variable.secret {
+        description.push_str(", secret");
+    }
+    if let Some(default) = &variable.default_value {
+        description.push_str(&format!(", default '{default}'"));
+    }
+    description
+}
+
+/// How a variable's declaration differs between two versions, such as `now secret`
+/// or `default 'info' -> 'warn'`. Values are ignored, so this is empty if only the
+/// value changed.
+pub fn declaration_changes(before: &VariableInfo, after: &VariableInfo) -> Vec<String> {
+    let mut changes = vec![];
+    if before.required != after.required {
+        changes.push(if after.required { "now required" } else { "now optional" }.to_owned());
+    }
+    if before.secret != after.secret {
+        changes.push(if after.secret { "now secret" } else { "no longer secret" }.to_owned());
+    }
+    match (&before.default_value, &after.default_value) {
+        (None, Some(default)) => changes.push(format!("default '{default}' added")),
+        (Some(default), None) => changes.push(format!("default '{default}' removed")),
+        (Some(old), Some(new)) if old != new => changes.push(format!("default '{old}' -> '{new}'")),
+        _ => {}
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::OutputFormatter;
+    use crate::values::{ResolvedValue, ValueSource};
+
+    fn variable(name: &str, required: bool, secret: bool) -> VariableInfo {
+        VariableInfo {
+            name: name.to_owned(),
+            default_value: (!required).then(|| "default".to_owned()),
+            required,
+            secret,
+            value: None,
+        }
+    }
+
+    fn json(variables: &[VariableInfo]) -> serde_json::Value {
+        serde_json::from_str(&format::JsonFormatter.format(variables, &FormatContext::default()).unwrap()).unwrap()
+    }
+
+    /// Applies the add, remove and replace operations of a JSON Patch to paths
+    /// within the `variables` array.
+    fn apply(document: &mut serde_json::Value, patch: &serde_json::Value) {
+        let variables = document["variables"].as_array_mut().unwrap();
+        for operation in patch.as_array().unwrap() {
+            let index = operation["path"].as_str().unwrap().strip_prefix("/variables/").unwrap();
+            match operation["op"].as_str().unwrap() {
+                "add" if index == "-" => variables.push(operation["value"].clone()),
+                "remove" => {
+                    variables.remove(index.parse().unwrap());
+                }
+                "replace" => variables[index.parse::<usize>().unwrap()] = operation["value"].clone(),
+                op => panic!("unexpected operation {op}"),
+            }
+        }
+    }
+
+    #[test]
+    fn json_patch_applies_to_json_output() {
+        let before = vec![variable("a", true, false), variable("b", true, false), variable("c", false, false), variable("d", true, false)];
+        let after = vec![variable("a", true, false), variable("c", false, true), variable("e", true, false)];
+
+        let patch = json_patch(&before, &after, &FormatContext::default()).unwrap();
+        let mut document = json(&before);
+        apply(&mut document, &patch);
+        assert_eq!(document, json(&after));
+    }
+
+    #[test]
+    fn json_patch_is_empty_without_changes() {
+        let variables = vec![variable("a", true, false)];
+        assert_eq!(json_patch(&variables, &variables, &FormatContext::default()).unwrap(), serde_json::json!([]));
+    }
+
+    #[test]
+    fn changes_list_removals_last() {
+        let before = vec![variable("old", true, false), variable("kept", true, false)];
+        let after = vec![variable("kept", false, false), variable("new", true, false)];
+        let names: Vec<_> = changes(&before, &after).iter().map(|c| c.to_string()).collect();
+        assert_eq!(names, ["~ kept: required, no value -> optional, no value", "+ new: required, no value", "- old"]);
+    }
+
+    #[test]
+    fn secret_values_are_not_described() {
+        let mut secret = variable("token", true, true);
+        secret.value = Some(ResolvedValue { value: "hunter2".to_owned(), source: ValueSource::Set });
+        assert!(!Change::Added(&secret).to_string().contains("hunter2"));
+    }
+
+    #[test]
+    fn declaration_changes_ignore_values() {
+        let before = variable("level", false, false);
+        let mut after = variable("level", false, true);
+        after.default_value = Some("warn".to_owned());
+        after.value = Some(ResolvedValue { value: "x".to_owned(), source: ValueSource::Set });
+        assert_eq!(declaration_changes(&before, &after), ["now secret", "default 'default' -> 'warn'"]);
+    }
+}
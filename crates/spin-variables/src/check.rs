@@ -0,0 +1,89 @@
+//! Checking that an application's variables will all be satisfied at runtime.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{setting, Setting};
+use crate::schema::Schema;
+use crate::values;
+
+/// The outcome of checking one variable.
+pub struct CheckResult<'a> {
+    pub variable: &'a VariableInfo,
+    /// Why the variable won't be satisfied as expected, or `None` if it will.
+    pub problem: Option<String>,
+    /// Something worth knowing that doesn't fail the check.
+    pub warning: Option<String>,
+}
+
+impl CheckResult<'_> {
+    pub fn passed(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+/// Checks each variable, in order. A variable fails if it is required (by the
+/// manifest, or by the schema given the other values) but has no value, if it maps
+/// to the same environment variable as an earlier one (since then they can't be
+/// given different values through the environment), or if its value breaks the
+/// constraints in the schema. Deprecated variables that are given values get a
+/// warning, as do values with [suspicious patterns](values::suspicious_patterns).
+pub fn check<'a>(variables: &'a [VariableInfo], env_prefix: &str, schema: &Schema) -> Vec<CheckResult<'a>> {
+    variables.iter().enumerate().map(|(index, variable)| {
+        let env_var_name = env::env_var_name(env_prefix, &variable.name);
+        let collision = variables[..index].iter().find(|other| env::env_var_name(env_prefix, &other.name) == env_var_name);
+        let required_condition = schema.get(&variable.name).and_then(|c| c.required_condition(variables));
+        let problem = if variable.required && variable.value.is_none() {
+            Some(format!("'{}' is required but has no value (set {env_var_name} or pass --set)", variable.name))
+        } else if let (Some(condition), None) = (&required_condition, &variable.value) {
+            Some(format!("'{}' is required because {condition}, but has no value (set {env_var_name} or pass --set)", variable.name))
+        } else if let Some(other) = collision {
+            Some(format!("'{}' maps to the same environment variable as '{}' ({env_var_name})", variable.name, other.name))
+        } else {
+            let constraints = schema.get(&variable.name);
+            let value = variable.value.as_ref();
+            constraints.zip(value).and_then(|(c, v)| c.validate(&v.value).err()).map(|e| format!("'{}' has an invalid value: {e}", variable.name))
+        };
+        let deprecation = schema.deprecation(&variable.name).filter(|_| matches!(setting(variable), Setting::Value(_))).map(|hint| match hint {
+            "" => format!("'{}' is deprecated, but has a value set", variable.name),
+            hint => format!("'{}' is deprecated ({hint}), but has a value set", variable.name),
+        });
+        let warning = deprecation.or_else(|| suspicious_value(variable));
+        CheckResult { variable, problem, warning }
+    }).collect()
+}
+
+/// A warning about a value that looks like a mistake, or `None` if it looks fine.
+/// Manifest defaults aren't checked, since they are written deliberately.
+pub fn suspicious_value(variable: &VariableInfo) -> Option<String> {
+    let Setting::Value(value) = setting(variable) else {
+        return None;
+    };
+    let patterns = values::suspicious_patterns(value);
+    if patterns.is_empty() {
+        return None;
+    }
+    Some(format!("The value of '{}' ({} characters) has {}", variable.name, value.chars().count(), patterns.join(" and ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::{ResolvedValue, ValueSource};
+
+    #[test]
+    fn failing_secrets_are_not_revealed() {
+        let variables = vec![VariableInfo {
+            name: "api_key".to_owned(),
+            default_value: None,
+            required: true,
+            secret: true,
+            value: Some(ResolvedValue { value: "sk-live-1234".to_owned(), source: ValueSource::Set }),
+        }];
+        let schema: Schema = toml::from_str("[variables.api_key]\nmin_length = 32").unwrap();
+
+        let results = check(&variables, env::DEFAULT_PREFIX, &schema);
+        let problem = results[0].problem.as_deref().unwrap();
+        assert!(problem.starts_with("'api_key' has an invalid value"));
+        assert!(!problem.contains("sk-live-1234"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Comparing an application's variables with what a SpinKube cluster provides, and
+//! reading the values it provides.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::app::VariableInfo;
+
+/// How a SpinApp supplies a variable, from an entry in its `spec.variables`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Provision {
+    Value,
+    SecretKey { name: String, key: String },
+    ConfigMapKey { name: String, key: String },
+    /// A `valueFrom` we don't know how to follow.
+    Other,
+}
+
+/// The variables a SpinApp resource (as JSON from `kubectl get -o json`) provides.
+pub fn spinapp_variables(spinapp: &serde_json::Value) -> Vec<(String, Provision)> {
+    spinapp_entries(spinapp).filter_map(provision).collect()
+}
+
+fn spinapp_entries(spinapp: &serde_json::Value) -> impl Iterator<Item = &serde_json::Value> {
+    spinapp.pointer("/spec/variables").and_then(|v| v.as_array()).into_iter().flatten()
+}
+
+/// The variable name and provision of an entry in a SpinApp's `spec.variables`.
+fn provision(entry: &serde_json::Value) -> Option<(String, Provision)> {
+    let name = entry.get("name")?.as_str()?.to_owned();
+    let key_ref = |kind: &str| {
+        let key_ref = entry.pointer(&format!("/valueFrom/{kind}"))?;
+        Some((key_ref.get("name")?.as_str()?.to_owned(), key_ref.get("key")?.as_str()?.to_owned()))
+    };
+    let provision = if entry.get("value").is_some() {
+        Provision::Value
+    } else if let Some((name, key)) = key_ref("secretKeyRef") {
+        Provision::SecretKey { name, key }
+    } else if let Some((name, key)) = key_ref("configMapKeyRef") {
+        Provision::ConfigMapKey { name, key }
+    } else {
+        Provision::Other
+    };
+    Some((name, provision))
+}
+
+/// Whether a Secret or ConfigMap (as JSON) has a key, in `data` or `stringData`.
+pub fn has_key(resource: &serde_json::Value, key: &str) -> bool {
+    ["data", "stringData", "binaryData"].iter().any(|field| resource.get(field).and_then(|d| d.get(key)).is_some())
+}
+
+/// A way in which the cluster doesn't match what the application needs.
+pub enum Gap<'a> {
+    /// The application requires the variable, but the cluster doesn't provide it.
+    Missing(&'a VariableInfo),
+    /// The cluster refers to a Secret or ConfigMap key that doesn't exist.
+    BrokenReference { name: &'a str, kind: &'static str, resource: &'a str, key: &'a str },
+    /// The cluster provides a variable the application doesn't declare.
+    Undeclared(&'a str),
+}
+
+impl Gap<'_> {
+    /// Whether the gap would stop the application working.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::Undeclared(_))
+    }
+}
+
+impl std::fmt::Display for Gap<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(variable) => write!(f, "'{}' is required, but the cluster doesn't provide it", variable.name),
+            Self::BrokenReference { name, kind, resource, key } => write!(f, "'{name}' refers to key '{key}' of {kind} '{resource}', which doesn't exist"),
+            Self::Undeclared(name) => write!(f, "'{name}' is provided by the cluster, but not declared by the application"),
+        }
+    }
+}
+
+/// Compares the application's variables with the cluster's. `key_exists` reports
+/// whether a Secret or ConfigMap reference can be resolved.
+pub fn gaps<'a>(
+    variables: &'a [VariableInfo],
+    provided: &'a [(String, Provision)],
+    key_exists: impl Fn(&Provision) -> bool,
+) -> Vec<Gap<'a>> {
+    let mut gaps = vec![];
+    for variable in variables {
+        if variable.required && !provided.iter().any(|(name, _)| name == &variable.name) {
+            gaps.push(Gap::Missing(variable));
+        }
+    }
+    for (name, provision) in provided {
+        if !variables.iter().any(|v| &v.name == name) {
+            gaps.push(Gap::Undeclared(name));
+            continue;
+        }
+        let (kind, resource, key) = match provision {
+            Provision::SecretKey { name: resource, key } => ("Secret", resource, key),
+            Provision::ConfigMapKey { name: resource, key } => ("ConfigMap", resource, key),
+            Provision::Value | Provision::Other => continue,
+        };
+        if !key_exists(provision) {
+            gaps.push(Gap::BrokenReference { name, kind, resource, key });
+        }
+    }
+    gaps
+}
+
+/// The values in a Secret or ConfigMap (as JSON), by key. Secret `data` is decoded
+/// from base64; values that aren't UTF-8 text are left out.
+pub fn resource_values(resource: &serde_json::Value) -> BTreeMap<String, String> {
+    use base64::Engine;
+
+    let entries = |field: &str| resource.get(field).and_then(|d| d.as_object()).into_iter().flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+        .collect::<Vec<_>>();
+    let is_secret = resource.get("kind").and_then(|k| k.as_str()) == Some("Secret");
+
+    let mut values = BTreeMap::new();
+    for (key, value) in entries("data") {
+        let value = match is_secret {
+            true => base64::engine::general_purpose::STANDARD.decode(&value).ok().and_then(|bytes| String::from_utf8(bytes).ok()),
+            false => Some(value),
+        };
+        if let Some(value) = value {
+            values.insert(key, value);
+        }
+    }
+    values.extend(entries("stringData"));
+    values
+}
+
+/// The values a SpinApp (as JSON) gives its variables, following Secret and ConfigMap
+/// references with `get_resource(kind, name)`, where kind is `secret` or `configmap`.
+/// References that can't be followed are left out.
+pub fn spinapp_values(
+    spinapp: &serde_json::Value,
+    mut get_resource: impl FnMut(&str, &str) -> anyhow::Result<Option<serde_json::Value>>,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut values = BTreeMap::new();
+    for entry in spinapp_entries(spinapp) {
+        let Some((name, provision)) = provision(entry) else {
+            continue;
+        };
+        let (kind, resource, key) = match provision {
+            Provision::Value => {
+                if let Some(value) = entry.get("value").and_then(|v| v.as_str()) {
+                    values.insert(name, value.to_owned());
+                }
+                continue;
+            }
+            Provision::SecretKey { name: resource, key } => ("secret", resource, key),
+            Provision::ConfigMapKey { name: resource, key } => ("configmap", resource, key),
+            Provision::Other => continue,
+        };
+        let resource = get_resource(kind, &resource).with_context(|| format!("Failed to get {kind} '{resource}' for '{name}'"))?;
+        if let Some(value) = resource.and_then(|r| resource_values(&r).remove(&key)) {
+            values.insert(name, value);
+        }
+    }
+    Ok(values)
+}
+
+/// Looks up values read from a cluster, such as with [`resource_values`] or
+/// [`spinapp_values`].
+#[derive(Debug)]
+pub struct KubeProvider {
+    values: BTreeMap<String, String>,
+}
+
+impl KubeProvider {
+    pub fn new(values: BTreeMap<String, String>) -> Self {
+        Self { values }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for KubeProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        Ok(self.values.get(key.as_str()).cloned())
+    }
+}
@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use crate::app::{AppInfo, VariableInfo};
+
+/// Variables declared by more than one application whose declarations differ, keyed
+/// by name, with each application's declaration.
+pub fn inconsistencies(apps: &[(String, AppInfo)]) -> BTreeMap<&str, Vec<(&String, &VariableInfo)>> {
+    let mut by_name: BTreeMap<&str, Vec<(&String, &VariableInfo)>> = BTreeMap::new();
+    for (source, app) in apps {
+        for variable in &app.variables {
+            by_name.entry(variable.name.as_str()).or_default().push((source, variable));
+        }
+    }
+
+    by_name.retain(|_, declarations| {
+        let (_, first) = declarations[0];
+        declarations.len() > 1 && declarations.iter().any(|(_, other)| !same_semantics(first, other))
+    });
+    by_name
+}
+
+fn same_semantics(a: &VariableInfo, b: &VariableInfo) -> bool {
+    a.required == b.required && a.secret == b.secret && a.default_value == b.default_value
+}
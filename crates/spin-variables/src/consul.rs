@@ -0,0 +1,86 @@
+//! Looking up variable values in Consul's key/value store, for services whose
+//! configuration already lives there.
+//!
+//! Each variable is read from the key made of the path prefix and the variable name,
+//! such as `apps/my-app/api_key` for the prefix `apps/my-app/`.
+
+use spin_expressions::{Key, Provider};
+
+/// The address of the local Consul agent, used if none is given.
+pub const DEFAULT_ADDRESS: &str = "http://127.0.0.1:8500";
+
+pub struct ConsulProvider {
+    client: reqwest::Client,
+    address: String,
+    token: Option<String>,
+    prefix: String,
+}
+
+impl ConsulProvider {
+    pub fn new(address: &str, token: Option<String>, prefix: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            address: address.trim_end_matches('/').to_owned(),
+            token,
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    fn key_url(&self, name: &str) -> String {
+        format!("{}/v1/kv/{}{name}?raw", self.address, self.prefix.trim_start_matches('/'))
+    }
+
+    fn request(&self, name: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(self.key_url(name));
+        match &self.token {
+            Some(token) => request.header("X-Consul-Token", token),
+            None => request,
+        }
+    }
+}
+
+impl std::fmt::Debug for ConsulProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsulProvider")
+            .field("address", &self.address)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for ConsulProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        let response = self.request(key.as_str()).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        Ok(Some(response.text().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_under_the_prefix() {
+        let provider = ConsulProvider::new("http://consul.internal:8500/", None, "/apps/my-app/");
+        assert_eq!(provider.key_url("api_key"), "http://consul.internal:8500/v1/kv/apps/my-app/api_key?raw");
+        let provider = ConsulProvider::new(DEFAULT_ADDRESS, None, "");
+        assert_eq!(provider.key_url("api_key"), "http://127.0.0.1:8500/v1/kv/api_key?raw");
+    }
+
+    #[test]
+    fn the_token_is_sent_but_not_shown() {
+        let provider = ConsulProvider::new(DEFAULT_ADDRESS, Some("s3cret".to_owned()), "");
+        let request = provider.request("api_key").build().unwrap();
+        assert_eq!(request.headers()["X-Consul-Token"], "s3cret");
+        assert!(!format!("{provider:?}").contains("s3cret"));
+
+        let provider = ConsulProvider::new(DEFAULT_ADDRESS, None, "");
+        let request = provider.request("api_key").build().unwrap();
+        assert!(request.headers().get("X-Consul-Token").is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Changing the variables declared in a spin.toml file in place, keeping its
+//! comments and layout.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// A new variable to declare.
+pub struct Declaration {
+    pub default: Option<String>,
+    pub required: bool,
+    pub secret: bool,
+}
+
+pub struct ManifestEditor {
+    path: PathBuf,
+    doc: toml_edit::DocumentMut,
+}
+
+impl ManifestEditor {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let doc = text.parse().with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            doc,
+        })
+    }
+
+    /// Writes the changes back, as long as the manifest is still one Spin accepts.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let text = self.doc.to_string();
+        crate::app::app_from_toml_str(&text).context("The changes would make the manifest invalid")?;
+        std::fs::write(&self.path, text).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Sets a declared variable's default. If the variable was required, it becomes
+    /// optional, since Spin doesn't allow a required variable to have a default.
+    /// Returns whether it was required.
+    pub fn set_default(&mut self, name: &str, value: &str) -> anyhow::Result<bool> {
+        let variable = self.variable_mut(name)?;
+        let was_required = variable.remove("required").is_some_and(|r| r.as_bool() == Some(true));
+        variable.insert("default", toml_edit::value(value));
+        Ok(was_required)
+    }
+
+    /// Changes whether a declared variable is secret or required, leaving flags given
+    /// as `None` alone. A variable with a default can't be made required, since Spin
+    /// doesn't allow that.
+    pub fn set_flags(&mut self, name: &str, secret: Option<bool>, required: Option<bool>) -> anyhow::Result<()> {
+        let variable = self.variable_mut(name)?;
+        if required == Some(true) && variable.contains_key("default") {
+            anyhow::bail!("'{name}' has a default, so it can't be required. Remove the default from the manifest first");
+        }
+        set_flag(variable, "secret", secret);
+        set_flag(variable, "required", required);
+        Ok(())
+    }
+
+    /// Whether the manifest declares any variables.
+    pub fn has_variables(&self) -> bool {
+        self.doc.get("variables").and_then(|v| v.as_table_like()).is_some_and(|v| !v.is_empty())
+    }
+
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.doc.get("variables").and_then(|v| v.as_table_like()).is_some_and(|v| v.contains_key(name))
+    }
+
+    /// Adds a variable to `[variables]`, creating the table if there isn't one.
+    pub fn declare(&mut self, name: &str, declaration: &Declaration) -> anyhow::Result<()> {
+        if self.is_declared(name) {
+            anyhow::bail!("Variable '{name}' is already declared");
+        }
+        crate::app::validate_variable_name(name).map_err(|e| anyhow::anyhow!("'{name}' is not a valid variable name: {e}"))?;
+
+        let mut variable = toml_edit::InlineTable::new();
+        if let Some(default) = &declaration.default {
+            variable.insert("default", default.into());
+        }
+        if declaration.required {
+            variable.insert("required", true.into());
+        }
+        if declaration.secret {
+            variable.insert("secret", true.into());
+        }
+
+        let variables = self.doc.entry("variables").or_insert_with(toml_edit::table);
+        let variables = variables.as_table_like_mut().context("[variables] is not a table")?;
+        variables.insert(name, toml_edit::value(variable));
+        Ok(())
+    }
+
+    /// Sets a component variable to a template, adding the component's `variables`
+    /// table (`config` in version 1 manifests) if it doesn't have one.
+    pub fn set_component_variable(&mut self, component_id: &str, name: &str, template: &str) -> anyhow::Result<()> {
+        crate::app::validate_variable_name(name).map_err(|e| anyhow::anyhow!("'{name}' is not a valid variable name: {e}"))?;
+        let path = self.path.display().to_string();
+        let (component, variables_key) = match self.doc.get_mut("component") {
+            Some(toml_edit::Item::ArrayOfTables(components)) => {
+                let component = components.iter_mut().find(|c| c.get("id").and_then(|id| id.as_str()) == Some(component_id));
+                (component.map(|c| c as &mut dyn toml_edit::TableLike), "config")
+            }
+            Some(components) => (components.as_table_like_mut().and_then(|c| c.get_mut(component_id)).and_then(|c| c.as_table_like_mut()), "variables"),
+            None => (None, "variables"),
+        };
+        let component = component.with_context(|| format!("Component '{component_id}' is not in {path}"))?;
+        let variables = component.entry(variables_key).or_insert(toml_edit::table());
+        let variables = variables.as_table_like_mut().with_context(|| format!("Component '{component_id}' {variables_key} is not a table"))?;
+        variables.insert(name, toml_edit::value(template));
+        Ok(())
+    }
+
+    /// A variable's declaration in `[variables]`, whether it is written as an inline
+    /// table or as a `[variables.<name>]` table.
+    fn variable_mut(&mut self, name: &str) -> anyhow::Result<&mut dyn toml_edit::TableLike> {
+        let path = self.path.display().to_string();
+        self.doc.get_mut("variables")
+            .and_then(|v| v.as_table_like_mut())
+            .and_then(|v| v.get_mut(name))
+            .and_then(|v| v.as_table_like_mut())
+            .with_context(|| format!("Variable '{name}' is not declared in {path}"))
+    }
+}
+
+/// Sets a flag to true, or removes it for false since that is Spin's default.
+fn set_flag(variable: &mut dyn toml_edit::TableLike, key: &str, value: Option<bool>) {
+    match value {
+        Some(true) => {
+            variable.insert(key, toml_edit::value(true));
+        }
+        Some(false) => {
+            variable.remove(key);
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,95 @@
+//! Encrypted values files: values encrypted with [age](https://age-encryption.org),
+//! using the `age` command line tool, and whole files encrypted with
+//! [SOPS](https://getsops.io), using the `sops` command line tool.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Context;
+
+const AGE_ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// How to encrypt values, parsed from a `--encrypt` argument such as `age:age1...`.
+#[derive(Clone, Debug)]
+pub enum Encryption {
+    Age { recipient: String },
+}
+
+impl std::str::FromStr for Encryption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("age", recipient)) if !recipient.is_empty() => Ok(Self::Age { recipient: recipient.to_owned() }),
+            _ => Err(format!("expected age:<RECIPIENT> but got '{s}'")),
+        }
+    }
+}
+
+impl Encryption {
+    pub fn encrypt(&self, value: &str) -> anyhow::Result<String> {
+        match self {
+            Self::Age { recipient } => run_age(&["--encrypt", "--armor", "--recipient", recipient], value),
+        }
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.trim_start().starts_with(AGE_ARMOR_HEADER)
+}
+
+pub fn decrypt(value: &str, identity_file: &Path) -> anyhow::Result<String> {
+    let identity_file = identity_file.to_string_lossy();
+    run_age(&["--decrypt", "--identity", &identity_file], value)
+}
+
+/// Whether a YAML or JSON values file is SOPS-encrypted, which SOPS marks with
+/// a top-level `sops` metadata entry.
+pub fn is_sops_encrypted(path: &Path, text: &str) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    if !["yaml", "yml", "json"].contains(&extension.as_str()) {
+        return false;
+    }
+    // JSON is YAML, so one parser does for both
+    serde_yaml::from_str::<serde_yaml::Value>(text).is_ok_and(|doc| doc.get("sops").is_some_and(|s| s.is_mapping()))
+}
+
+/// Decrypts a SOPS-encrypted file, returning the plain text. This goes through
+/// `sops` so that all its key sources (age, PGP, cloud KMS and so on) work.
+#[tracing::instrument(level = "info")]
+pub fn sops_decrypt(path: &Path) -> anyhow::Result<String> {
+    let output = Command::new("sops")
+        .arg("--decrypt")
+        .arg(path)
+        .output()
+        .context("Failed to run sops. Is it installed and on your PATH?")?;
+    if !output.status.success() {
+        anyhow::bail!("sops failed to decrypt {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    String::from_utf8(output.stdout).context("sops produced a file that is not UTF-8")
+}
+
+fn run_age(args: &[&str], input: &str) -> anyhow::Result<String> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run age. Is it installed and on your PATH?")?;
+    let mut stdin = child.stdin.take().context("Failed to open age input")?;
+    // The input is written on another thread while the output is read, so that
+    // age can't block on a full output pipe while we block on a full input pipe
+    let (output, written) = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output();
+        (output, writer.join().expect("writing age input panicked"))
+    });
+    let output = output?;
+    if !output.status.success() {
+        anyhow::bail!("age failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    written.context("Failed to write age input")?;
+    String::from_utf8(output.stdout).context("age produced a value that is not UTF-8")
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::providers::{ProviderChain, ProviderLabel};
+use crate::runtime_config;
+
+/// The prefix Spin uses for variable environment variables if the runtime config
+/// doesn't specify one.
+pub const DEFAULT_PREFIX: &str = "SPIN_VARIABLE";
+
+/// Looks up variable values in the process environment and a dotenv file, the
+/// same way as Spin's `env` variables provider.
+pub struct EnvProvider {
+    prefix: String,
+    dotenv_path: Option<PathBuf>,
+    dotenv: HashMap<String, String>,
+}
+
+impl EnvProvider {
+    pub fn new(prefix: Option<String>, dotenv_path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let dotenv = match &dotenv_path {
+            Some(path) if path.exists() => load_dotenv(path)?,
+            _ => HashMap::new(),
+        };
+        Ok(Self {
+            prefix: prefix.unwrap_or_else(|| DEFAULT_PREFIX.to_owned()),
+            dotenv_path,
+            dotenv,
+        })
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The dotenv file values are read from, which may not exist.
+    pub fn dotenv_path(&self) -> Option<&Path> {
+        self.dotenv_path.as_deref()
+    }
+
+    /// The environment variable Spin reads for the given application variable.
+    pub fn env_var_name(&self, variable_name: &str) -> String {
+        env_var_name(&self.prefix, variable_name)
+    }
+
+    /// Adds the process environment and the dotenv file to the chain. The process
+    /// environment takes precedence over the dotenv file.
+    pub fn add_to(&self, chain: &mut ProviderChain) {
+        chain.push(ProviderLabel::Environment { prefix: self.prefix.clone() }, EnvVarProvider { prefix: self.prefix.clone() });
+        if let Some(dotenv_path) = &self.dotenv_path {
+            chain.push(ProviderLabel::Dotenv(dotenv_path.clone()), DotenvProvider {
+                prefix: self.prefix.clone(),
+                values: self.dotenv.clone(),
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+struct EnvVarProvider {
+    prefix: String,
+}
+
+#[async_trait::async_trait]
+impl Provider for EnvVarProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        Ok(std::env::var(env_var_name(&self.prefix, key.as_str())).ok())
+    }
+}
+
+#[derive(Debug)]
+struct DotenvProvider {
+    prefix: String,
+    values: HashMap<String, String>,
+}
+
+#[async_trait::async_trait]
+impl Provider for DotenvProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        Ok(self.values.get(&env_var_name(&self.prefix, key.as_str())).cloned())
+    }
+}
+
+pub fn env_var_name(prefix: &str, variable_name: &str) -> String {
+    format!("{prefix}_{}", variable_name.to_ascii_uppercase())
+}
+
+/// The variables a dotenv file sets for the given prefix, as variable names and
+/// values. Entries without the prefix are ignored.
+pub fn variables_in_dotenv(path: &Path, prefix: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let env_prefix = format!("{prefix}_");
+    let mut variables: Vec<_> = load_dotenv(path)?.into_iter()
+        .filter_map(|(key, value)| Some((key.strip_prefix(&env_prefix)?.to_ascii_lowercase(), value)))
+        .collect();
+    variables.sort();
+    Ok(variables)
+}
+
+fn load_dotenv(path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let entries = dotenvy::from_path_iter(path).with_context(|| format!("Failed to read dotenv file {}", path.display()))?;
+    entries
+        .map(|entry| entry.with_context(|| format!("Failed to parse dotenv file {}", path.display())))
+        .collect()
+}
+
+/// The `env` provider settings from a runtime config file's `[[variables_provider]]`
+/// (or legacy `[[config_provider]]`) entries.
+pub struct EnvProviderConfig {
+    pub prefix: Option<String>,
+    pub dotenv_path: Option<PathBuf>,
+}
+
+pub fn env_configs_from_runtime_config(path: &Path) -> anyhow::Result<Vec<EnvProviderConfig>> {
+    let base_dir = crate::app::parent_dir(path);
+    let configs = runtime_config::variables_providers(path)?
+        .into_iter()
+        .filter(|provider| runtime_config::provider_type(provider) == Some("env"))
+        .map(|provider| EnvProviderConfig {
+            prefix: provider.get("prefix").and_then(|p| p.as_str()).map(|p| p.to_owned()),
+            dotenv_path: provider.get("dotenv_path").and_then(|p| p.as_str()).map(|p| base_dir.join(p)),
+        })
+        .collect();
+    Ok(configs)
+}
+
+/// The environment providers Spin would use: those in the runtime config file if
+/// there is one, or else a default provider reading `.env` in the application
+/// directory. An explicit prefix or dotenv path overrides the provider settings.
+pub fn env_providers(
+    app_dir: &Path,
+    runtime_config_file: Option<&Path>,
+    prefix: Option<&str>,
+    dotenv_path: Option<&Path>,
+) -> anyhow::Result<Vec<EnvProvider>> {
+    if let Some(dotenv_path) = dotenv_path {
+        if !dotenv_path.exists() {
+            anyhow::bail!("Dotenv file {} does not exist", dotenv_path.display());
+        }
+    }
+
+    let mut configs = match runtime_config_file {
+        Some(path) => env_configs_from_runtime_config(path)?,
+        None => vec![],
+    };
+    if configs.is_empty() {
+        configs.push(EnvProviderConfig {
+            prefix: None,
+            dotenv_path: Some(app_dir.join(".env")),
+        });
+    }
+
+    configs.into_iter().map(|config| EnvProvider::new(
+        prefix.map(|p| p.to_owned()).or(config.prefix),
+        dotenv_path.map(|p| p.to_owned()).or(config.dotenv_path),
+    )).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for one test's files.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spin-variables-env-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_are_prefixed_and_upper_case() {
+        assert_eq!(env_var_name(DEFAULT_PREFIX, "api_key"), "SPIN_VARIABLE_API_KEY");
+        assert_eq!(EnvProvider::new(Some("MYAPP".to_owned()), None).unwrap().env_var_name("api_key"), "MYAPP_API_KEY");
+    }
+
+    #[test]
+    fn the_default_provider_reads_dotenv_in_the_app_dir() {
+        let dir = test_dir("default");
+        let providers = env_providers(&dir, None, None, None).unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].prefix(), DEFAULT_PREFIX);
+        assert_eq!(providers[0].dotenv_path(), Some(dir.join(".env").as_path()));
+
+        let error = env_providers(&dir, None, None, Some(&dir.join("missing.env"))).unwrap_err();
+        assert!(error.to_string().ends_with("missing.env does not exist"), "{error}");
+    }
+
+    #[test]
+    fn runtime_config_providers_can_be_overridden() {
+        let dir = test_dir("runtime-config");
+        let runtime_config = dir.join("runtime-config.toml");
+        std::fs::write(&runtime_config, "[[variables_provider]]\ntype = \"env\"\nprefix = \"MYAPP\"\ndotenv_path = \"config/.env\"\n\n[[variables_provider]]\ntype = \"vault\"\nurl = \"http://127.0.0.1:8200\"\ntoken = \"root\"\nmount = \"secret\"\n").unwrap();
+
+        let providers = env_providers(&dir, Some(&runtime_config), None, None).unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].prefix(), "MYAPP");
+        assert_eq!(providers[0].dotenv_path(), Some(dir.join("config/.env").as_path()));
+
+        let providers = env_providers(&dir, Some(&runtime_config), Some("OTHER"), None).unwrap();
+        assert_eq!(providers[0].prefix(), "OTHER");
+        assert_eq!(providers[0].dotenv_path(), Some(dir.join("config/.env").as_path()));
+    }
+
+    #[tokio::test]
+    async fn the_environment_wins_over_dotenv() {
+        let dir = test_dir("dotenv");
+        let prefix = format!("SPIN_VARIABLES_TEST_{}", std::process::id());
+        let dotenv_path = dir.join(".env");
+        std::fs::write(&dotenv_path, format!("{prefix}_A=dotenv\n{prefix}_B=dotenv\nOTHER_C=ignored\n")).unwrap();
+        std::env::set_var(format!("{prefix}_B"), "env");
+
+        let mut chain = ProviderChain::new();
+        EnvProvider::new(Some(prefix.clone()), Some(dotenv_path.clone())).unwrap().add_to(&mut chain);
+        let a = chain.get("a").await.unwrap().unwrap();
+        assert_eq!((a.value.as_str(), a.source), ("dotenv", crate::values::ValueSource::Dotenv(dotenv_path.clone())));
+        let b = chain.get("b").await.unwrap().unwrap();
+        assert_eq!((b.value.as_str(), b.source), ("env", crate::values::ValueSource::Environment(format!("{prefix}_B"))));
+        assert_eq!(chain.get("c").await.unwrap(), None);
+
+        assert_eq!(variables_in_dotenv(&dotenv_path, &prefix).unwrap(), [("a".to_owned(), "dotenv".to_owned()), ("b".to_owned(), "dotenv".to_owned())]);
+    }
+}
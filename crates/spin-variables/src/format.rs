@@ -0,0 +1,605 @@
+//! Formatting variables for display or for use elsewhere.
+//!
+//! Each output format is an [`OutputFormatter`]. The [`FormatterRegistry`] holds
+//! the built-in formatters, and others can be registered alongside them.
+
+pub mod azure;
+pub mod cloud_run;
+pub mod consul;
+pub mod ecs;
+pub mod fly;
+pub mod github;
+pub mod kube;
+pub mod markdown;
+pub mod paas;
+pub mod spin_cloud;
+
+use std::collections::BTreeMap;
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::schema::Schema;
+use crate::templates;
+use crate::values::{ResolvedValue, ValueSource};
+
+/// A way of presenting an application's variables.
+pub trait OutputFormatter {
+    /// The name used to choose this format, such as `table`.
+    fn name(&self) -> &str;
+
+    /// A short description of the format, for help text.
+    fn description(&self) -> &str;
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String>;
+}
+
+/// Information that formatters may need beyond the variables themselves.
+#[derive(Clone, Debug)]
+pub struct FormatContext {
+    /// The prefix of the environment variables that Spin reads values from.
+    pub env_prefix: String,
+    /// The application name, for formats that name resources or target an app.
+    pub app_name: String,
+    /// Constraints and other information about the variables beyond the manifest.
+    pub schema: Schema,
+    /// The longest a value may be in human-readable formats before it is cut short,
+    /// in characters, or `None` to show values in full.
+    pub max_value_width: Option<usize>,
+    /// The lengths of the resolved values in characters, by variable name, taken
+    /// before secrets were redacted. Human-readable formats show them if there are any.
+    pub value_lengths: BTreeMap<String, usize>,
+}
+
+/// The default for [`FormatContext::max_value_width`].
+pub const DEFAULT_MAX_VALUE_WIDTH: usize = 40;
+
+impl Default for FormatContext {
+    fn default() -> Self {
+        Self {
+            env_prefix: env::DEFAULT_PREFIX.to_owned(),
+            app_name: String::new(),
+            schema: Schema::default(),
+            max_value_width: Some(DEFAULT_MAX_VALUE_WIDTH),
+            value_lengths: BTreeMap::new(),
+        }
+    }
+}
+
+/// The available output formats, looked up by name.
+pub struct FormatterRegistry {
+    formatters: Vec<Box<dyn OutputFormatter>>,
+}
+
+impl FormatterRegistry {
+    /// A registry with no formatters.
+    pub fn empty() -> Self {
+        Self { formatters: vec![] }
+    }
+
+    /// A registry with the built-in formatters.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::empty();
+        registry.register(TableFormatter);
+        registry.register(BashFormatter);
+        registry.register(EnvExampleFormatter);
+        registry.register(JsonFormatter);
+        registry.register(kube::KubeFormatter);
+        registry.register(kube::ScaffoldFormatter);
+        registry.register(spin_cloud::DeployFormatter);
+        registry.register(spin_cloud::CloudFormatter);
+        registry.register(github::GitHubFormatter);
+        registry.register(azure::AzureFormatter);
+        registry.register(ecs::EcsFormatter);
+        registry.register(cloud_run::CloudRunFormatter);
+        registry.register(fly::FlyFormatter);
+        registry.register(paas::ConfigSetFormatter::heroku());
+        registry.register(paas::ConfigSetFormatter::dokku());
+        registry.register(markdown::MarkdownFormatter);
+        registry.register(consul::ConsulFormatter);
+        registry
+    }
+
+    /// Adds a formatter. If one with the same name is already registered, the new
+    /// formatter replaces it.
+    pub fn register(&mut self, formatter: impl OutputFormatter + 'static) {
+        self.formatters.retain(|f| f.name() != formatter.name());
+        self.formatters.push(Box::new(formatter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormatter> {
+        self.formatters.iter().find(|f| f.name() == name).map(|f| f.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn OutputFormatter> {
+        self.formatters.iter().map(|f| f.as_ref())
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+pub struct TableFormatter;
+
+impl OutputFormatter for TableFormatter {
+    fn name(&self) -> &str {
+        "table"
+    }
+
+    fn description(&self) -> &str {
+        "a human-readable tabular display format"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        Ok(table(variables, context).to_string())
+    }
+}
+
+pub struct BashFormatter;
+
+impl OutputFormatter for BashFormatter {
+    fn name(&self) -> &str {
+        "bash"
+    }
+
+    fn description(&self) -> &str {
+        "a bash script which can be saved, edited, and used to export values"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        Ok(bash(variables, &context.env_prefix).to_string())
+    }
+}
+
+pub struct EnvExampleFormatter;
+
+impl OutputFormatter for EnvExampleFormatter {
+    fn name(&self) -> &str {
+        "env-example"
+    }
+
+    fn description(&self) -> &str {
+        "a .env.example file listing every variable, for committing as documentation"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        Ok(env_example(variables, context))
+    }
+}
+
+pub struct JsonFormatter;
+
+impl OutputFormatter for JsonFormatter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn description(&self) -> &str {
+        "a JSON document for use by other tools"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let variables = variables.iter().map(|variable| VariableEntry::new(variable, context)).collect();
+        Ok(serde_json::to_string_pretty(&Versioned::new(VariablesDocument { variables }))?)
+    }
+}
+
+/// A variable as it appears in the `variables` array of `-o json` output.
+pub fn json_entry(variable: &VariableInfo, context: &FormatContext) -> serde_json::Result<serde_json::Value> {
+    serde_json::to_value(VariableEntry::new(variable, context))
+}
+
+/// Wraps data for machine-readable output, recording the schema version so that
+/// consumers can detect incompatible changes.
+#[derive(serde::Serialize)]
+pub struct Versioned<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            schema_version: crate::SCHEMA_VERSION,
+            data,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct VariablesDocument<'a> {
+    variables: Vec<VariableEntry<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct VariableEntry<'a> {
+    #[serde(flatten)]
+    variable: &'a VariableInfo,
+    /// The environment variable Spin reads the value from, with the context's prefix.
+    env_var: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+impl<'a> VariableEntry<'a> {
+    fn new(variable: &'a VariableInfo, context: &'a FormatContext) -> Self {
+        Self {
+            variable,
+            env_var: env::env_var_name(&context.env_prefix, &variable.name),
+            description: context.schema.description(&variable.name),
+        }
+    }
+}
+
+/// A human-readable table of the variables and the environment variables they are
+/// read from, including their values if any were
+/// resolved from somewhere other than the manifest defaults, and whether they are
+/// deprecated and their descriptions if the schema has any. Long or multi-line values are cut short
+/// to the context's maximum width. Value lengths are shown if the context has them.
+pub fn table(variables: &[VariableInfo], context: &FormatContext) -> impl std::fmt::Display {
+    let schema = &context.schema;
+    let fit = |value: &str| truncate(value, context.max_value_width);
+
+    // Only show values if there are some beyond the defaults, which are already shown
+    let show_values = variables.iter().any(|v| v.value.as_ref().is_some_and(|r| !matches!(r.source, ValueSource::Default)));
+    let show_deprecated = variables.iter().any(|v| schema.deprecation(&v.name).is_some());
+    let show_descriptions = variables.iter().any(|v| schema.description(&v.name).is_some());
+
+    let mut table = comfy_table::Table::new();
+    let mut header = vec!["Name", "Env var", "Required?", "Default value", "Secret?"];
+    let show_lengths = show_values && !context.value_lengths.is_empty();
+    if show_values {
+        header.push("Value");
+    }
+    if show_lengths {
+        header.push("Length");
+    }
+    if show_values {
+        header.push("Source");
+    }
+    if show_deprecated {
+        header.push("Deprecated?");
+    }
+    if show_descriptions {
+        header.push("Description");
+    }
+    table.set_header(comfy_table::Row::from(header));
+    table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+
+    for variable in variables {
+        let default_value = match &variable.default_value {
+            Some(default_value) if templates::is_templated(default_value) => format!("{} (template)", fit(default_value)),
+            Some(default_value) => fit(default_value),
+            None => String::new(),
+        };
+
+        let required = if variable.required {
+            "Required"
+        } else {
+            "Optional"
+        };
+
+        let secret = if variable.secret {
+            "Secret"
+        } else {
+            ""
+        };
+
+        let mut row = vec![
+            variable.name.to_owned(),
+            env::env_var_name(&context.env_prefix, &variable.name),
+            required.to_owned(),
+            default_value,
+            secret.to_owned(),
+        ];
+        if show_values {
+            row.push(variable.value.as_ref().map(|r| fit(&r.value)).unwrap_or_default());
+            if show_lengths {
+                row.push(context.value_lengths.get(&variable.name).map(|l| l.to_string()).unwrap_or_default());
+            }
+            row.push(variable.value.as_ref().map(|r| r.source.to_string()).unwrap_or_default());
+        }
+        if show_deprecated {
+            row.push(match schema.deprecation(&variable.name) {
+                Some("") => "Deprecated".to_owned(),
+                Some(hint) => format!("Deprecated: {hint}"),
+                None => String::new(),
+            });
+        }
+        if show_descriptions {
+            row.push(fit(schema.description(&variable.name).unwrap_or_default()));
+        }
+
+        table.add_row(row);
+    }
+
+    table
+}
+
+/// What a deployment-oriented format should configure for a variable.
+pub enum Setting<'a> {
+    /// A value that doesn't come from the manifest default.
+    Value(&'a str),
+    /// The variable is required but has no value, so the output needs filling in.
+    Placeholder,
+    /// The manifest default applies, so nothing needs configuring.
+    Default,
+}
+
+/// The text used in outputs for values the user needs to fill in.
+pub const PLACEHOLDER: &str = "TO-DO";
+
+pub fn setting(variable: &VariableInfo) -> Setting<'_> {
+    match &variable.value {
+        Some(ResolvedValue { source: ValueSource::Default | ValueSource::ExpandedDefault, .. }) => Setting::Default,
+        Some(ResolvedValue { value, .. }) => Setting::Value(value),
+        None if variable.required => Setting::Placeholder,
+        None => Setting::Default,
+    }
+}
+
+/// Each variable that doesn't fall back to its manifest default, with the value to
+/// configure for it, or a placeholder if it has none.
+pub fn settings(variables: &[VariableInfo]) -> impl Iterator<Item = (&VariableInfo, &str)> {
+    variables.iter().filter_map(|v| match setting(v) {
+        Setting::Value(value) => Some((v, value)),
+        Setting::Placeholder => Some((v, PLACEHOLDER)),
+        Setting::Default => None,
+    })
+}
+
+/// The text that replaces secret values in outputs unless they are revealed.
+pub const REDACTED: &str = "REDACTED";
+
+/// Replaces the values of secret variables with [`REDACTED`], so that outputs can
+/// be shared or logged safely, and returns the names of the variables redacted.
+/// Manifest defaults are left alone, since they are not secret from anyone who can
+/// see the manifest, and so are generated values, which only exist in the output.
+pub fn redact_secrets(variables: &mut [VariableInfo]) -> Vec<String> {
+    replace_secrets(variables, |_| REDACTED.to_owned())
+}
+
+/// A digest algorithm for showing secret values as hashes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            _ => anyhow::bail!("Unknown hash algorithm '{s}'. The available algorithm is: sha256"),
+        }
+    }
+}
+
+/// Replaces the values of secret variables with their digests, such as
+/// `sha256:2c26b4...`, so that they can be compared across environments or with
+/// expected values without being revealed. Returns the names of the variables hashed.
+/// The same values are left alone as by [`redact_secrets`].
+#[cfg(feature = "native")]
+pub fn hash_secrets(variables: &mut [VariableInfo], algorithm: HashAlgorithm) -> Vec<String> {
+    use sha2::Digest;
+
+    replace_secrets(variables, |value| match algorithm {
+        HashAlgorithm::Sha256 => format!("sha256:{}", hex::encode(sha2::Sha256::digest(value.as_bytes()))),
+    })
+}
+
+fn replace_secrets(variables: &mut [VariableInfo], replacement: impl Fn(&str) -> String) -> Vec<String> {
+    let mut replaced = vec![];
+    for variable in variables.iter_mut().filter(|v| v.secret) {
+        if let Some(resolved) = variable.value.as_mut().filter(|r| !matches!(r.source, ValueSource::Default | ValueSource::ExpandedDefault | ValueSource::Generated)) {
+            resolved.value = replacement(&resolved.value);
+            replaced.push(variable.name.clone());
+        }
+    }
+    replaced
+}
+
+/// The lengths of the variables' values in characters, for [`FormatContext::value_lengths`].
+pub fn value_lengths(variables: &[VariableInfo]) -> BTreeMap<String, usize> {
+    variables.iter()
+        .filter_map(|v| Some((v.name.clone(), v.value.as_ref()?.value.chars().count())))
+        .collect()
+}
+
+/// The application name, or a placeholder if the manifest doesn't give one.
+pub fn app_name(context: &FormatContext) -> &str {
+    if context.app_name.is_empty() {
+        PLACEHOLDER
+    } else {
+        &context.app_name
+    }
+}
+
+/// Quotes a string for use as a single shell word, if it needs it.
+pub fn shell_quote(s: &str) -> std::borrow::Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c);
+    if !s.is_empty() && s.chars().all(is_safe) {
+        s.into()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''")).into()
+    }
+}
+
+/// A command line split over several lines, one argument group per line.
+pub fn multiline_command(command: &str, args: &[String]) -> String {
+    std::iter::once(command.to_owned()).chain(args.iter().cloned()).collect::<Vec<_>>().join(" \\\n  ")
+}
+
+/// Cuts a value down to its first line and at most `max_width` characters, marking
+/// where anything was removed with an ellipsis.
+pub fn truncate(value: &str, max_width: Option<usize>) -> String {
+    let Some(max_width) = max_width else {
+        return value.to_owned();
+    };
+    let first_line = value.lines().next().unwrap_or_default();
+    let cut_line = first_line.len() < value.trim_end().len();
+    if first_line.chars().count() > max_width {
+        let kept: String = first_line.chars().take(max_width.saturating_sub(1)).collect();
+        format!("{kept}…")
+    } else if cut_line {
+        format!("{first_line}…")
+    } else {
+        value.to_owned()
+    }
+}
+
+/// A bash script which can be saved, edited, and used to export values.
+pub fn bash(variables: &[VariableInfo], env_prefix: &str) -> impl std::fmt::Display {
+    let mut lines = vec![
+        "# You may `source` this or reference it in your runtime-config.toml via the `dotenv_path` field".to_owned(),
+        "".to_owned(),
+    ];
+    lines.extend(variables.iter().map(|v| format_one_bash(v, env_prefix)));
+    lines.join("\n")
+}
+
+/// A dotenv file with an entry for every variable, described in a comment. Values
+/// are never included, so the file can be committed: required variables have empty
+/// entries to fill in, and optional ones are commented out with their defaults.
+pub fn env_example(variables: &[VariableInfo], context: &FormatContext) -> String {
+    let mut lines = vec!["# Copy this file to .env and fill in the values. Lines that are commented out are optional.".to_owned()];
+    for variable in variables {
+        let env_var_name = env::env_var_name(&context.env_prefix, &variable.name);
+        let mut notes = vec![if variable.required { "required" } else { "optional" }.to_owned()];
+        if variable.secret {
+            notes.push("secret".to_owned());
+        }
+        match context.schema.deprecation(&variable.name) {
+            Some("") => notes.push("deprecated".to_owned()),
+            Some(hint) => notes.push(format!("deprecated: {hint}")),
+            None => {}
+        }
+
+        lines.push(String::new());
+        lines.push(format!("# {}: {}", variable.name, notes.join(", ")));
+        if let Some(description) = context.schema.description(&variable.name) {
+            lines.extend(description.lines().map(|line| format!("# {line}").trim_end().to_owned()));
+        }
+        match &variable.default_value {
+            // Newlines are escaped, as in bash output, so that the rest of the default isn't read as entries
+            Some(default_value) => lines.push(format!("# {env_var_name}={}", shell_quote(&default_value.replace('\n', "\\n")))),
+            None if variable.required => lines.push(format!("{env_var_name}=")),
+            None => lines.push(format!("# {env_var_name}=")),
+        }
+    }
+    lines.join("\n")
+}
+
+fn format_one_bash(variable: &VariableInfo, env_prefix: &str) -> String {
+    let env_var_name = env::env_var_name(env_prefix, &variable.name);
+    match &variable.value {
+        // Newlines are escaped on commented-out lines, so that the rest of the value isn't run
+        Some(ResolvedValue { value, source: ValueSource::Default | ValueSource::ExpandedDefault }) => format!("# export {env_var_name}={}  # optional", shell_quote(&value.replace('\n', "\\n"))),
+        Some(ResolvedValue { value, source }) => format!("export {env_var_name}={}  # from {source}", shell_quote(value)),
+        None if variable.required => format!("export {env_var_name}={PLACEHOLDER}  # required"),
+        None => format!("# export {env_var_name}=  # optional"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn variable(name: &str, required: bool, value: Option<(&str, ValueSource)>) -> VariableInfo {
+        VariableInfo {
+            name: name.to_owned(),
+            default_value: None,
+            required,
+            secret: false,
+            value: value.map(|(value, source)| ResolvedValue { value: value.to_owned(), source }),
+        }
+    }
+
+    /// Variables for the deploy formatters covering each kind of setting: a value
+    /// that needs quoting, a secret, a required variable with no value, and one
+    /// left to its default, which should not appear in the output.
+    pub(super) fn deploy_variables() -> Vec<VariableInfo> {
+        let mut api_key = variable("api_key", true, Some(("it's s3cret", ValueSource::Set)));
+        api_key.secret = true;
+        vec![
+            variable("region", false, Some(("eu west", ValueSource::Environment("SPIN_VARIABLE_REGION".to_owned())))),
+            api_key,
+            variable("token", true, None),
+            variable("motd", false, Some(("hello", ValueSource::Default))),
+        ]
+    }
+
+    pub(super) fn deploy_context() -> FormatContext {
+        FormatContext {
+            app_name: "my app".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bash_values_are_quoted() {
+        let variables = [variable("greeting", true, Some(("it's $(rm -rf ~) `now`", ValueSource::Set)))];
+        let script = bash(&variables, env::DEFAULT_PREFIX).to_string();
+        assert!(script.contains("export SPIN_VARIABLE_GREETING='it'\\''s $(rm -rf ~) `now`'  # from"));
+    }
+
+    #[test]
+    fn bash_commented_defaults_stay_commented() {
+        let variables = [variable("motd", false, Some(("hello\nrm -rf ~", ValueSource::Default)))];
+        let script = bash(&variables, env::DEFAULT_PREFIX).to_string();
+        assert!(script.lines().all(|line| line.is_empty() || line.starts_with('#')));
+    }
+
+    #[test]
+    fn bash_only_marks_required_variables_as_required() {
+        let variables = [variable("token", true, None), variable("region", false, None)];
+        let script = bash(&variables, env::DEFAULT_PREFIX).to_string();
+        assert!(script.contains("export SPIN_VARIABLE_TOKEN=TO-DO  # required"));
+        assert!(script.contains("# export SPIN_VARIABLE_REGION=  # optional"));
+    }
+
+    #[test]
+    fn env_example_multi_line_defaults_stay_commented() {
+        let mut motd = variable("motd", false, None);
+        motd.default_value = Some("hello\nSPIN_VARIABLE_ADMIN=true".to_owned());
+        let example = env_example(&[motd], &FormatContext::default());
+        assert!(example.lines().all(|line| line.is_empty() || line.starts_with('#')));
+        assert!(example.contains("# SPIN_VARIABLE_MOTD='hello\\nSPIN_VARIABLE_ADMIN=true'"));
+    }
+
+    #[test]
+    fn env_example_multi_line_descriptions_stay_commented() {
+        let mut context = FormatContext::default();
+        context.schema.add_descriptions([("region".to_owned(), "Where to deploy.\nSPIN_VARIABLE_ADMIN=true".to_owned())]);
+        let example = env_example(&[variable("region", false, None)], &context);
+        assert!(example.lines().all(|line| line.is_empty() || line.starts_with('#')));
+        assert!(example.contains("# Where to deploy.\n# SPIN_VARIABLE_ADMIN=true\n"));
+    }
+
+    #[test]
+    fn env_example_leaves_required_entries_to_fill_in() {
+        let example = env_example(&[variable("token", true, None)], &FormatContext::default());
+        assert!(example.ends_with("# token: required\nSPIN_VARIABLE_TOKEN="));
+    }
+
+    #[test]
+    fn shell_quote_leaves_safe_words_alone() {
+        assert_eq!(shell_quote("https://example.com/a-b"), "https://example.com/a-b");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+    }
+
+    #[test]
+    fn truncate_cuts_to_the_first_line_and_width() {
+        assert_eq!(truncate("short", Some(10)), "short");
+        assert_eq!(truncate("abcdefghijkl", Some(5)), "abcd…");
+        assert_eq!(truncate("line one\nline two", Some(40)), "line one…");
+        assert_eq!(truncate("trailing newline\n", Some(40)), "trailing newline\n");
+        assert_eq!(truncate("ééééé", Some(5)), "ééééé");
+        assert_eq!(truncate("anything\ngoes", None), "anything\ngoes");
+    }
+}
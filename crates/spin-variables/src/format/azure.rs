@@ -0,0 +1,73 @@
+//! Commands for configuring an Azure Container App running a Spin application.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{app_name, multiline_command, settings, shell_quote, FormatContext, OutputFormatter, PLACEHOLDER};
+
+/// An `az containerapp secret set` command for the secret variables, and an
+/// `az containerapp update` command setting environment variables, with the
+/// secret ones referring to the secrets rather than holding their values.
+pub struct AzureFormatter;
+
+impl OutputFormatter for AzureFormatter {
+    fn name(&self) -> &str {
+        "az"
+    }
+
+    fn description(&self) -> &str {
+        "`az containerapp` commands setting secrets and environment variables"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let target = format!("--name {} --resource-group {PLACEHOLDER}", shell_quote(app_name(context)));
+
+        let mut secrets = vec![];
+        let mut env_vars = vec![];
+        for (variable, value) in settings(variables) {
+            let env_var_name = env::env_var_name(&context.env_prefix, &variable.name);
+            if variable.secret {
+                let secret_name = secret_name(&variable.name);
+                secrets.push(shell_quote(&format!("{secret_name}={value}")).into_owned());
+                env_vars.push(format!("{env_var_name}=secretref:{secret_name}"));
+            } else {
+                env_vars.push(shell_quote(&format!("{env_var_name}={value}")).into_owned());
+            }
+        }
+
+        let mut commands = vec![];
+        if !secrets.is_empty() {
+            commands.push(multiline_command(&format!("az containerapp secret set {target}"), &[format!("--secrets {}", secrets.join(" "))]));
+        }
+        if !env_vars.is_empty() {
+            commands.push(multiline_command(&format!("az containerapp update {target}"), &[format!("--set-env-vars {}", env_vars.join(" "))]));
+        }
+        Ok(commands.join("\n"))
+    }
+}
+
+/// Container Apps secret names may only contain lowercase letters, digits and '-'.
+fn secret_name(variable_name: &str) -> String {
+    variable_name.to_ascii_lowercase().replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_are_set_separately_and_referred_to() {
+        let output = AzureFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "az containerapp secret set --name 'my app' --resource-group TO-DO \\",
+            "  --secrets 'api-key=it'\\''s s3cret'",
+            "az containerapp update --name 'my app' --resource-group TO-DO \\",
+            "  --set-env-vars 'SPIN_VARIABLE_REGION=eu west' SPIN_VARIABLE_API_KEY=secretref:api-key SPIN_VARIABLE_TOKEN=TO-DO",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn secret_names_are_valid_for_container_apps() {
+        assert_eq!(secret_name("DB_Password"), "db-password");
+    }
+}
@@ -0,0 +1,79 @@
+//! Commands for deploying Spin applications to Google Cloud Run.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{app_name, multiline_command, settings, shell_quote, FormatContext, OutputFormatter};
+
+/// A `gcloud run deploy` command setting environment variables, with secret
+/// variables taken from Secret Manager secrets of the same name.
+pub struct CloudRunFormatter;
+
+impl OutputFormatter for CloudRunFormatter {
+    fn name(&self) -> &str {
+        "cloudrun"
+    }
+
+    fn description(&self) -> &str {
+        "a `gcloud run deploy` command setting environment variables and secrets"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let mut env_vars = vec![];
+        let mut secrets = vec![];
+        for (variable, value) in settings(variables) {
+            let env_var_name = env::env_var_name(&context.env_prefix, &variable.name);
+            if variable.secret {
+                secrets.push(format!("{env_var_name}={}:latest", variable.name));
+            } else {
+                env_vars.push(format!("{env_var_name}={value}"));
+            }
+        }
+
+        let mut args = vec![];
+        if !env_vars.is_empty() {
+            args.push(format!("--set-env-vars={}", shell_quote(&gcloud_list(&env_vars))));
+        }
+        if !secrets.is_empty() {
+            args.push(format!("--set-secrets={}", shell_quote(&gcloud_list(&secrets))));
+        }
+        Ok(multiline_command(&format!("gcloud run deploy {}", shell_quote(app_name(context))), &args))
+    }
+}
+
+/// A gcloud list argument. Items are separated by commas unless a value contains
+/// one, in which case we use gcloud's `^DELIMITER^` syntax to choose another.
+fn gcloud_list(items: &[String]) -> String {
+    let delimiter = [',', '|', '@', '#', ';', '~']
+        .into_iter()
+        .find(|d| !items.iter().any(|item| item.contains(*d)))
+        .unwrap_or('\u{1f}');
+    let joined = items.join(&delimiter.to_string());
+    if delimiter == ',' {
+        joined
+    } else {
+        format!("^{delimiter}^{joined}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_come_from_secret_manager() {
+        let output = CloudRunFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "gcloud run deploy 'my app' \\",
+            "  --set-env-vars='SPIN_VARIABLE_REGION=eu west,SPIN_VARIABLE_TOKEN=TO-DO' \\",
+            "  --set-secrets=SPIN_VARIABLE_API_KEY=api_key:latest",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn lists_avoid_delimiters_in_values() {
+        assert_eq!(gcloud_list(&["A=1".to_owned(), "B=2".to_owned()]), "A=1,B=2");
+        assert_eq!(gcloud_list(&["A=1,2".to_owned(), "B=3".to_owned()]), "^|^A=1,2|B=3");
+        assert_eq!(gcloud_list(&["A=1,2|3".to_owned()]), "^@^A=1,2|3");
+    }
+}
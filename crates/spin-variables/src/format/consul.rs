@@ -0,0 +1,43 @@
+//! Commands for seeding Consul's key/value store with variable values, for use with
+//! the Consul provider.
+
+use crate::app::VariableInfo;
+use crate::format::{settings, shell_quote, FormatContext, OutputFormatter};
+
+/// `consul kv put` commands, one per variable that doesn't fall back to its manifest
+/// default. Keys start with `$CONSUL_KV_PREFIX`, which should be set to the path
+/// prefix the values will be resolved with.
+pub struct ConsulFormatter;
+
+impl OutputFormatter for ConsulFormatter {
+    fn name(&self) -> &str {
+        "consul"
+    }
+
+    fn description(&self) -> &str {
+        "`consul kv put` commands storing the values in Consul's key/value store"
+    }
+
+    fn format(&self, variables: &[VariableInfo], _context: &FormatContext) -> anyhow::Result<String> {
+        let mut lines = vec!["# Set CONSUL_KV_PREFIX to the path prefix the values are resolved with, such as apps/my-app/".to_owned()];
+        lines.extend(settings(variables).map(|(variable, value)| format!("consul kv put \"${{CONSUL_KV_PREFIX}}{}\" {}", variable.name, shell_quote(value))));
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn values_are_put_under_the_prefix() {
+        let output = ConsulFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        let commands: Vec<_> = output.lines().skip(1).collect();
+        assert_eq!(commands, [
+            "consul kv put \"${CONSUL_KV_PREFIX}region\" 'eu west'",
+            "consul kv put \"${CONSUL_KV_PREFIX}api_key\" 'it'\\''s s3cret'",
+            "consul kv put \"${CONSUL_KV_PREFIX}token\" TO-DO",
+        ]);
+    }
+}
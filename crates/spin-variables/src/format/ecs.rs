@@ -0,0 +1,59 @@
+//! Container definition fragments for Spin applications on Amazon ECS or Fargate.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{settings, FormatContext, OutputFormatter};
+
+/// The `environment` and `secrets` arrays of an ECS container definition. Secret
+/// variables refer to Secrets Manager ARNs whose region, account and secret name
+/// need filling in.
+pub struct EcsFormatter;
+
+impl OutputFormatter for EcsFormatter {
+    fn name(&self) -> &str {
+        "ecs"
+    }
+
+    fn description(&self) -> &str {
+        "the environment and secrets of an ECS container definition, as JSON"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let mut environment = vec![];
+        let mut secrets = vec![];
+        for (variable, value) in settings(variables) {
+            let name = env::env_var_name(&context.env_prefix, &variable.name);
+            if variable.secret {
+                let value_from = format!("arn:aws:secretsmanager:REGION:ACCOUNT:secret:{}", variable.name);
+                secrets.push(serde_json::json!({ "name": name, "valueFrom": value_from }));
+            } else {
+                environment.push(serde_json::json!({ "name": name, "value": value }));
+            }
+        }
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "environment": environment,
+            "secrets": secrets,
+        }))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_refer_to_secrets_manager() {
+        let output = EcsFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        let definition: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(definition, serde_json::json!({
+            "environment": [
+                { "name": "SPIN_VARIABLE_REGION", "value": "eu west" },
+                { "name": "SPIN_VARIABLE_TOKEN", "value": "TO-DO" },
+            ],
+            "secrets": [
+                { "name": "SPIN_VARIABLE_API_KEY", "valueFrom": "arn:aws:secretsmanager:REGION:ACCOUNT:secret:api_key" },
+            ],
+        }));
+    }
+}
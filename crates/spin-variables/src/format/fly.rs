@@ -0,0 +1,77 @@
+//! Configuration for Spin applications hosted on fly.io.
+
+use std::collections::BTreeMap;
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{multiline_command, settings, shell_quote, FormatContext, OutputFormatter};
+
+/// An `[env]` table for `fly.toml` holding the non-secret variables, and a
+/// `fly secrets set` command for the secret ones.
+pub struct FlyFormatter;
+
+impl OutputFormatter for FlyFormatter {
+    fn name(&self) -> &str {
+        "fly"
+    }
+
+    fn description(&self) -> &str {
+        "an [env] table for fly.toml, and a `fly secrets set` command for secrets"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let mut env = BTreeMap::new();
+        let mut secrets = vec![];
+        for (variable, value) in settings(variables) {
+            let env_var_name = env::env_var_name(&context.env_prefix, &variable.name);
+            if variable.secret {
+                secrets.push(shell_quote(&format!("{env_var_name}={value}")).into_owned());
+            } else {
+                env.insert(env_var_name, value);
+            }
+        }
+
+        let mut sections = vec![];
+        if !env.is_empty() {
+            sections.push(format!("# In fly.toml\n{}", toml::to_string(&FlyConfig { env })?));
+        }
+        if !secrets.is_empty() {
+            let command = match context.app_name.as_str() {
+                "" => "fly secrets set".to_owned(),
+                app_name => format!("fly secrets set --app {}", shell_quote(app_name)),
+            };
+            sections.push(multiline_command(&command, &secrets));
+        }
+        Ok(sections.join("\n"))
+    }
+}
+
+#[derive(serde::Serialize)]
+struct FlyConfig<'a> {
+    env: BTreeMap<String, &'a str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_are_set_by_command_not_in_fly_toml() {
+        let output = FlyFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        let (fly_toml, command) = output.split_once("fly secrets set").unwrap();
+
+        let fly_toml: toml::Table = toml::from_str(fly_toml).unwrap();
+        let env = fly_toml["env"].as_table().unwrap();
+        assert_eq!(env.keys().collect::<Vec<_>>(), ["SPIN_VARIABLE_REGION", "SPIN_VARIABLE_TOKEN"]);
+        assert_eq!(env["SPIN_VARIABLE_REGION"].as_str(), Some("eu west"));
+
+        assert_eq!(command, " --app 'my app' \\\n  'SPIN_VARIABLE_API_KEY=it'\\''s s3cret'");
+    }
+
+    #[test]
+    fn the_app_is_left_to_fly_toml_if_unknown() {
+        let output = FlyFormatter.format(&deploy_variables(), &FormatContext::default()).unwrap();
+        assert!(output.contains("fly secrets set \\\n"));
+    }
+}
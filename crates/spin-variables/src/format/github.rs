@@ -0,0 +1,53 @@
+//! Commands for seeding a GitHub repository's Actions configuration.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{settings, shell_quote, FormatContext, OutputFormatter};
+
+/// `gh secret set` commands for secret variables and `gh variable set` commands
+/// for the rest, named as the environment variables Spin reads.
+pub struct GitHubFormatter;
+
+impl OutputFormatter for GitHubFormatter {
+    fn name(&self) -> &str {
+        "gh"
+    }
+
+    fn description(&self) -> &str {
+        "`gh secret set` and `gh variable set` commands for a GitHub repository"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let commands: Vec<_> = settings(variables)
+            .map(|(variable, value)| {
+                let kind = if variable.secret { "secret" } else { "variable" };
+                let env_var_name = env::env_var_name(&context.env_prefix, &variable.name);
+                format!("gh {kind} set {env_var_name} --body {}", shell_quote(value))
+            })
+            .collect();
+        Ok(commands.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_are_set_as_secrets() {
+        let output = GitHubFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "gh variable set SPIN_VARIABLE_REGION --body 'eu west'",
+            "gh secret set SPIN_VARIABLE_API_KEY --body 'it'\\''s s3cret'",
+            "gh variable set SPIN_VARIABLE_TOKEN --body TO-DO",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn names_use_the_env_prefix() {
+        let context = FormatContext { env_prefix: "MYAPP".to_owned(), ..deploy_context() };
+        let output = GitHubFormatter.format(&deploy_variables(), &context).unwrap();
+        assert!(output.starts_with("gh variable set MYAPP_REGION "));
+    }
+}
@@ -0,0 +1,148 @@
+//! Kubernetes resources holding variable values, for SpinKube and similar
+//! deployments.
+
+use std::collections::BTreeMap;
+
+use crate::app::VariableInfo;
+use crate::format::{multiline_command, setting, settings, shell_quote, FormatContext, OutputFormatter, Setting, PLACEHOLDER};
+
+/// A `spin kube scaffold` command passing each variable that doesn't fall back to
+/// its manifest default as a `--variable` flag. The image is left as a placeholder,
+/// since it isn't known until the app is pushed.
+pub struct ScaffoldFormatter;
+
+impl OutputFormatter for ScaffoldFormatter {
+    fn name(&self) -> &str {
+        "scaffold"
+    }
+
+    fn description(&self) -> &str {
+        "a `spin kube scaffold` command passing the values as --variable arguments"
+    }
+
+    fn format(&self, variables: &[VariableInfo], _context: &FormatContext) -> anyhow::Result<String> {
+        let args: Vec<_> = std::iter::once(format!("--from {PLACEHOLDER}"))
+            .chain(settings(variables).map(|(variable, value)| format!("--variable {}", shell_quote(&format!("{}={value}", variable.name)))))
+            .collect();
+        Ok(multiline_command("spin kube scaffold", &args))
+    }
+}
+
+/// A Secret for the secret variables and a ConfigMap for the rest. Variables
+/// that fall back to their manifest defaults are left out.
+pub struct KubeFormatter;
+
+impl OutputFormatter for KubeFormatter {
+    fn name(&self) -> &str {
+        "kube"
+    }
+
+    fn description(&self) -> &str {
+        "a Kubernetes Secret and ConfigMap holding the values"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let name = resource_name(&context.app_name);
+
+        let mut secrets = BTreeMap::new();
+        let mut config = BTreeMap::new();
+        for variable in variables {
+            let value = match setting(variable) {
+                Setting::Value(value) => value,
+                Setting::Placeholder => PLACEHOLDER,
+                Setting::Default => continue,
+            };
+            let target = if variable.secret { &mut secrets } else { &mut config };
+            target.insert(variable.name.as_str(), value);
+        }
+
+        let mut documents = vec![];
+        if !secrets.is_empty() {
+            documents.push(serde_yaml::to_string(&Resource {
+                api_version: "v1",
+                kind: "Secret",
+                metadata: Metadata { name: &name },
+                data: None,
+                string_data: Some(secrets),
+            })?);
+        }
+        if !config.is_empty() {
+            documents.push(serde_yaml::to_string(&Resource {
+                api_version: "v1",
+                kind: "ConfigMap",
+                metadata: Metadata { name: &name },
+                data: Some(config),
+                string_data: None,
+            })?);
+        }
+        Ok(documents.join("---\n"))
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resource<'a> {
+    api_version: &'a str,
+    kind: &'a str,
+    metadata: Metadata<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    string_data: Option<BTreeMap<&'a str, &'a str>>,
+}
+
+#[derive(serde::Serialize)]
+struct Metadata<'a> {
+    name: &'a str,
+}
+
+/// The name of the Secret and ConfigMap: the application name made into a valid
+/// Kubernetes resource name.
+pub fn resource_name(app_name: &str) -> String {
+    let sanitised: String = app_name.to_ascii_lowercase().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    let sanitised = sanitised.trim_matches('-');
+    if sanitised.is_empty() {
+        "spin-app-variables".to_owned()
+    } else {
+        format!("{sanitised}-variables")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn secrets_go_in_the_secret_and_the_rest_in_the_config_map() {
+        let output = KubeFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        let documents: Vec<serde_yaml::Value> = output.split("---\n").map(|d| serde_yaml::from_str(d).unwrap()).collect();
+        assert_eq!(documents.len(), 2);
+
+        let (secret, config_map) = (&documents[0], &documents[1]);
+        assert_eq!(secret["kind"], "Secret");
+        assert_eq!(secret["metadata"]["name"], "my-app-variables");
+        assert_eq!(secret["stringData"], serde_yaml::from_str::<serde_yaml::Value>("api_key: it's s3cret").unwrap());
+        assert_eq!(config_map["kind"], "ConfigMap");
+        assert_eq!(config_map["data"], serde_yaml::from_str::<serde_yaml::Value>("region: eu west\ntoken: TO-DO").unwrap());
+    }
+
+    #[test]
+    fn scaffold_passes_quoted_variables() {
+        let output = ScaffoldFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "spin kube scaffold \\",
+            "  --from TO-DO \\",
+            "  --variable 'region=eu west' \\",
+            "  --variable 'api_key=it'\\''s s3cret' \\",
+            "  --variable token=TO-DO",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn resource_names_are_valid() {
+        assert_eq!(resource_name("My_App"), "my-app-variables");
+        assert_eq!(resource_name("--app--"), "app-variables");
+        assert_eq!(resource_name("!!!"), "spin-app-variables");
+    }
+}
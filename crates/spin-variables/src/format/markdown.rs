@@ -0,0 +1,113 @@
+//! Documentation of an application's variables, for READMEs and other docs.
+
+use crate::app::VariableInfo;
+use crate::format::{FormatContext, OutputFormatter};
+
+/// The comment marking the start of generated content in a Markdown document.
+pub const BEGIN_MARKER: &str = "<!-- spin-variables:begin -->";
+/// The comment marking the end of generated content in a Markdown document.
+pub const END_MARKER: &str = "<!-- spin-variables:end -->";
+
+/// A Markdown table of the variables' declarations. Resolved values are left out,
+/// since docs describe the application rather than one environment.
+pub struct MarkdownFormatter;
+
+impl OutputFormatter for MarkdownFormatter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn description(&self) -> &str {
+        "a Markdown table documenting the variables, for READMEs"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let show_deprecated = variables.iter().any(|v| context.schema.deprecation(&v.name).is_some());
+        let show_descriptions = variables.iter().any(|v| context.schema.description(&v.name).is_some());
+
+        let mut header = vec!["Name"];
+        if show_descriptions {
+            header.push("Description");
+        }
+        header.extend(["Required", "Default", "Secret"]);
+        if show_deprecated {
+            header.push("Deprecated");
+        }
+        let mut lines = vec![
+            row(header.iter().map(|h| h.to_string())),
+            row(header.iter().map(|_| "---".to_owned())),
+        ];
+
+        let yes = |b: bool| if b { "Yes" } else { "" }.to_owned();
+        for variable in variables {
+            let mut cells = vec![format!("`{}`", variable.name)];
+            if show_descriptions {
+                cells.push(context.schema.description(&variable.name).unwrap_or_default().to_owned());
+            }
+            cells.extend([
+                yes(variable.required),
+                variable.default_value.as_ref().map(|d| format!("`{d}`")).unwrap_or_default(),
+                yes(variable.secret),
+            ]);
+            if show_deprecated {
+                cells.push(match context.schema.deprecation(&variable.name) {
+                    Some("") => "Yes".to_owned(),
+                    Some(hint) => hint.to_owned(),
+                    None => String::new(),
+                });
+            }
+            lines.push(row(cells.into_iter()));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+fn row(cells: impl Iterator<Item = String>) -> String {
+    let cells: Vec<_> = cells.map(|c| c.replace('|', "\\|").replace('\n', " ")).collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Replaces whatever is between the markers in a document with new content,
+/// keeping the markers so the document can be updated again.
+pub fn inject_between_markers(document: &str, content: &str) -> anyhow::Result<String> {
+    let Some(begin) = document.find(BEGIN_MARKER) else {
+        anyhow::bail!("The document has no {BEGIN_MARKER} marker");
+    };
+    let content_start = begin + BEGIN_MARKER.len();
+    let Some(end) = document[content_start..].find(END_MARKER).map(|i| i + content_start) else {
+        anyhow::bail!("The document has no {END_MARKER} marker after {BEGIN_MARKER}");
+    };
+    Ok(format!("{}\n{}\n{}", &document[..content_start], content.trim_end(), &document[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_escaped() {
+        let mut region = crate::format::tests::variable("region", false, None);
+        region.default_value = Some("eu|west".to_owned());
+        let mut api_key = crate::format::tests::variable("api_key", true, None);
+        api_key.secret = true;
+        let mut context = FormatContext::default();
+        context.schema.add_descriptions([("region".to_owned(), "Where it\nruns | mostly".to_owned())]);
+
+        let output = MarkdownFormatter.format(&[region, api_key], &context).unwrap();
+        assert_eq!(output, [
+            "| Name | Description | Required | Default | Secret |",
+            "| --- | --- | --- | --- | --- |",
+            "| `region` | Where it runs \\| mostly |  | `eu\\|west` |  |",
+            "| `api_key` |  | Yes |  | Yes |",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn content_between_markers_is_replaced() {
+        let document = format!("intro\n{BEGIN_MARKER}\nold\n{END_MARKER}\nrest");
+        assert_eq!(inject_between_markers(&document, "new\n").unwrap(), format!("intro\n{BEGIN_MARKER}\nnew\n{END_MARKER}\nrest"));
+        assert!(inject_between_markers(&format!("{END_MARKER}\n{BEGIN_MARKER}"), "new").is_err());
+        assert!(inject_between_markers("no markers", "new").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! Commands for platforms that take configuration through a `config:set` style CLI.
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::{app_name, multiline_command, settings, shell_quote, FormatContext, OutputFormatter};
+
+/// A single command setting the environment variables Spin reads, such as
+/// `heroku config:set`. Other platforms with the same style of CLI can be supported
+/// by registering another instance.
+pub struct ConfigSetFormatter {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Builds the command up to the first NAME=VALUE argument, given the app name.
+    pub command: fn(&str) -> String,
+}
+
+impl ConfigSetFormatter {
+    pub fn heroku() -> Self {
+        Self {
+            name: "heroku",
+            description: "a `heroku config:set` command",
+            command: |app| format!("heroku config:set --app {app}"),
+        }
+    }
+
+    pub fn dokku() -> Self {
+        Self {
+            name: "dokku",
+            description: "a `dokku config:set` command",
+            command: |app| format!("dokku config:set {app}"),
+        }
+    }
+}
+
+impl OutputFormatter for ConfigSetFormatter {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let args: Vec<_> = settings(variables)
+            .map(|(variable, value)| shell_quote(&format!("{}={value}", env::env_var_name(&context.env_prefix, &variable.name))).into_owned())
+            .collect();
+        Ok(multiline_command(&(self.command)(&shell_quote(app_name(context))), &args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn values_are_quoted_as_arguments() {
+        let output = ConfigSetFormatter::heroku().format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "heroku config:set --app 'my app' \\",
+            "  'SPIN_VARIABLE_REGION=eu west' \\",
+            "  'SPIN_VARIABLE_API_KEY=it'\\''s s3cret' \\",
+            "  SPIN_VARIABLE_TOKEN=TO-DO",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn unknown_apps_are_left_to_fill_in() {
+        let output = ConfigSetFormatter::dokku().format(&deploy_variables(), &FormatContext::default()).unwrap();
+        assert!(output.starts_with("dokku config:set TO-DO \\\n"));
+    }
+}
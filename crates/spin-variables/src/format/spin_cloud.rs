@@ -0,0 +1,73 @@
+//! Commands for configuring variables of applications deployed to Fermyon Cloud,
+//! which takes variables as command line arguments rather than from the environment.
+
+use crate::app::VariableInfo;
+use crate::format::{app_name, multiline_command, settings, shell_quote, FormatContext, OutputFormatter};
+
+/// A `spin deploy` command setting each variable that doesn't fall back to its
+/// manifest default.
+pub struct DeployFormatter;
+
+impl OutputFormatter for DeployFormatter {
+    fn name(&self) -> &str {
+        "deploy"
+    }
+
+    fn description(&self) -> &str {
+        "a `spin deploy` command passing the values as --variable arguments"
+    }
+
+    fn format(&self, variables: &[VariableInfo], _context: &FormatContext) -> anyhow::Result<String> {
+        let args: Vec<_> = settings(variables).map(|(variable, value)| format!("--variable {}", shell_quote(&format!("{}={value}", variable.name)))).collect();
+        Ok(multiline_command("spin deploy", &args))
+    }
+}
+
+/// `spin cloud variables set` commands, one per variable that doesn't fall back to
+/// its manifest default, for configuring an app that is already deployed.
+pub struct CloudFormatter;
+
+impl OutputFormatter for CloudFormatter {
+    fn name(&self) -> &str {
+        "cloud"
+    }
+
+    fn description(&self) -> &str {
+        "`spin cloud variables set` commands for an app deployed to Fermyon Cloud"
+    }
+
+    fn format(&self, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+        let app = shell_quote(app_name(context));
+        let commands: Vec<_> = settings(variables)
+            .map(|(variable, value)| format!("spin cloud variables set --app {app} {}", shell_quote(&format!("{}={value}", variable.name))))
+            .collect();
+        Ok(commands.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::tests::{deploy_context, deploy_variables};
+
+    #[test]
+    fn deploy_passes_quoted_variables() {
+        let output = DeployFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "spin deploy \\",
+            "  --variable 'region=eu west' \\",
+            "  --variable 'api_key=it'\\''s s3cret' \\",
+            "  --variable token=TO-DO",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn cloud_sets_each_variable_on_the_app() {
+        let output = CloudFormatter.format(&deploy_variables(), &deploy_context()).unwrap();
+        assert_eq!(output, [
+            "spin cloud variables set --app 'my app' 'region=eu west'",
+            "spin cloud variables set --app 'my app' 'api_key=it'\\''s s3cret'",
+            "spin cloud variables set --app 'my app' token=TO-DO",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Random values for secret variables, for bootstrapping development and test
+//! environments.
+
+use crate::app::VariableInfo;
+use crate::values::{ResolvedValue, ValueSource};
+
+/// How to generate a secret value, given as `LENGTH[/CHARSET]` or `CHARSET`,
+/// such as `64/hex`. The default is 32 alphanumeric characters.
+#[derive(Clone, Copy, Debug)]
+pub struct SecretSpec {
+    pub length: usize,
+    pub charset: Charset,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Alphanumeric,
+    Hex,
+    /// The URL-safe base64 alphabet, `A-Z`, `a-z`, `0-9`, `-` and `_`.
+    Base64,
+}
+
+impl Charset {
+    fn chars(&self) -> &'static [u8] {
+        match self {
+            Self::Alphanumeric => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+            Self::Hex => b"0123456789abcdef",
+            Self::Base64 => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        }
+    }
+}
+
+impl Default for SecretSpec {
+    fn default() -> Self {
+        Self {
+            length: 32,
+            charset: Charset::Alphanumeric,
+        }
+    }
+}
+
+impl std::str::FromStr for Charset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alnum" | "alphanumeric" => Ok(Self::Alphanumeric),
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            _ => anyhow::bail!("Unknown character set '{s}'. The available sets are: alnum, hex, base64"),
+        }
+    }
+}
+
+impl std::str::FromStr for SecretSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let default = Self::default();
+        let (length, charset) = match s.split_once('/') {
+            Some((length, charset)) => (Some(length), Some(charset)),
+            None if s.chars().all(|c| c.is_ascii_digit()) => (Some(s), None),
+            None => (None, Some(s)),
+        };
+        let length = match length {
+            Some(length) => length.parse().map_err(|_| anyhow::anyhow!("'{length}' is not a valid length"))?,
+            None => default.length,
+        };
+        if length == 0 {
+            anyhow::bail!("Generated secrets must be at least one character long");
+        }
+        let charset = match charset {
+            Some(charset) => charset.parse()?,
+            None => default.charset,
+        };
+        Ok(Self { length, charset })
+    }
+}
+
+/// A random value, from the operating system's secure random number generator.
+pub fn generate(spec: &SecretSpec) -> String {
+    use rand::Rng;
+
+    let chars = spec.charset.chars();
+    let mut rng = rand::rngs::OsRng;
+    (0..spec.length).map(|_| chars[rng.gen_range(0..chars.len())] as char).collect()
+}
+
+/// Gives each secret variable that has no value a generated one, and returns
+/// the names of the variables given values.
+pub fn generate_missing_secrets(variables: &mut [VariableInfo], spec: &SecretSpec) -> Vec<String> {
+    let mut generated = vec![];
+    for variable in variables.iter_mut().filter(|v| v.secret && v.value.is_none()) {
+        variable.value = Some(ResolvedValue {
+            value: generate(spec),
+            source: ValueSource::Generated,
+        });
+        generated.push(variable.name.clone());
+    }
+    generated
+}
@@ -0,0 +1,443 @@
+//! Updating existing Kubernetes manifests with an application's variables.
+//!
+//! Manifests are edited line by line, so that comments and formatting outside the
+//! entries being set are kept. Only block-style YAML can be edited this way; files
+//! that use flow style (`env: [...]`) where entries need to go are rejected rather
+//! than rewritten.
+
+use std::ops::Range;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::kube::resource_name;
+use crate::format::{setting, FormatContext, Setting, PLACEHOLDER};
+
+/// Sets the application's variables in the SpinApps and Deployments of a
+/// (possibly multi-document) Kubernetes manifest. SpinApps get `spec.variables`
+/// entries; Deployments get container `env` entries. Secret variables refer to the
+/// Secret produced by `-o kube` rather than holding the value. Existing entries for
+/// the same variable are replaced, new ones are added at the end of the list, and
+/// everything else is left as it was.
+pub fn inject(manifest: &str, variables: &[VariableInfo], context: &FormatContext) -> anyhow::Result<String> {
+    let secret_name = resource_name(&context.app_name);
+    let newline = if manifest.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = manifest.lines().map(|l| l.to_owned()).collect();
+    let mut updated_any = false;
+
+    // Documents are edited from the last, so that earlier line numbers stay valid
+    for range in documents(&lines).into_iter().rev() {
+        let text = lines[range.clone()].join("\n");
+        let document: Value = serde_yaml::from_str(&text)?;
+        let mut expected = document.clone();
+        let mut edits = vec![];
+        match document.get("kind").and_then(|k| k.as_str()) {
+            Some("SpinApp") => {
+                let entries: Vec<_> = variables.iter()
+                    .filter_map(|v| Some((v.name.clone(), entry(v, &v.name, &secret_name)?)))
+                    .collect();
+                let spec = mapping_value(&lines, range.clone(), 0, "spec")?.ok_or_else(|| anyhow::anyhow!("A SpinApp has no spec"))?;
+                upsert_list(&lines, &mut edits, spec, "variables", &entries)?;
+                let expected_entries = list_at(&mut expected, &["spec", "variables"])?;
+                for (name, entry) in entries {
+                    upsert(expected_entries, &name, entry);
+                }
+            }
+            Some("Deployment") => {
+                let entries: Vec<_> = variables.iter().filter_map(|v| {
+                    let env_var_name = env::env_var_name(&context.env_prefix, &v.name);
+                    Some((env_var_name.clone(), entry(v, &env_var_name, &secret_name)?))
+                }).collect();
+                let mut mapping = Some((range.clone(), 0));
+                for key in ["spec", "template", "spec"] {
+                    mapping = match mapping {
+                        Some((range, indent)) => mapping_value(&lines, range, indent, key)?,
+                        None => None,
+                    };
+                }
+                let (spec, indent) = mapping.ok_or_else(|| anyhow::anyhow!("A Deployment has no spec.template.spec"))?;
+                let containers = find_key(&lines, spec, indent, "containers")?.ok_or_else(|| anyhow::anyhow!("A Deployment has no containers"))?;
+                let (_, items) = list_items(&lines, &containers)?;
+                for item in items {
+                    upsert_list(&lines, &mut edits, (item.range, item.indent), "env", &entries)?;
+                }
+                for container in list_at(&mut expected, &["spec", "template", "spec", "containers"])? {
+                    let expected_entries = list_at(container, &["env"])?;
+                    for (name, entry) in &entries {
+                        upsert(expected_entries, name, entry.clone());
+                    }
+                }
+            }
+            _ => continue,
+        }
+        updated_any = true;
+
+        let mut document_lines = lines[range.clone()].to_vec();
+        let offset = range.start;
+        edits.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+        for edit in edits {
+            document_lines.splice(edit.range.start - offset..edit.range.end - offset, edit.lines);
+        }
+
+        // Check the edit against a structural update, in case the layout was one the
+        // line editing misread
+        let edited: Value = serde_yaml::from_str(&document_lines.join("\n"))?;
+        if edited != expected {
+            anyhow::bail!("The manifest's layout can't be updated safely. Try reformatting it in plain block style");
+        }
+        lines.splice(range, document_lines);
+    }
+
+    if !updated_any {
+        anyhow::bail!("No SpinApp or Deployment found to inject variables into");
+    }
+    let mut updated = lines.join(newline);
+    if manifest.ends_with('\n') {
+        updated.push_str(newline);
+    }
+    Ok(updated)
+}
+
+/// The `name`/`value` (or `valueFrom`) entry for a variable, or `None` if the
+/// manifest default applies.
+fn entry(variable: &VariableInfo, name: &str, secret_name: &str) -> Option<Value> {
+    let mut entry = Mapping::new();
+    entry.insert("name".into(), name.into());
+    if variable.secret {
+        if matches!(setting(variable), Setting::Default) {
+            return None;
+        }
+        let mut secret_key_ref = Mapping::new();
+        secret_key_ref.insert("name".into(), secret_name.into());
+        secret_key_ref.insert("key".into(), variable.name.as_str().into());
+        let mut value_from = Mapping::new();
+        value_from.insert("secretKeyRef".into(), secret_key_ref.into());
+        entry.insert("valueFrom".into(), value_from.into());
+    } else {
+        let value = match setting(variable) {
+            Setting::Value(value) => value,
+            Setting::Placeholder => PLACEHOLDER,
+            Setting::Default => return None,
+        };
+        entry.insert("value".into(), value.into());
+    }
+    Some(entry.into())
+}
+
+/// A replacement of some lines of the manifest. Insertions replace an empty range.
+struct Edit {
+    range: Range<usize>,
+    lines: Vec<String>,
+}
+
+/// A key of a block mapping, and the lines holding its value.
+struct KeyBlock {
+    /// The key's indentation.
+    indent: usize,
+    value: Range<usize>,
+}
+
+/// An item of a block sequence whose value is a mapping.
+struct Item {
+    range: Range<usize>,
+    /// The indentation of the mapping's keys.
+    indent: usize,
+    name: Option<String>,
+}
+
+/// The line ranges of the documents in a multi-document file.
+fn documents(lines: &[String]) -> Vec<Range<usize>> {
+    let mut documents = vec![];
+    let mut start = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if line == "---" || line.starts_with("--- ") {
+            documents.push(start..index);
+            start = index + 1;
+        }
+    }
+    documents.push(start..lines.len());
+    documents.retain(|range| lines[range.clone()].iter().any(|l| is_significant(l)));
+    documents
+}
+
+fn is_significant(line: &str) -> bool {
+    let content = line.trim_start();
+    !content.is_empty() && !content.starts_with('#')
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The text of a line from the given indentation, looking past the `- ` of
+/// sequence items that start on the line, or `None` if nothing starts there.
+fn content_at(line: &str, indent: usize) -> Option<&str> {
+    let mut position = indent_of(line);
+    while position < indent && line[position..].starts_with('-') {
+        let after = &line[position + 1..];
+        let spaces = after.len() - after.trim_start_matches(' ').len();
+        if spaces == 0 {
+            return None;
+        }
+        position += 1 + spaces;
+    }
+    (position == indent).then(|| &line[position..])
+}
+
+/// Splits `key: rest` into the (unquoted) key and the rest, without any comment.
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    let (key, rest) = content.split_once(':')?;
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let rest = rest.trim();
+    let rest = if rest.starts_with('#') { "" } else { rest };
+    Some((key.trim().trim_matches(|c| c == '"' || c == '\''), rest))
+}
+
+/// Finds a key among the keys at `indent` in a block mapping, and the lines of its
+/// value. Keys whose values are written inline can't be edited, so are an error.
+fn find_key(lines: &[String], range: Range<usize>, indent: usize, key: &str) -> anyhow::Result<Option<KeyBlock>> {
+    for index in range.clone() {
+        let Some((found, rest)) = content_at(&lines[index], indent).and_then(split_key) else {
+            continue;
+        };
+        if found != key {
+            continue;
+        }
+        if !rest.is_empty() {
+            anyhow::bail!("'{key}' on line {} is written inline, so it can't be updated in place. Write it in block style", index + 1);
+        }
+        let end = (index + 1..range.end).find(|&i| {
+            let line = &lines[i];
+            is_significant(line) && (indent_of(line) < indent || (indent_of(line) == indent && !line.trim_start().starts_with('-')))
+        }).unwrap_or(range.end);
+        return Ok(Some(KeyBlock { indent, value: index + 1..end }));
+    }
+    Ok(None)
+}
+
+/// The lines of a key's value as a block mapping, with the indentation of its keys.
+fn mapping_value(lines: &[String], range: Range<usize>, indent: usize, key: &str) -> anyhow::Result<Option<(Range<usize>, usize)>> {
+    let Some(block) = find_key(lines, range, indent, key)? else {
+        return Ok(None);
+    };
+    let first = block.value.clone().find(|&i| is_significant(&lines[i]));
+    match first.map(|i| indent_of(&lines[i])).filter(|i| *i > indent) {
+        Some(child_indent) => Ok(Some((block.value, child_indent))),
+        None => anyhow::bail!("Expected '{key}' to be a mapping"),
+    }
+}
+
+/// The items of a block sequence, and the indentation of their dashes.
+fn list_items(lines: &[String], block: &KeyBlock) -> anyhow::Result<(usize, Vec<Item>)> {
+    let significant: Vec<_> = block.value.clone().filter(|&i| is_significant(&lines[i])).collect();
+    let Some(&first) = significant.first() else {
+        return Ok((block.indent + 2, vec![]));
+    };
+    let dash_indent = indent_of(&lines[first]);
+    let starts: Vec<_> = significant.iter().copied()
+        .filter(|&i| indent_of(&lines[i]) == dash_indent && (lines[i].trim_start().starts_with("- ") || lines[i].trim() == "-"))
+        .collect();
+    if starts.first() != Some(&first) {
+        anyhow::bail!("Expected a list on line {}", first + 1);
+    }
+
+    let mut items = vec![];
+    for (n, &start) in starts.iter().enumerate() {
+        let next = starts.get(n + 1).copied().unwrap_or(block.value.end);
+        // Comments and blank lines before the next item are left with it
+        let end = (start..next).rev().find(|&i| is_significant(&lines[i])).map_or(start + 1, |i| i + 1);
+        let after_dash = &lines[start][dash_indent + 1..];
+        let indent = dash_indent + 1 + (after_dash.len() - after_dash.trim_start_matches(' ').len());
+        let text = std::iter::once(format!("{}{}", " ".repeat(indent), after_dash.trim_start()))
+            .chain(lines[start + 1..end].iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let name = serde_yaml::from_str::<Value>(&text).ok()
+            .and_then(|item| item.get("name")?.as_str().map(|n| n.to_owned()));
+        items.push(Item { range: start..end, indent, name });
+    }
+    Ok((dash_indent, items))
+}
+
+/// Replaces the entries of the list under `key` that have the same names, adds
+/// the others to its end, and adds the list if the mapping doesn't have it.
+fn upsert_list(lines: &[String], edits: &mut Vec<Edit>, (mapping, indent): (Range<usize>, usize), key: &str, entries: &[(String, Value)]) -> anyhow::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let Some(block) = find_key(lines, mapping.clone(), indent, key)? else {
+        let mut new_lines = vec![format!("{}{key}:", " ".repeat(indent))];
+        for (_, entry) in entries {
+            new_lines.extend(render_item(entry, indent + 2)?);
+        }
+        let end = trimmed_end(lines, mapping);
+        edits.push(Edit { range: end..end, lines: new_lines });
+        return Ok(());
+    };
+
+    let (dash_indent, items) = list_items(lines, &block)?;
+    let mut appended = vec![];
+    for (name, entry) in entries {
+        match items.iter().find(|item| item.name.as_deref() == Some(name)) {
+            Some(item) => edits.push(Edit { range: item.range.clone(), lines: render_item(entry, dash_indent)? }),
+            None => appended.extend(render_item(entry, dash_indent)?),
+        }
+    }
+    if !appended.is_empty() {
+        let end = trimmed_end(lines, block.value.start - 1..block.value.end);
+        edits.push(Edit { range: end..end, lines: appended });
+    }
+    Ok(())
+}
+
+/// The line after the last significant line of a range.
+fn trimmed_end(lines: &[String], range: Range<usize>) -> usize {
+    range.clone().rev().find(|&i| is_significant(&lines[i])).map_or(range.start, |i| i + 1)
+}
+
+/// A list entry as block-style lines, with its dash at `indent`.
+fn render_item(entry: &Value, indent: usize) -> anyhow::Result<Vec<String>> {
+    let text = serde_yaml::to_string(entry)?;
+    let padding = " ".repeat(indent);
+    Ok(text.lines().enumerate().map(|(index, line)| match index {
+        0 => format!("{padding}- {line}"),
+        _ => format!("{padding}  {line}"),
+    }).collect())
+}
+
+fn upsert(entries: &mut Vec<Value>, name: &str, entry: Value) {
+    match entries.iter_mut().find(|e| e.get("name").and_then(|n| n.as_str()) == Some(name)) {
+        Some(existing) => *existing = entry,
+        None => entries.push(entry),
+    }
+}
+
+/// The sequence at a path of mapping keys, creating it (and any missing mappings
+/// on the way) if it doesn't exist.
+fn list_at<'a>(value: &'a mut Value, path: &[&str]) -> anyhow::Result<&'a mut Vec<Value>> {
+    let mut current = value;
+    for (index, key) in path.iter().enumerate() {
+        let is_last = index == path.len() - 1;
+        let Some(mapping) = current.as_mapping_mut() else {
+            anyhow::bail!("Expected a mapping above '{key}'");
+        };
+        let default = if is_last { Value::Sequence(vec![]) } else { Value::Mapping(Mapping::new()) };
+        current = mapping.entry((*key).into()).or_insert(default);
+    }
+    match current.as_sequence_mut() {
+        Some(sequence) => Ok(sequence),
+        None => anyhow::bail!("Expected '{}' to be a list", path.join(".")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::{ResolvedValue, ValueSource};
+
+    fn variables() -> Vec<VariableInfo> {
+        let variable = |name: &str, value: &str, secret: bool| VariableInfo {
+            name: name.to_owned(),
+            default_value: None,
+            required: true,
+            secret,
+            value: Some(ResolvedValue { value: value.to_owned(), source: ValueSource::Set }),
+        };
+        vec![variable("log_level", "debug", false), variable("api_key", "sk-1234", true)]
+    }
+
+    fn context() -> FormatContext {
+        FormatContext { app_name: "my-app".to_owned(), ..Default::default() }
+    }
+
+    #[test]
+    fn spinapp_comments_and_other_entries_are_kept() {
+        let manifest = "\
+# The production deployment
+apiVersion: core.spinkube.dev/v1alpha1
+kind: SpinApp
+metadata:
+  name: my-app   # keep this name
+spec:
+  image: ghcr.io/me/app:1
+  variables:
+    # Old level
+    - name: log_level
+      value: info
+    - name: region   # set by ops
+      value: eu
+  replicas: 2
+";
+        let updated = inject(manifest, &variables(), &context()).unwrap();
+        assert_eq!(updated, "\
+# The production deployment
+apiVersion: core.spinkube.dev/v1alpha1
+kind: SpinApp
+metadata:
+  name: my-app   # keep this name
+spec:
+  image: ghcr.io/me/app:1
+  variables:
+    # Old level
+    - name: log_level
+      value: debug
+    - name: region   # set by ops
+      value: eu
+    - name: api_key
+      valueFrom:
+        secretKeyRef:
+          name: my-app-variables
+          key: api_key
+  replicas: 2
+");
+    }
+
+    #[test]
+    fn missing_lists_are_added() {
+        let manifest = "\
+kind: Deployment
+spec:
+  template:
+    spec:
+      containers:
+      - name: app   # the only container
+        image: app:1
+";
+        let updated = inject(manifest, &variables()[..1], &context()).unwrap();
+        assert_eq!(updated, "\
+kind: Deployment
+spec:
+  template:
+    spec:
+      containers:
+      - name: app   # the only container
+        image: app:1
+        env:
+          - name: SPIN_VARIABLE_LOG_LEVEL
+            value: debug
+");
+    }
+
+    #[test]
+    fn other_documents_are_untouched() {
+        let manifest = "kind: Service\nmetadata:\n  name: svc   # comment\n---\nkind: SpinApp\nspec:\n  image: app:1\n";
+        let updated = inject(manifest, &variables()[..1], &context()).unwrap();
+        assert!(updated.starts_with("kind: Service\nmetadata:\n  name: svc   # comment\n---\n"));
+        assert!(updated.ends_with("  image: app:1\n  variables:\n    - name: log_level\n      value: debug\n"));
+    }
+
+    #[test]
+    fn flow_style_lists_are_rejected() {
+        let manifest = "kind: SpinApp\nspec:\n  variables: [{name: log_level, value: info}]\n";
+        let error = inject(manifest, &variables(), &context()).unwrap_err();
+        assert!(error.to_string().contains("written inline"));
+    }
+
+    #[test]
+    fn manifests_without_targets_are_rejected() {
+        assert!(inject("kind: Service\n", &variables(), &context()).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Keeping variable values in the operating system's keychain, so that secrets
+//! on developer machines don't need to sit in plaintext dotenv files.
+//!
+//! Values are stored per application directory, under the `spin-variables` service.
+
+use std::path::{Path, PathBuf};
+
+use spin_expressions::{Key, Provider};
+
+/// The keychain service that values are stored under.
+pub const SERVICE: &str = "spin-variables";
+
+fn entry(app_dir: &Path, name: &str) -> anyhow::Result<keyring::Entry> {
+    let app_dir = std::fs::canonicalize(app_dir).unwrap_or_else(|_| app_dir.to_owned());
+    Ok(keyring::Entry::new(SERVICE, &format!("{}:{name}", app_dir.display()))?)
+}
+
+/// Stores a variable's value for the application, replacing any existing value.
+pub fn store(app_dir: &Path, name: &str, value: &str) -> anyhow::Result<()> {
+    Ok(entry(app_dir, name)?.set_password(value)?)
+}
+
+/// Removes a variable's value for the application, returning whether there was one.
+pub fn remove(app_dir: &Path, name: &str) -> anyhow::Result<bool> {
+    match entry(app_dir, name)?.delete_password() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Looks up values stored with [`store`] for an application.
+#[derive(Debug)]
+pub struct KeychainProvider {
+    app_dir: PathBuf,
+}
+
+impl KeychainProvider {
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            app_dir: app_dir.to_owned(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for KeychainProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        match entry(&self.app_dir, key.as_str())?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Inspecting the configuration variables of Spin applications.
+//!
+//! This is the logic behind the `spin variables` plugin: loading an application's
+//! variables from a manifest or registry ([`app`], [`registry`]), resolving their values
+//! ([`values`], [`providers`], [`env`]), analysing how they are used ([`usage`], [`triggers`], [`lint`], [`wasm_imports`]), and
+//! formatting them for output ([`format`]). [`edit`] changes their declarations in
+//! the manifest. [`query::VariablesQuery`] puts loading and resolution together for
+//! programmatic use.
+//!
+//! The default `native` feature adds loading from registries and resolving manifest
+//! paths the way Spin does. Without it, the crate builds for wasm32, for use in
+//! web tooling, and applications are loaded with [`app::app_from_toml_str`].
+
+pub mod app;
+#[cfg(feature = "native")]
+pub mod aws;
+#[cfg(feature = "native")]
+pub mod azure_key_vault;
+pub mod changes;
+pub mod check;
+pub mod cluster;
+pub mod consistency;
+#[cfg(feature = "native")]
+pub mod consul;
+pub mod edit;
+pub mod encryption;
+pub mod env;
+pub mod format;
+#[cfg(feature = "native")]
+pub mod generate;
+pub mod inject;
+#[cfg(feature = "native")]
+pub mod keychain;
+pub mod lint;
+pub mod locations;
+#[cfg(feature = "native")]
+pub mod oci_layout;
+pub mod preflight;
+pub mod profiles;
+pub mod providers;
+pub mod query;
+pub mod registry;
+pub mod reports;
+pub mod runtime_config;
+pub mod schema;
+#[cfg(feature = "native")]
+pub mod snapshots;
+#[cfg(feature = "native")]
+pub mod store;
+pub mod templates;
+pub mod triggers;
+pub mod usage;
+pub mod values;
+#[cfg(feature = "native")]
+pub mod vault;
+pub mod wasm_imports;
+
+pub use app::{AppInfo, ComponentInfo, TriggerInfo, VariableInfo};
+pub use query::VariablesQuery;
+
+/// The version of the data model in machine-readable outputs such as JSON. Adding
+/// fields does not change the version; removing fields, or changing their meaning
+/// or representation, does.
+pub const SCHEMA_VERSION: u32 = 1;
@@ -0,0 +1,453 @@
+use std::collections::HashSet;
+
+use crate::app::AppInfo;
+use crate::locations::{Location, ManifestLocator};
+use crate::usage::{self, SiteKind};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.rule.id(), self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => f.write_str("warning"),
+            Self::Error => f.write_str("error"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    UnusedVariable,
+    UndefinedVariable,
+    InvalidName,
+    InvalidTemplate,
+    InvalidManifest,
+    SecretExposure,
+    EnvNameCollision,
+    SecretDefault,
+    VariablesImportMismatch,
+}
+
+impl Rule {
+    pub const ALL: &'static [Rule] = &[
+        Self::UnusedVariable,
+        Self::UndefinedVariable,
+        Self::InvalidName,
+        Self::InvalidTemplate,
+        Self::InvalidManifest,
+        Self::SecretExposure,
+        Self::EnvNameCollision,
+        Self::SecretDefault,
+        Self::VariablesImportMismatch,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable => "unused-variable",
+            Self::UndefinedVariable => "undefined-variable",
+            Self::InvalidName => "invalid-name",
+            Self::InvalidTemplate => "invalid-template",
+            Self::InvalidManifest => "invalid-manifest",
+            Self::SecretExposure => "secret-exposure",
+            Self::EnvNameCollision => "env-name-collision",
+            Self::SecretDefault => "secret-default",
+            Self::VariablesImportMismatch => "variables-import-mismatch",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable => "Variable is declared but never used",
+            Self::UndefinedVariable => "Template refers to a variable that is not declared",
+            Self::InvalidName => "Variable name is not valid",
+            Self::InvalidTemplate => "Template is not valid",
+            Self::InvalidManifest => "Manifest could not be loaded",
+            Self::SecretExposure => "Secret variable may be exposed",
+            Self::EnvNameCollision => "Variables map to the same environment variable",
+            Self::SecretDefault => "Secret variable has a default in the manifest",
+            Self::VariablesImportMismatch => "Component's variables don't match whether its Wasm reads variables",
+        }
+    }
+}
+
+impl std::str::FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().find(|rule| rule.id() == s).copied().ok_or_else(|| {
+            let ids: Vec<_> = Self::ALL.iter().map(|rule| rule.id()).collect();
+            format!("unknown rule '{s}'. The rules are: {}", ids.join(", "))
+        })
+    }
+}
+
+/// How seriously to take a rule's findings, overriding its usual severity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Off,
+    Warning,
+    Error,
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("unknown level '{s}'. The levels are: off, warning, error")),
+        }
+    }
+}
+
+/// Parses a rule level override of the form `RULE=LEVEL`, such as `secret-default=warning`.
+pub fn parse_rule_level(s: &str) -> Result<(Rule, Level), String> {
+    let (rule, level) = s.split_once('=').ok_or_else(|| "expected RULE=LEVEL".to_owned())?;
+    Ok((rule.parse()?, level.parse()?))
+}
+
+/// Applies rule level overrides to findings, removing those whose rule is off.
+/// Later overrides for the same rule win.
+pub fn configure(findings: Vec<Finding>, levels: &[(Rule, Level)]) -> Vec<Finding> {
+    findings.into_iter().filter_map(|mut finding| {
+        match levels.iter().rev().find(|(rule, _)| *rule == finding.rule).map(|(_, level)| *level) {
+            Some(Level::Off) => return None,
+            Some(Level::Warning) => finding.severity = Severity::Warning,
+            Some(Level::Error) => finding.severity = Severity::Error,
+            None => {}
+        }
+        Some(finding)
+    }).collect()
+}
+
+/// Lints a loaded application. If the application came from a manifest file, pass
+/// a locator for it so that findings include their positions.
+pub fn lint(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let mut findings = vec![];
+    findings.extend(invalid_names(app, locator));
+    let names: Vec<_> = app.variables.iter().map(|v| (v.name.clone(), locator.and_then(|l| l.variable(&v.name)))).collect();
+    findings.extend(env_name_collisions(&names));
+    findings.extend(invalid_templates(app, locator));
+    findings.extend(unused_variables(app, locator));
+    findings.extend(undefined_variables(app, locator));
+    findings.extend(secret_exposures(app, locator));
+    findings.extend(secret_defaults(app, locator));
+    findings
+}
+
+/// Checks each component's Wasm against the manifest: a component that imports the
+/// variables interface but is given no variables can't read anything, and one given
+/// variables without importing it never reads them. Components whose Wasm is not a
+/// local file, or hasn't been built yet, are skipped.
+pub fn variables_import_mismatches(app: &AppInfo, locator: &ManifestLocator) -> Vec<Finding> {
+    let app_dir = locator.path().parent().unwrap_or(std::path::Path::new("."));
+    let mut findings = vec![];
+    for component in &app.components {
+        let Some((source, location)) = locator.component_source(&component.id) else {
+            continue;
+        };
+        let Ok(wasm) = std::fs::read(app_dir.join(&source)) else {
+            continue;
+        };
+        let imports_variables = match crate::wasm_imports::imports_variables(&wasm) {
+            Ok(imports_variables) => imports_variables,
+            Err(e) => {
+                findings.push(Finding {
+                    rule: Rule::VariablesImportMismatch,
+                    severity: Severity::Warning,
+                    message: format!("component '{}' source {source} could not be read as Wasm: {e}", component.id),
+                    location,
+                });
+                continue;
+            }
+        };
+        let message = match (imports_variables, component.variables.is_empty()) {
+            (true, true) => format!("component '{}' reads variables, but the manifest gives it none, so every lookup will fail", component.id),
+            (false, false) => format!("component '{}' has variables in the manifest, but its Wasm doesn't import a variables interface, so they are never read", component.id),
+            _ => continue,
+        };
+        findings.push(Finding {
+            rule: Rule::VariablesImportMismatch,
+            severity: Severity::Warning,
+            message,
+            location,
+        });
+    }
+    findings
+}
+
+/// Lints a manifest that Spin could not load, reporting what can be found from
+/// the raw TOML along with the load error.
+pub fn lint_unloadable(locator: &ManifestLocator, error: anyhow::Error) -> Vec<Finding> {
+    let collisions = env_name_collisions(&locator.variable_names());
+
+    let variable_names = locator.variable_names().into_iter().map(|(name, location)| (format!("variable '{name}'"), name, location));
+    let component_variable_names = locator.component_variable_names().into_iter()
+        .map(|(component, name, location)| (format!("component '{component}' variable '{name}'"), name, location));
+
+    let mut findings: Vec<_> = variable_names.chain(component_variable_names)
+        .filter_map(|(description, name, location)| invalid_name_finding(&description, &name, location))
+        .collect();
+    findings.extend(collisions);
+
+    findings.push(Finding {
+        rule: Rule::InvalidManifest,
+        severity: Severity::Error,
+        message: format!("the manifest could not be loaded, so other checks were skipped: {error:#}"),
+        location: None,
+    });
+    findings
+}
+
+fn invalid_names(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let variable_names = app.variables.iter()
+        .map(|v| (format!("variable '{}'", v.name), v.name.clone(), locator.and_then(|l| l.variable(&v.name))));
+    let component_variable_names = app.components.iter().flat_map(|c| {
+        c.variables.iter().map(move |(name, _)| (format!("component '{}' variable '{name}'", c.id), name.clone(), None))
+    });
+
+    variable_names.chain(component_variable_names)
+        .filter_map(|(description, name, location)| invalid_name_finding(&description, &name, location))
+        .collect()
+}
+
+fn invalid_name_finding(description: &str, name: &str, location: Option<Location>) -> Option<Finding> {
+    let problem = name_problem(name)?;
+    Some(Finding {
+        rule: Rule::InvalidName,
+        severity: Severity::Error,
+        message: format!("{description} is not a valid name: {problem}"),
+        location,
+    })
+}
+
+/// Spin variable names must be lower snake case. This is Spin's own check, so
+/// the problem is described in Spin's words.
+fn name_problem(name: &str) -> Option<String> {
+    crate::app::validate_variable_name(name).err()
+}
+
+/// Spin reads variables from environment variables named by uppercasing the variable
+/// name, so names that differ only in case end up sharing an environment variable,
+/// and one silently shadows the other.
+fn env_name_collisions(names: &[(String, Option<Location>)]) -> Vec<Finding> {
+    let mut findings = vec![];
+    for (index, (name, location)) in names.iter().enumerate() {
+        let earlier = names[..index].iter().find(|(other, _)| other.to_ascii_uppercase() == name.to_ascii_uppercase());
+        if let Some((other, _)) = earlier {
+            findings.push(Finding {
+                rule: Rule::EnvNameCollision,
+                severity: Severity::Error,
+                message: format!("variable '{name}' maps to the same environment variable as '{other}' ({}), so one will shadow the other", crate::env::env_var_name(crate::env::DEFAULT_PREFIX, name)),
+                location: location.clone(),
+            });
+        }
+    }
+    findings
+}
+
+fn invalid_templates(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let mut findings = vec![];
+    for site in usage::template_sites(app) {
+        let problem = match spin_expressions::Template::new(site.template) {
+            Err(e) => Some(e.to_string()),
+            Ok(_) => usage::references(site.template).into_iter().find_map(|name| {
+                spin_expressions::Key::new(name).err().map(|e| format!("'{name}' is not a valid variable reference: {e}"))
+            }),
+        };
+        if let Some(problem) = problem {
+            findings.push(Finding {
+                rule: Rule::InvalidTemplate,
+                severity: Severity::Error,
+                message: format!("{site} has an invalid template: {problem}"),
+                location: locator.and_then(|l| l.template_site(&site)),
+            });
+        }
+    }
+    findings
+}
+
+fn unused_variables(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let sites = usage::template_sites(app);
+    let referenced: HashSet<_> = sites.iter().flat_map(|site| usage::references(site.template)).collect();
+
+    app.variables.iter()
+        .filter(|variable| !referenced.contains(variable.name.as_str()))
+        .map(|variable| Finding {
+            rule: Rule::UnusedVariable,
+            severity: Severity::Warning,
+            message: format!("variable '{}' is declared but not used by any component, outbound host or trigger", variable.name),
+            location: locator.and_then(|l| l.variable(&variable.name)),
+        })
+        .collect()
+}
+
+/// Spin only detects these when the template is resolved, which may not be until
+/// a request is handled.
+fn undefined_variables(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let mut findings = vec![];
+    for site in usage::template_sites(app) {
+        for name in usage::references(site.template) {
+            if !app.variables.iter().any(|v| v.name == name) {
+                findings.push(Finding {
+                    rule: Rule::UndefinedVariable,
+                    severity: Severity::Error,
+                    message: format!("{site} refers to '{name}', which is not declared in [variables]"),
+                    location: locator.and_then(|l| l.template_site(&site)),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Defaults are part of the manifest, so they end up in source control and in
+/// registries, where anyone who can read the application can see them.
+fn secret_defaults(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    app.variables.iter()
+        .filter(|variable| variable.secret && variable.default_value.is_some())
+        .map(|variable| Finding {
+            rule: Rule::SecretDefault,
+            severity: Severity::Error,
+            message: format!("secret variable '{}' has a default, which is stored in plaintext in source control and registries", variable.name),
+            location: locator.and_then(|l| l.variable(&variable.name)),
+        })
+        .collect()
+}
+
+/// Outbound hosts and trigger settings are not treated as secret by Spin, and may
+/// show up in logs and error messages. Component variables have no secret flag of
+/// their own, so those are judged by name, the same way `import-env` guesses which
+/// variables are secret: a secret in `greeting` is probably a mistake, but one in
+/// `api_key` is how secrets are meant to reach a component.
+fn secret_exposures(app: &AppInfo, locator: Option<&ManifestLocator>) -> Vec<Finding> {
+    let mut findings = vec![];
+    for site in usage::template_sites(app) {
+        let reason = match site.kind {
+            SiteKind::VariableDefault => continue,
+            SiteKind::ComponentVariable if crate::app::looks_secret(&site.key) => continue,
+            SiteKind::ComponentVariable => "its name doesn't look secret, so the component may treat the value as ordinary configuration",
+            SiteKind::OutboundHost | SiteKind::TriggerSetting => "is not a secret context so the value may appear in logs",
+        };
+        for name in usage::references(site.template) {
+            if app.variables.iter().any(|v| v.name == name && v.secret) {
+                findings.push(Finding {
+                    rule: Rule::SecretExposure,
+                    severity: Severity::Warning,
+                    message: format!("{site} uses secret variable '{name}', but {reason}"),
+                    location: locator.and_then(|l| l.template_site(&site)),
+                });
+            }
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"spin_manifest_version = 2
+
+[application]
+name = "test"
+
+[variables]
+api_key = { required = true, secret = true }
+token = { default = "abc", secret = true }
+host = { default = "example.com" }
+base_url = { default = "https://{{ host }}" }
+unused = { default = "x" }
+
+[[trigger.http]]
+route = "/..."
+component = "web"
+
+[component.web]
+source = "web.wasm"
+allowed_outbound_hosts = ["https://{{ api_key }}.example.com", "{{ base_url }}"]
+
+[component.web.variables]
+key = "{{ api_key }}"
+token = "{{ token }}"
+missing = "{{ nope }}"
+broken = "{{ host"
+greeting = "hello {{ api_key }}"
+"#;
+
+    fn findings(rule: Rule) -> Vec<String> {
+        let app = crate::app::app_from_toml_str(MANIFEST).unwrap();
+        let locator = ManifestLocator::parse("spin.toml", MANIFEST.to_owned()).unwrap();
+        lint(&app, Some(&locator)).into_iter().filter(|f| f.rule == rule).map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn variables_used_only_by_other_defaults_are_used() {
+        assert_eq!(findings(Rule::UnusedVariable), ["spin.toml:11:1: warning[unused-variable]: variable 'unused' is declared but not used by any component, outbound host or trigger"]);
+    }
+
+    #[test]
+    fn templates_must_refer_to_declared_variables() {
+        assert_eq!(findings(Rule::UndefinedVariable), ["spin.toml:24:11: error[undefined-variable]: component 'web' variable 'missing' refers to 'nope', which is not declared in [variables]"]);
+        let invalid = findings(Rule::InvalidTemplate);
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].starts_with("spin.toml:25:10: error[invalid-template]: component 'web' variable 'broken' has an invalid template"));
+    }
+
+    #[test]
+    fn secrets_are_flagged_outside_secret_looking_component_variables() {
+        assert_eq!(findings(Rule::SecretExposure), [
+            "spin.toml:26:12: warning[secret-exposure]: component 'web' variable 'greeting' uses secret variable 'api_key', but its name doesn't look secret, so the component may treat the value as ordinary configuration",
+            "spin.toml:19:27: warning[secret-exposure]: component 'web' allowed outbound host 'https://{{ api_key }}.example.com' uses secret variable 'api_key', but is not a secret context so the value may appear in logs",
+        ]);
+        assert_eq!(findings(Rule::SecretDefault), [
+            "spin.toml:8:1: error[secret-default]: secret variable 'token' has a default, which is stored in plaintext in source control and registries",
+        ]);
+    }
+
+    #[test]
+    fn unloadable_manifests_report_names_and_collisions() {
+        let locator = ManifestLocator::parse("spin.toml", "[variables]\napi_key = { default = \"a\" }\nAPI_KEY = { default = \"b\" }\n".to_owned()).unwrap();
+        let findings = lint_unloadable(&locator, anyhow::anyhow!("bad manifest"));
+        let rules: Vec<_> = findings.iter().map(|f| (f.rule, f.location.as_ref().map(|l| l.line))).collect();
+        assert_eq!(rules, [(Rule::InvalidName, Some(3)), (Rule::EnvNameCollision, Some(3)), (Rule::InvalidManifest, None)]);
+        assert!(findings[0].message.starts_with("variable 'API_KEY' is not a valid name: "));
+        assert!(findings[1].message.contains("SPIN_VARIABLE_API_KEY"));
+    }
+
+    #[test]
+    fn rule_levels_override_severities() {
+        let finding = |rule| Finding { rule, severity: Severity::Error, message: String::new(), location: None };
+        let levels = [parse_rule_level("secret-default=warning").unwrap(), parse_rule_level("unused-variable=off").unwrap(), parse_rule_level("secret-default=error").unwrap()];
+        let configured = configure(vec![finding(Rule::SecretDefault), finding(Rule::UnusedVariable), finding(Rule::InvalidName)], &levels);
+        let configured: Vec<_> = configured.iter().map(|f| (f.rule, f.severity)).collect();
+        assert_eq!(configured, [(Rule::SecretDefault, Severity::Error), (Rule::InvalidName, Severity::Error)]);
+        assert!(parse_rule_level("no-such-rule=off").unwrap_err().contains("unused-variable"));
+        assert!(parse_rule_level("secret-default").is_err());
+    }
+}
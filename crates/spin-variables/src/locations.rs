@@ -0,0 +1,317 @@
+//! Finding where things are in a spin.toml file, so that problems can be reported
+//! with line and column positions.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::usage::{SiteKind, TemplateSite};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Location {
+    pub path: PathBuf,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// The raw TOML of a manifest, with span information retained. This does not
+/// depend on the manifest being valid as far as Spin is concerned.
+///
+/// Both manifest versions are understood: version 1 declares components as an
+/// array of tables (`[[component]]` with an `id`), and their variables in `config`.
+pub struct ManifestLocator {
+    path: PathBuf,
+    doc: toml_edit::ImDocument<String>,
+}
+
+impl ManifestLocator {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(path, text)
+    }
+
+    /// A locator for manifest text, such as an unsaved buffer. Locations are reported
+    /// against the given path.
+    pub fn parse(path: impl Into<PathBuf>, text: String) -> anyhow::Result<Self> {
+        let path = path.into();
+        let doc = toml_edit::ImDocument::parse(text).with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self {
+            path,
+            doc,
+        })
+    }
+
+    /// The names declared in `[variables]`, with their locations.
+    pub fn variable_names(&self) -> Vec<(String, Option<Location>)> {
+        self.keys_of(self.variables_table())
+    }
+
+    /// The names declared in each component's `variables` table, with their locations.
+    pub fn component_variable_names(&self) -> Vec<(String, String, Option<Location>)> {
+        let mut names = vec![];
+        for (component_id, component) in self.components() {
+            for (name, location) in self.keys_of(component_variables(component)) {
+                names.push((component_id.clone(), name, location));
+            }
+        }
+        names
+    }
+
+    /// The descriptions of variables, from the comments directly above their entries in
+    /// `[variables]` or their `[variables.<name>]` tables. Comment lines are joined
+    /// with spaces.
+    pub fn variable_descriptions(&self) -> Vec<(String, String)> {
+        let Some(variables) = self.variables_table() else {
+            return vec![];
+        };
+        variables.iter().filter_map(|(name, item)| {
+            let (key, _) = variables.get_key_value(name)?;
+            let prefix = match item {
+                toml_edit::Item::Table(table) => table.decor().prefix(),
+                _ => key.leaf_decor().prefix(),
+            }?;
+            let text = match prefix.as_str() {
+                Some(text) => text,
+                None => self.doc.raw().get(prefix.span()?)?,
+            };
+            Some((name.to_owned(), comment_block(text)?))
+        }).collect()
+    }
+
+    /// The location of a variable's declaration in `[variables]`.
+    pub fn variable(&self, name: &str) -> Option<Location> {
+        let (key, _) = self.variables_table()?.get_key_value(name)?;
+        self.location(key.span()?)
+    }
+
+    /// The location of the template at a usage site.
+    pub fn template_site(&self, site: &TemplateSite) -> Option<Location> {
+        match site.kind {
+            SiteKind::ComponentVariable => {
+                let component = self.component(&site.owner)?;
+                let value = component_variables(component)?.get(&site.key)?;
+                self.location(value.span()?)
+            }
+            SiteKind::OutboundHost => {
+                let component = self.component(&site.owner)?;
+                let hosts = component.get("allowed_outbound_hosts")?.as_array()?;
+                let host = hosts.iter().find(|h| h.as_str() == Some(site.template))?;
+                self.location(host.span()?)
+            }
+            SiteKind::TriggerSetting => {
+                let global_triggers = self.doc.as_table().get("application").and_then(|a| a.as_table_like()).and_then(|a| a.get("trigger"));
+                let triggers = self.doc.as_table().get("trigger");
+                let value = [triggers, global_triggers].into_iter().flatten().find_map(|item| find_string(item, site.template))?;
+                self.location(value.span()?)
+            }
+            SiteKind::VariableDefault => {
+                let variable = self.variables_table()?.get(&site.owner)?.as_table_like()?;
+                self.location(variable.get("default")?.span()?)
+            }
+        }
+    }
+
+    /// The manifest file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A component's Wasm file, as written in the manifest, and its location. This is
+    /// `None` if the component's source is a URL or registry package.
+    pub fn component_source(&self, id: &str) -> Option<(String, Option<Location>)> {
+        let source = self.component(id)?.get("source")?.as_value()?;
+        Some((source.as_str()?.to_owned(), self.location(source.span()?)))
+    }
+
+    fn variables_table(&self) -> Option<&dyn toml_edit::TableLike> {
+        self.doc.as_table().get("variables")?.as_table_like()
+    }
+
+    fn components(&self) -> Vec<(String, &dyn toml_edit::TableLike)> {
+        match self.doc.as_table().get("component") {
+            Some(toml_edit::Item::ArrayOfTables(components)) => components.iter()
+                .filter_map(|component| Some((component.get("id")?.as_str()?.to_owned(), component as &dyn toml_edit::TableLike)))
+                .collect(),
+            Some(components) => match components.as_table_like() {
+                Some(components) => components.iter()
+                    .filter_map(|(id, component)| Some((id.to_owned(), component.as_table_like()?)))
+                    .collect(),
+                None => vec![],
+            },
+            None => vec![],
+        }
+    }
+
+    fn component(&self, id: &str) -> Option<&dyn toml_edit::TableLike> {
+        self.components().into_iter().find(|(component_id, _)| component_id == id).map(|(_, component)| component)
+    }
+
+    fn keys_of(&self, table: Option<&dyn toml_edit::TableLike>) -> Vec<(String, Option<Location>)> {
+        let Some(table) = table else {
+            return vec![];
+        };
+        table.iter()
+            .map(|(name, _)| {
+                let location = table.get_key_value(name).and_then(|(key, _)| key.span()).and_then(|span| self.location(span));
+                (name.to_owned(), location)
+            })
+            .collect()
+    }
+
+    fn location(&self, span: Range<usize>) -> Option<Location> {
+        let text = self.doc.raw();
+        let before = text.get(..span.start)?;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Some(Location {
+            path: self.path.clone(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        })
+    }
+}
+
+/// The text of the comment lines at the end of some whitespace and comments, or `None`
+/// if there are none. A blank line ends the block, so a comment separated from an
+/// entry by a blank line isn't taken as describing it.
+fn comment_block(text: &str) -> Option<String> {
+    let mut lines: Vec<_> = text.split('\n').map(|line| line.trim()).collect();
+    // The last piece is the indentation on the entry's own line, not a blank line
+    lines.pop();
+    let comments: Vec<_> = lines.iter().rev()
+        .map_while(|line| line.strip_prefix('#'))
+        .map(|comment| comment.trim())
+        .collect();
+    let description = comments.into_iter().rev().filter(|c| !c.is_empty()).collect::<Vec<_>>().join(" ");
+    Some(description).filter(|d| !d.is_empty())
+}
+
+/// A component's variables: `variables` in version 2 manifests, `config` in version 1.
+fn component_variables(component: &dyn toml_edit::TableLike) -> Option<&dyn toml_edit::TableLike> {
+    component.get("variables").or_else(|| component.get("config"))?.as_table_like()
+}
+
+/// Finds a string value anywhere within an item.
+fn find_string<'a>(item: &'a toml_edit::Item, text: &str) -> Option<&'a toml_edit::Value> {
+    match item {
+        toml_edit::Item::Value(value) => find_string_in_value(value, text),
+        toml_edit::Item::Table(table) => table.iter().find_map(|(_, item)| find_string(item, text)),
+        toml_edit::Item::ArrayOfTables(tables) => tables.iter().find_map(|table| table.iter().find_map(|(_, item)| find_string(item, text))),
+        toml_edit::Item::None => None,
+    }
+}
+
+fn find_string_in_value<'a>(value: &'a toml_edit::Value, text: &str) -> Option<&'a toml_edit::Value> {
+    match value {
+        toml_edit::Value::String(s) if s.value() == text => Some(value),
+        toml_edit::Value::Array(values) => values.iter().find_map(|v| find_string_in_value(v, text)),
+        toml_edit::Value::InlineTable(table) => table.iter().find_map(|(_, v)| find_string_in_value(v, text)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"spin_manifest_version = 2
+
+[application]
+name = "test"
+
+[variables]
+# The API key.
+# From the dashboard.
+api_key = { required = true, secret = true }
+
+# Not a description, because of the blank line.
+
+"région" = { default = "eu" }
+
+# Built from the host.
+[variables.base_url]
+default = "https://{{ host }}"
+
+[[trigger.http]]
+route = "/..."
+component = "web"
+executor = { type = "wagi", argv = "{{ region }}" }
+
+[component.web]
+source = "target/web.wasm"
+allowed_outbound_hosts = ["https://{{ host }}", "redis://{{ host }}"]
+
+[component.web.variables]
+key = "{{ api_key }}"
+"#;
+
+    fn locator(text: &str) -> ManifestLocator {
+        ManifestLocator::parse("spin.toml", text.to_owned()).unwrap()
+    }
+
+    fn position(location: Option<Location>) -> Option<(usize, usize)> {
+        location.map(|l| (l.line, l.column))
+    }
+
+    fn site<'a>(kind: SiteKind, owner: &str, key: &str, template: &'a str) -> TemplateSite<'a> {
+        TemplateSite { kind, owner: owner.to_owned(), key: key.to_owned(), template }
+    }
+
+    #[test]
+    fn variables_are_located_by_key() {
+        let locator = locator(MANIFEST);
+        assert_eq!(position(locator.variable("api_key")), Some((9, 1)));
+        assert_eq!(position(locator.variable("base_url")), Some((16, 12)));
+        assert_eq!(position(locator.variable("missing")), None);
+        let names: Vec<_> = locator.variable_names().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["api_key", "région", "base_url"]);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let locator = locator("[variables]\nx = { default = \"é\" }\n\"é\" = { default = \"{{ x }}\" }\n");
+        let default = site(SiteKind::VariableDefault, "é", "default", "{{ x }}");
+        assert_eq!(position(locator.template_site(&default)), Some((3, 19)));
+    }
+
+    #[test]
+    fn template_sites_are_located_at_their_values() {
+        let locator = locator(MANIFEST);
+        let sites = [
+            (site(SiteKind::ComponentVariable, "web", "key", "{{ api_key }}"), (29, 7)),
+            (site(SiteKind::OutboundHost, "web", "redis://{{ host }}", "redis://{{ host }}"), (26, 49)),
+            (site(SiteKind::TriggerSetting, "http", "executor.argv", "{{ region }}"), (22, 36)),
+            (site(SiteKind::VariableDefault, "base_url", "default", "https://{{ host }}"), (17, 11)),
+        ];
+        for (site, expected) in sites {
+            assert_eq!(position(locator.template_site(&site)), Some(expected), "{site}");
+        }
+        assert_eq!(locator.component_source("web").map(|(source, l)| (source, position(l))), Some(("target/web.wasm".to_owned(), Some((25, 10)))));
+    }
+
+    #[test]
+    fn descriptions_come_from_comments_directly_above() {
+        let descriptions = locator(MANIFEST).variable_descriptions();
+        assert_eq!(descriptions, [
+            ("api_key".to_owned(), "The API key. From the dashboard.".to_owned()),
+            ("base_url".to_owned(), "Built from the host.".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn version_1_components_are_found_by_id() {
+        let locator = locator("spin_manifest_version = \"1\"\n\n[[component]]\nid = \"web\"\nsource = \"web.wasm\"\n[component.config]\nkey = \"{{ api_key }}\"\n");
+        let key = site(SiteKind::ComponentVariable, "web", "key", "{{ api_key }}");
+        assert_eq!(position(locator.template_site(&key)), Some((7, 7)));
+        assert_eq!(locator.component_variable_names().into_iter().map(|(c, n, _)| (c, n)).collect::<Vec<_>>(), [("web".to_owned(), "key".to_owned())]);
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading applications from OCI image layouts, as directories or tar archives,
+//! for artifacts moved between registries without a live connection (for example
+//! with `skopeo copy ... oci-archive:app.tar`).
+//!
+//! A Spin application's OCI config blob is its locked application, so only the
+//! index, manifest and config are read; component layers are left alone.
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::app::{self, AppInfo};
+
+/// The annotation OCI layouts use to name the tag of a manifest.
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+#[derive(serde::Deserialize)]
+struct Index {
+    manifests: Vec<Descriptor>,
+}
+
+#[derive(serde::Deserialize)]
+struct Manifest {
+    config: Descriptor,
+}
+
+#[derive(serde::Deserialize)]
+struct Descriptor {
+    digest: String,
+    #[serde(default)]
+    annotations: std::collections::HashMap<String, String>,
+}
+
+/// Loads an application from an OCI layout directory. If `tag` is given, this is
+/// the manifest with that `ref.name`, otherwise the layout must hold only one.
+#[tracing::instrument(level = "info")]
+pub fn app_from_oci_layout(dir: &Path, tag: Option<&str>) -> anyhow::Result<AppInfo> {
+    let read = |name: &str| {
+        let path = dir.join(name);
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+    load(read, tag).with_context(|| format!("Failed to load OCI layout {}", dir.display()))
+}
+
+/// Loads an application from a tar archive of an OCI layout.
+#[tracing::instrument(level = "info")]
+pub fn app_from_oci_archive(path: &Path, tag: Option<&str>) -> anyhow::Result<AppInfo> {
+    let read = |name: &str| read_archive_entry(path, name);
+    load(read, tag).with_context(|| format!("Failed to load OCI archive {}", path.display()))
+}
+
+fn load(read: impl Fn(&str) -> anyhow::Result<Vec<u8>>, tag: Option<&str>) -> anyhow::Result<AppInfo> {
+    let index: Index = serde_json::from_slice(&read("index.json")?).context("index.json is not a valid OCI index")?;
+    let descriptor = select_manifest(&index, tag)?;
+
+    let manifest: Manifest = serde_json::from_slice(&read(&blob_path(&descriptor.digest)?)?).context("The manifest is not a valid OCI manifest")?;
+    let locked_app = serde_json::from_slice(&read(&blob_path(&manifest.config.digest)?)?).context("The image config is not a Spin application")?;
+
+    Ok(app::app_from_locked(locked_app))
+}
+
+fn select_manifest<'a>(index: &'a Index, tag: Option<&str>) -> anyhow::Result<&'a Descriptor> {
+    match tag {
+        Some(tag) => index.manifests.iter()
+            .find(|m| m.annotations.get(REF_NAME_ANNOTATION).is_some_and(|name| name == tag))
+            .with_context(|| format!("The layout has no manifest tagged '{tag}'")),
+        None => match index.manifests.as_slice() {
+            [manifest] => Ok(manifest),
+            [] => anyhow::bail!("The layout has no manifests"),
+            _ => anyhow::bail!("The layout has several manifests. Choose one by adding ':<tag>'"),
+        },
+    }
+}
+
+/// The layout path of a blob, such as `blobs/sha256/abc...` for `sha256:abc...`.
+fn blob_path(digest: &str) -> anyhow::Result<String> {
+    let (algorithm, encoded) = digest.split_once(':').with_context(|| format!("'{digest}' is not a valid digest"))?;
+    Ok(format!("blobs/{algorithm}/{encoded}"))
+}
+
+/// Reads one file from a tar archive. Archives are read from the start each time,
+/// since only a few small entries are needed.
+fn read_archive_entry(archive: &Path, name: &str) -> anyhow::Result<Vec<u8>> {
+    let file = std::fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut tar = tar::Archive::new(file);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let is_match = {
+            let path = entry.path()?;
+            path.strip_prefix(".").unwrap_or(&path) == Path::new(name)
+        };
+        if is_match {
+            let mut data = vec![];
+            entry.read_to_end(&mut data)?;
+            return Ok(data);
+        }
+    }
+    anyhow::bail!("The archive has no {name}")
+}
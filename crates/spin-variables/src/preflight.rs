@@ -0,0 +1,56 @@
+//! Predicting whether `spin up` will start, by resolving what it resolves at
+//! startup: every required variable, and every template in component variables,
+//! allowed outbound hosts, and trigger settings that support templates.
+//!
+//! Values should be resolved only from the providers Spin would use (those in the
+//! runtime config file, or its default environment provider), not from
+//! `--set`, values files or profiles, which `spin up` knows nothing about.
+
+use crate::app::AppInfo;
+use crate::templates::{self, ExpansionError};
+use crate::triggers::TriggerRegistry;
+use crate::usage::{self, SiteKind};
+
+/// Something that would stop `spin up` from starting.
+pub enum Failure {
+    /// A required variable that none of the providers has a value for.
+    Unresolved { variable: String },
+    /// A template that can't be expanded, described by where it is.
+    Template { site: String, error: ExpansionError },
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unresolved { variable } => write!(f, "no provider resolves required variable '{variable}'"),
+            Self::Template { site, error } => write!(f, "{site} can't be resolved: {error}"),
+        }
+    }
+}
+
+/// The failures `spin up` would hit with the application's resolved values.
+/// Templates that fail only because a required variable is unresolved aren't
+/// reported separately, since that variable is reported already.
+pub fn preflight(app: &AppInfo, triggers: &TriggerRegistry) -> Vec<Failure> {
+    let mut failures: Vec<_> = app.variables.iter()
+        .filter(|v| v.required && v.value.is_none())
+        .map(|v| Failure::Unresolved { variable: v.name.clone() })
+        .collect();
+
+    // Spin doesn't expand defaults, so templated defaults are only this tool's concern
+    for site in usage::template_sites(app).into_iter().filter(|s| s.kind != SiteKind::VariableDefault) {
+        if site.kind == SiteKind::TriggerSetting {
+            let trigger_type = app.triggers.iter().find(|t| t.display_name() == site.owner).map(|t| t.trigger_type.as_str());
+            if trigger_type.and_then(|t| triggers.supports_templates(t, &site.key)) != Some(true) {
+                continue;
+            }
+        }
+        match templates::expand(site.template, &app.variables) {
+            Ok(_) => {}
+            Err(ExpansionError::NoValue(name)) if app.variables.iter().any(|v| v.name == name && v.required) => {}
+            Err(error) => failures.push(Failure::Template { site: site.to_string(), error }),
+        }
+    }
+
+    failures
+}
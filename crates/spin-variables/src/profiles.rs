@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::VariableInfo;
+use crate::values::ValuesFile;
+
+/// A named set of values, stored as a TOML values file under `.spin/variables`.
+pub struct Profile {
+    pub name: String,
+    pub values: ValuesFile,
+}
+
+impl Profile {
+    pub fn load(app_dir: &Path, name: &str) -> anyhow::Result<Self> {
+        let path = profile_path(app_dir, name)?;
+        if !path.exists() {
+            anyhow::bail!("Profile '{name}' does not exist (expected {})", path.display());
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            values: ValuesFile::load(&path)?,
+        })
+    }
+}
+
+/// Where profiles are stored for the application in the given directory.
+pub fn profiles_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join(".spin").join("variables")
+}
+
+/// The file for a profile. Names are file names, so they can't lead outside the
+/// profiles directory.
+pub fn profile_path(app_dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    validate_profile_name(name).map_err(|e| anyhow::anyhow!("'{name}' is not a valid profile name: {e}"))?;
+    Ok(profiles_dir(app_dir).join(format!("{name}.toml")))
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("names must not be empty".to_owned());
+    }
+    if name.chars().any(|c| std::path::is_separator(c) || c == '/' || c == '\\') {
+        return Err("names must not contain path separators".to_owned());
+    }
+    if name.contains("..") {
+        return Err("names must not contain '..'".to_owned());
+    }
+    Ok(())
+}
+
+/// The names of the application's profiles, in alphabetical order.
+pub fn profile_names(app_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let dir = profiles_dir(app_dir);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut names = vec![];
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                names.push(stem.to_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// The initial content of a new profile: a commented-out entry for each variable.
+pub fn skeleton(name: &str, variables: &[VariableInfo]) -> String {
+    let mut lines = vec![
+        format!("# Values for the '{name}' profile. Uncomment and edit the entries you need."),
+        "".to_owned(),
+    ];
+    lines.extend(variables.iter().map(|variable| {
+        let value = toml::Value::String(variable.default_value.clone().unwrap_or_default());
+        let note = match (variable.required, variable.secret) {
+            (true, true) => "required, secret",
+            (true, false) => "required",
+            (false, true) => "optional, secret",
+            (false, false) => "optional",
+        };
+        format!("# {} = {value}  # {note}", variable.name)
+    }));
+    lines.push("".to_owned());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_names_stay_in_the_profiles_directory() {
+        let app_dir = Path::new("app");
+        assert_eq!(profile_path(app_dir, "prod").unwrap(), profiles_dir(app_dir).join("prod.toml"));
+        assert!(profile_path(app_dir, "prod.eu-1").is_ok());
+        for name in ["", "../prod", "..", "a/b", "a\\b", "/etc/passwd", "prod..old"] {
+            assert!(profile_path(app_dir, name).is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::values::{ResolvedValue, ValueSource};
+
+/// An ordered list of value providers, each with a label saying where its values
+/// come from. Earlier providers take precedence over later ones.
+#[derive(Default)]
+pub struct ProviderChain {
+    providers: Vec<(ProviderLabel, Box<dyn Provider>)>,
+}
+
+impl ProviderChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a provider with lower precedence than those already in the chain.
+    pub fn push(&mut self, label: ProviderLabel, provider: impl Provider + 'static) {
+        self.providers.push((label, Box::new(provider)));
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &ProviderLabel> {
+        self.providers.iter().map(|(label, _)| label)
+    }
+
+    /// Looks up a variable in each provider in turn, returning the first value found.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get(&self, variable_name: &str) -> anyhow::Result<Option<ResolvedValue>> {
+        let key = Key::new(variable_name).with_context(|| format!("'{variable_name}' is not a valid variable name"))?;
+        for (label, provider) in &self.providers {
+            let value = provider.get(&key).await.with_context(|| format!("Failed to get '{variable_name}' from {label}"))?;
+            if let Some(value) = value {
+                return Ok(Some(ResolvedValue {
+                    value,
+                    source: label.source(variable_name),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Where a provider's values come from, for attributing resolved values.
+#[derive(Clone, Debug)]
+pub enum ProviderLabel {
+    /// Process environment variables with the given prefix.
+    Environment { prefix: String },
+    /// A dotenv file.
+    Dotenv(PathBuf),
+    /// Any other kind of provider, such as a secret store.
+    Named(String),
+}
+
+impl ProviderLabel {
+    fn source(&self, variable_name: &str) -> ValueSource {
+        match self {
+            Self::Environment { prefix } => ValueSource::Environment(crate::env::env_var_name(prefix, variable_name)),
+            Self::Dotenv(path) => ValueSource::Dotenv(path.clone()),
+            Self::Named(name) => ValueSource::Provider(name.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Environment { prefix } => write!(f, "environment variables ({prefix}_*)"),
+            Self::Dotenv(path) => write!(f, "dotenv file {}", path.display()),
+            Self::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A provider with a fixed set of values, for testing precedence.
+    #[derive(Debug)]
+    pub(crate) struct StaticProvider(pub Vec<(&'static str, &'static str)>);
+
+    #[async_trait::async_trait]
+    impl Provider for StaticProvider {
+        async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+            Ok(self.0.iter().find(|(name, _)| *name == key.as_str()).map(|(_, value)| value.to_string()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct FailingProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for FailingProvider {
+        async fn get(&self, _key: &Key) -> anyhow::Result<Option<String>> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    #[tokio::test]
+    async fn earlier_providers_win() {
+        let mut chain = ProviderChain::new();
+        chain.push(ProviderLabel::Named("first".to_owned()), StaticProvider(vec![("a", "first a")]));
+        chain.push(ProviderLabel::Environment { prefix: "MYAPP".to_owned() }, StaticProvider(vec![("a", "second a"), ("b", "second b")]));
+
+        assert_eq!(chain.get("a").await.unwrap(), Some(ResolvedValue { value: "first a".to_owned(), source: ValueSource::Provider("first".to_owned()) }));
+        assert_eq!(chain.get("b").await.unwrap(), Some(ResolvedValue { value: "second b".to_owned(), source: ValueSource::Environment("MYAPP_B".to_owned()) }));
+        assert_eq!(chain.get("c").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn errors_say_where_they_came_from() {
+        let mut chain = ProviderChain::new();
+        chain.push(ProviderLabel::Named("Vault".to_owned()), FailingProvider);
+        assert_eq!(chain.get("a").await.unwrap_err().to_string(), "Failed to get 'a' from Vault");
+        assert_eq!(chain.get("Not-Valid").await.unwrap_err().to_string(), "'Not-Valid' is not a valid variable name");
+    }
+
+    #[test]
+    fn labels_describe_the_source() {
+        assert_eq!(ProviderLabel::Environment { prefix: "SPIN_VARIABLE".to_owned() }.to_string(), "environment variables (SPIN_VARIABLE_*)");
+        assert_eq!(ProviderLabel::Dotenv(".env".into()).to_string(), "dotenv file .env");
+        assert_eq!(ProviderLabel::Dotenv(".env".into()).source("a"), ValueSource::Dotenv(".env".into()));
+    }
+}
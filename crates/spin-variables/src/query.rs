@@ -0,0 +1,129 @@
+use spin_expressions::Provider;
+
+use crate::app::{self, AppSource, VariableInfo};
+use crate::env::EnvProvider;
+use crate::profiles::Profile;
+use crate::providers::{ProviderChain, ProviderLabel};
+use crate::registry::RegistryOptions;
+use crate::values::{self, ValuesFile};
+
+/// Loads an application's variables and resolves their values, for embedders who
+/// want the CLI's behaviour without its plumbing.
+///
+/// ```ignore
+/// let variables = VariablesQuery::new()
+///     .source(AppSource::File("spin.toml".into()))
+///     .set("log_level", "debug")
+///     .resolve_with(ProviderLabel::Named("vault".into()), vault_provider)
+///     .filter(|v| v.required)
+///     .run()
+///     .await?;
+/// ```
+pub struct VariablesQuery {
+    source: AppSource,
+    registry: RegistryOptions,
+    set: Vec<(String, String)>,
+    values_files: Vec<ValuesFile>,
+    profile: Option<Profile>,
+    providers: ProviderChain,
+    filters: Vec<Box<dyn Fn(&VariableInfo) -> bool>>,
+}
+
+impl Default for VariablesQuery {
+    fn default() -> Self {
+        Self {
+            source: AppSource::File(app::DEFAULT_MANIFEST_FILE.into()),
+            registry: RegistryOptions::default(),
+            set: vec![],
+            values_files: vec![],
+            profile: None,
+            providers: ProviderChain::new(),
+            filters: vec![],
+        }
+    }
+}
+
+impl VariablesQuery {
+    /// A query for `spin.toml` in the current directory, with no values other than
+    /// manifest defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn source(mut self, source: AppSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Ignore server certificate errors when loading from a registry.
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.registry.insecure = insecure;
+        self
+    }
+
+    /// How to talk to the registry, including timeouts and retries.
+    pub fn registry_options(mut self, options: RegistryOptions) -> Self {
+        self.registry = options;
+        self
+    }
+
+    /// Sets a value, as with `--set`. Later values for the same name win.
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a values file, with precedence over files already added.
+    pub fn values_file(mut self, values_file: ValuesFile) -> Self {
+        self.values_files.push(values_file);
+        self
+    }
+
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Adds a provider, with lower precedence than providers already added.
+    pub fn resolve_with(mut self, label: ProviderLabel, provider: impl Provider + 'static) -> Self {
+        self.providers.push(label, provider);
+        self
+    }
+
+    /// Adds the process environment and dotenv file of an `env` provider.
+    pub fn resolve_with_env(mut self, env_provider: &EnvProvider) -> Self {
+        env_provider.add_to(&mut self.providers);
+        self
+    }
+
+    /// Keeps only the variables matching the predicate. Filters are applied after
+    /// resolution, so they can look at values.
+    pub fn filter(mut self, predicate: impl Fn(&VariableInfo) -> bool + 'static) -> Self {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    pub async fn run(self) -> anyhow::Result<Vec<VariableInfo>> {
+        let mut app = match &self.source {
+            AppSource::File(path) => app::app_from_toml(path).await?,
+            #[cfg(feature = "native")]
+            AppSource::Registry(reference) => app::app_from_registry(reference, &self.registry).await?,
+            #[cfg(feature = "native")]
+            AppSource::OciLayout { path, tag } => crate::oci_layout::app_from_oci_layout(path, tag.as_deref())?,
+            #[cfg(feature = "native")]
+            AppSource::OciArchive { path, tag } => crate::oci_layout::app_from_oci_archive(path, tag.as_deref())?,
+            #[cfg(not(feature = "native"))]
+            AppSource::Registry(_) | AppSource::OciLayout { .. } | AppSource::OciArchive { .. } => anyhow::bail!("Loading from a registry or OCI layout requires the 'native' feature"),
+        };
+
+        let sources = values::Sources {
+            set: &self.set,
+            values_files: &self.values_files,
+            profile: self.profile.as_ref(),
+            providers: &self.providers,
+        };
+        values::resolve_values(&mut app.variables, &sources).await?;
+
+        Ok(app.variables.into_iter().filter(|v| self.filters.iter().all(|f| f(v))).collect())
+    }
+}
@@ -0,0 +1,104 @@
+//! How registry operations behave on unreliable networks.
+//!
+//! Proxies are taken from the usual `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY`
+//! environment variables.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Settings for talking to a registry.
+#[derive(Clone, Debug)]
+pub struct RegistryOptions {
+    /// Ignore server certificate errors, and use HTTP rather than HTTPS.
+    pub insecure: bool,
+    /// How long each attempt at an operation may take, or `None` to wait indefinitely.
+    pub timeout: Option<Duration>,
+    /// How many times to retry a failed operation. Each retry waits twice as long
+    /// as the one before, starting from [`INITIAL_BACKOFF`].
+    pub retries: u32,
+    /// PEM files of extra certificate authorities to trust, for registries with
+    /// certificates from an internal CA.
+    pub ca_certs: Vec<PathBuf>,
+}
+
+/// The default for [`RegistryOptions::timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The default for [`RegistryOptions::retries`].
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// How long to wait before the first retry.
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+impl Default for RegistryOptions {
+    fn default() -> Self {
+        Self {
+            insecure: false,
+            timeout: Some(DEFAULT_TIMEOUT),
+            retries: DEFAULT_RETRIES,
+            ca_certs: vec![],
+        }
+    }
+}
+
+/// A registry client for the options, trusting the extra certificate authorities.
+#[cfg(feature = "native")]
+pub fn client(options: &RegistryOptions) -> anyhow::Result<oci_distribution::Client> {
+    use oci_distribution::client::{ClientConfig, ClientProtocol};
+
+    Ok(oci_distribution::Client::new(ClientConfig {
+        protocol: if options.insecure { ClientProtocol::Http } else { ClientProtocol::Https },
+        extra_root_certificates: root_certificates(options)?,
+        ..Default::default()
+    }))
+}
+
+/// The extra certificate authorities, in the form the registry client takes.
+#[cfg(feature = "native")]
+pub fn root_certificates(options: &RegistryOptions) -> anyhow::Result<Vec<oci_distribution::client::Certificate>> {
+    use anyhow::Context;
+
+    options.ca_certs.iter().map(|path| {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+        Ok(oci_distribution::client::Certificate {
+            encoding: oci_distribution::client::CertificateEncoding::Pem,
+            data,
+        })
+    }).collect()
+}
+
+/// Runs a registry operation, retrying with exponential backoff if it fails or
+/// times out. `what` describes the operation for diagnostics, such as
+/// "Pulling ghcr.io/example/app:1".
+#[cfg(feature = "native")]
+pub async fn with_retries<T, F, Fut>(options: &RegistryOptions, what: &str, mut operation: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let attempts = options.retries + 1;
+    let mut backoff = INITIAL_BACKOFF;
+
+    for attempt in 1..=attempts {
+        tracing::debug!(attempt, attempts, "{what}");
+        let result = match options.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, operation()).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("timed out after {}s (use --timeout to allow longer)", timeout.as_secs())),
+            },
+            None => operation().await,
+        };
+
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < attempts => {
+                tracing::warn!(attempt, attempts, retry_in_secs = backoff.as_secs(), "{what} failed: {e:#}. Retrying");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => return Err(e.context(format!("{what} failed after {attempts} attempt(s)"))),
+        }
+    }
+
+    unreachable!("there is always at least one attempt")
+}
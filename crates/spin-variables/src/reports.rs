@@ -0,0 +1,263 @@
+//! Test reports of check results, for CI systems to display.
+
+use crate::check::CheckResult;
+use crate::lint::{Finding, Severity};
+use crate::locations::{Location, ManifestLocator};
+
+/// A JUnit XML report with one test case per variable.
+pub fn junit(results: &[CheckResult], app_name: &str) -> String {
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    let suite_name = if app_name.is_empty() { "spin variables".to_owned() } else { format!("spin variables: {app_name}") };
+    let class_name = if app_name.is_empty() { "variables".to_owned() } else { format!("{app_name}.variables") };
+
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_owned(),
+        "<testsuites>".to_owned(),
+        format!(r#"  <testsuite name="{}" tests="{}" failures="{failures}">"#, xml_escape(&suite_name), results.len()),
+    ];
+    for result in results {
+        let test_case = format!(r#"    <testcase classname="{}" name="{}""#, xml_escape(&class_name), xml_escape(&result.variable.name));
+        match &result.problem {
+            None => lines.push(format!("{test_case}/>")),
+            Some(problem) => {
+                lines.push(format!("{test_case}>"));
+                lines.push(format!(r#"      <failure message="{}"/>"#, xml_escape(problem)));
+                lines.push("    </testcase>".to_owned());
+            }
+        }
+    }
+    lines.push("  </testsuite>".to_owned());
+    lines.push("</testsuites>".to_owned());
+    lines.join("\n")
+}
+
+/// A Test Anything Protocol report with one test per variable.
+pub fn tap(results: &[CheckResult]) -> String {
+    let mut lines = vec!["TAP version 13".to_owned(), format!("1..{}", results.len())];
+    for (index, result) in results.iter().enumerate() {
+        let number = index + 1;
+        match &result.problem {
+            None => lines.push(format!("ok {number} - {}", result.variable.name)),
+            Some(problem) => {
+                lines.push(format!("not ok {number} - {}", result.variable.name));
+                lines.push("  ---".to_owned());
+                // A JSON string is a YAML double-quoted scalar, with line breaks escaped
+                lines.push(format!("  message: {}", serde_json::Value::from(problem.as_str())));
+                lines.push("  ...".to_owned());
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// A SARIF log of lint findings, for code scanning tools to show against the
+/// manifest lines.
+pub fn sarif(findings: &[Finding]) -> anyhow::Result<String> {
+    let mut rules = vec![];
+    for finding in findings {
+        if !rules.contains(&finding.rule) {
+            rules.push(finding.rule);
+        }
+    }
+    let rules: Vec<_> = rules.iter().map(|rule| serde_json::json!({
+        "id": rule.id(),
+        "shortDescription": { "text": rule.description() },
+    })).collect();
+
+    let results: Vec<_> = findings.iter().map(|finding| {
+        let mut result = serde_json::json!({
+            "ruleId": finding.rule.id(),
+            "level": match finding.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            },
+            "message": { "text": finding.message },
+        });
+        if let Some(location) = &finding.location {
+            result["locations"] = serde_json::json!([{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": location.path.to_string_lossy().replace('\\', "/") },
+                    "region": { "startLine": location.line, "startColumn": location.column },
+                },
+            }]);
+        }
+        result
+    }).collect();
+
+    let log = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "spin-variables",
+                    "informationUri": "https://github.com/itowlson/spin-variables",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    Ok(serde_json::to_string_pretty(&log)?)
+}
+
+/// GitHub Actions workflow commands that annotate the manifest with the variables
+/// that failed the check.
+pub fn github_check(results: &[CheckResult], locator: Option<&ManifestLocator>) -> String {
+    let annotations: Vec<_> = results.iter().filter_map(|result| {
+        let (severity, message) = match (&result.problem, &result.warning) {
+            (Some(problem), _) => (Severity::Error, problem),
+            (None, Some(warning)) => (Severity::Warning, warning),
+            (None, None) => return None,
+        };
+        let location = locator.and_then(|l| l.variable(&result.variable.name));
+        Some(github_annotation(severity, location.as_ref(), &result.variable.name, message))
+    }).collect();
+    annotations.join("\n")
+}
+
+/// GitHub Actions workflow commands that annotate the manifest with lint findings.
+pub fn github_lint(findings: &[Finding]) -> String {
+    let annotations: Vec<_> = findings.iter()
+        .map(|f| github_annotation(f.severity, f.location.as_ref(), f.rule.id(), &f.message))
+        .collect();
+    annotations.join("\n")
+}
+
+/// GitHub Actions step outputs, in the `name=value` form of the `GITHUB_OUTPUT` file:
+/// `passed`, `missing_required` and `failed` (comma-separated variable names), and
+/// `variables_json`, the variables as a JSON array with secret values redacted.
+pub fn github_outputs(results: &[CheckResult]) -> anyhow::Result<String> {
+    let names = |f: &dyn Fn(&CheckResult) -> bool| results.iter().filter(|r| f(r)).map(|r| r.variable.name.as_str()).collect::<Vec<_>>().join(",");
+    let variables: Vec<_> = results.iter().map(|r| {
+        let variable = r.variable;
+        serde_json::json!({
+            "name": variable.name,
+            "required": variable.required,
+            "secret": variable.secret,
+            "value": variable.value.as_ref().map(|v| if variable.secret { crate::format::REDACTED } else { v.value.as_str() }),
+            "source": variable.value.as_ref().map(|v| v.source.to_string()),
+            "passed": r.passed(),
+        })
+    }).collect();
+
+    let outputs = [
+        ("passed", results.iter().all(|r| r.passed()).to_string()),
+        ("missing_required", names(&|r| r.variable.required && r.variable.value.is_none())),
+        ("failed", names(&|r| !r.passed())),
+        // Compact JSON escapes newlines, so this stays on one line
+        ("variables_json", serde_json::to_string(&variables)?),
+    ];
+    Ok(outputs.iter().map(|(name, value)| format!("{name}={value}\n")).collect())
+}
+
+fn github_annotation(severity: Severity, location: Option<&Location>, title: &str, message: &str) -> String {
+    let level = match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let mut properties = vec![];
+    if let Some(location) = location {
+        properties.push(format!("file={}", github_property(&location.path.to_string_lossy())));
+        properties.push(format!("line={}", location.line));
+        properties.push(format!("col={}", location.column));
+    }
+    properties.push(format!("title={}", github_property(title)));
+    format!("::{level} {}::{}", properties.join(","), github_data(message))
+}
+
+fn github_data(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn github_property(s: &str) -> String {
+    github_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Escapes text for XML attributes and content. Line breaks and tabs are written as
+/// character references, because parsers turn them into spaces in attribute values,
+/// and other control characters, which XML 1.0 can't represent, are replaced.
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\r' | '\t' => escaped.push_str(&format!("&#{};", c as u32)),
+            c if c.is_control() => escaped.push(char::REPLACEMENT_CHARACTER),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::VariableInfo;
+
+    fn variable(name: &str) -> VariableInfo {
+        VariableInfo {
+            name: name.to_owned(),
+            default_value: None,
+            required: true,
+            secret: false,
+            value: None,
+        }
+    }
+
+    fn result<'a>(variable: &'a VariableInfo, problem: Option<&str>) -> CheckResult<'a> {
+        CheckResult {
+            variable,
+            problem: problem.map(|p| p.to_owned()),
+            warning: None,
+        }
+    }
+
+    #[test]
+    fn junit_escapes_markup_and_line_breaks() {
+        let (ok, bad) = (variable("ok"), variable("bad"));
+        let report = junit(&[result(&ok, None), result(&bad, Some("<too> \"long\" & 'odd'\nsee docs\u{7}"))], "a&b");
+
+        assert!(report.contains(r#"<testsuite name="spin variables: a&amp;b" tests="2" failures="1">"#));
+        assert!(report.contains(r#"<testcase classname="a&amp;b.variables" name="ok"/>"#));
+        assert!(report.contains("<failure message=\"&lt;too&gt; &quot;long&quot; &amp; &apos;odd&apos;&#10;see docs\u{FFFD}\"/>"));
+        assert_eq!(report.lines().filter(|l| l.contains("<testcase")).count(), 2);
+    }
+    #[test]
+    fn tap_messages_are_valid_yaml() {
+        let (ok, bad) = (variable("ok"), variable("bad"));
+        let problem = "it's: \"odd\" # and\nspans lines \\ too";
+        let report = tap(&[result(&ok, None), result(&bad, Some(problem))]);
+        let lines: Vec<_> = report.lines().collect();
+
+        assert_eq!(&lines[..4], &["TAP version 13", "1..2", "ok 1 - ok", "not ok 2 - bad"]);
+        assert_eq!(lines[4], "  ---");
+        assert_eq!(lines[6], "  ...");
+        let diagnostics: serde_yaml::Value = serde_yaml::from_str(lines[5]).unwrap();
+        assert_eq!(diagnostics["message"].as_str(), Some(problem));
+    }
+
+    #[test]
+    fn github_annotations_escape_properties_and_data() {
+        let (ok, bad) = (variable("ok"), variable("bad"));
+        let mut warned = result(&ok, None);
+        warned.warning = Some("100% deprecated".to_owned());
+        let report = github_check(&[warned, result(&bad, Some("missing\r\nvalue"))], None);
+        assert_eq!(report, "::warning title=ok::100%25 deprecated\n::error title=bad::missing%0D%0Avalue");
+
+        let finding = Finding {
+            rule: crate::lint::Rule::UnusedVariable,
+            severity: Severity::Warning,
+            message: "variable 'a' is unused".to_owned(),
+            location: Some(Location { path: "apps/a,b:c/spin.toml".into(), line: 3, column: 1 }),
+        };
+        assert_eq!(
+            github_lint(&[finding]),
+            "::warning file=apps/a%2Cb%3Ac/spin.toml,line=3,col=1,title=unused-variable::variable 'a' is unused"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Reading the variables providers listed in a Spin runtime config file.
+
+use std::path::Path;
+
+use anyhow::Context;
+
+#[cfg(feature = "native")]
+use crate::providers::ProviderChain;
+#[cfg(feature = "native")]
+use crate::vault::VaultOptions;
+
+/// The `[[variables_provider]]` (or legacy `[[config_provider]]`) tables of a runtime
+/// config file, in the order Spin consults them.
+pub fn variables_providers(path: &Path) -> anyhow::Result<Vec<toml::Table>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read runtime config file {}", path.display()))?;
+    let runtime_config: toml::Table = toml::from_str(&text).with_context(|| format!("Failed to parse runtime config file {}", path.display()))?;
+
+    let provider_tables = ["variables_provider", "config_provider"]
+        .iter()
+        .filter_map(|key| runtime_config.get(*key))
+        .filter_map(|providers| providers.as_array())
+        .flatten()
+        .filter_map(|provider| provider.as_table())
+        .cloned()
+        .collect();
+    Ok(provider_tables)
+}
+
+/// The `type` of a variables provider table, such as `env` or `azure_key_vault`.
+pub fn provider_type(provider: &toml::Table) -> Option<&str> {
+    provider.get("type").and_then(|t| t.as_str())
+}
+
+/// Adds providers for the secret stores a runtime config file lists, such as Vault
+/// and Azure Key Vault, in the order it lists them. `env` providers are handled by
+/// [`crate::env::env_providers`].
+#[cfg(feature = "native")]
+pub fn add_secret_stores(path: &Path, vault_options: &VaultOptions, chain: &mut ProviderChain) -> anyhow::Result<()> {
+    for provider in variables_providers(path)? {
+        match provider_type(&provider) {
+            Some("vault") => {
+                let config = parse_provider(path, "vault", provider)?;
+                crate::vault::VaultProvider::new(config, vault_options)?.add_to(chain);
+            }
+            Some("azure_key_vault") => {
+                let config = parse_provider(path, "azure_key_vault", provider)?;
+                crate::azure_key_vault::AzureKeyVaultProvider::new(config)?.add_to(chain);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn parse_provider<T: serde::de::DeserializeOwned>(path: &Path, provider_type: &str, provider: toml::Table) -> anyhow::Result<T> {
+    toml::Value::Table(provider).try_into()
+        .with_context(|| format!("Invalid {provider_type} provider in runtime config file {}", path.display()))
+}
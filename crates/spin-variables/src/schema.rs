@@ -0,0 +1,307 @@
+//! Constraints on variable values, declared in a `variables.schema.toml` file
+//! alongside the manifest, since Spin manifests have nowhere to put them.
+//!
+//! ```toml
+//! [variables.port]
+//! type = "port"
+//! min = 1024
+//!
+//! [variables.api_key]
+//! min_length = 32
+//!
+//! [variables.tenant_id]
+//! pattern = "[a-z]{3}-[0-9]{4}"
+//!
+//! [variables.db_password]
+//! required_if = { db_auth = "password" }
+//!
+//! [variables.log_file]
+//! deprecated = "use log_target instead"
+//!
+//! [variables.log_level]
+//! allowed_values = ["debug", "info", "warn", "error"]
+//! description = "How much detail to log"
+//! ```
+//!
+//! Descriptions can also be written as comments directly above a variable's entry in
+//! the manifest; see [`Schema::add_descriptions`].
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::VariableInfo;
+
+/// The schema file name looked for in the application directory.
+pub const SCHEMA_FILE: &str = "variables.schema.toml";
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    #[serde(default)]
+    pub variables: BTreeMap<String, VariableSchema>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariableSchema {
+    #[serde(rename = "type")]
+    pub value_type: Option<ValueType>,
+    /// A regular expression that the whole value must match.
+    pub pattern: Option<String>,
+    /// The only values the variable may take.
+    pub allowed_values: Option<Vec<String>>,
+    /// The shortest the value may be, in characters.
+    pub min_length: Option<usize>,
+    /// The longest the value may be, in characters.
+    pub max_length: Option<usize>,
+    /// The smallest number the value may be.
+    pub min: Option<f64>,
+    /// The largest number the value may be.
+    pub max: Option<f64>,
+    /// Other variables' values which, if they all match, make this variable required.
+    pub required_if: Option<BTreeMap<String, String>>,
+    /// Whether the variable is on its way out: `true`, or a hint such as what to
+    /// use instead.
+    pub deprecated: Option<Deprecation>,
+    /// What the variable is for, shown in tables and generated documentation.
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Deprecation {
+    Flag(bool),
+    Hint(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    Int,
+    Bool,
+    Url,
+    Port,
+    /// A number followed by a unit, such as `500ms`, `30s`, `5m`, `1h` or `7d`.
+    Duration,
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int => f.write_str("an integer"),
+            Self::Bool => f.write_str("true or false"),
+            Self::Url => f.write_str("a URL"),
+            Self::Port => f.write_str("a port number"),
+            Self::Duration => f.write_str("a duration such as 30s"),
+        }
+    }
+}
+
+impl Schema {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read schema file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse schema file {}", path.display()))
+    }
+
+    /// Loads the given schema file, or else the application's `variables.schema.toml`
+    /// if there is one. An application without a schema has no constraints.
+    pub fn load_for_app(app_dir: &Path, path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => {
+                let default_path = default_path(app_dir);
+                if default_path.exists() {
+                    Self::load(default_path)
+                } else {
+                    Ok(Self::default())
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VariableSchema> {
+        self.variables.get(name)
+    }
+
+    pub fn description(&self, name: &str) -> Option<&str> {
+        self.get(name)?.description.as_deref()
+    }
+
+    /// Adds descriptions found elsewhere, such as in manifest comments, for variables
+    /// the schema doesn't describe. Descriptions in the schema file win.
+    pub fn add_descriptions(&mut self, descriptions: impl IntoIterator<Item = (String, String)>) {
+        for (name, description) in descriptions {
+            let variable = self.variables.entry(name).or_default();
+            if variable.description.is_none() {
+                variable.description = Some(description);
+            }
+        }
+    }
+
+    /// If the variable is deprecated, the hint for it (which may be empty).
+    pub fn deprecation(&self, name: &str) -> Option<&str> {
+        match self.get(name)?.deprecated.as_ref()? {
+            Deprecation::Flag(true) => Some(""),
+            Deprecation::Flag(false) => None,
+            Deprecation::Hint(hint) => Some(hint),
+        }
+    }
+}
+
+pub fn default_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SCHEMA_FILE)
+}
+
+impl VariableSchema {
+    /// If the `required_if` conditions are all met by the other variables' values,
+    /// a description of them.
+    pub fn required_condition(&self, variables: &[VariableInfo]) -> Option<String> {
+        let conditions = self.required_if.as_ref().filter(|c| !c.is_empty())?;
+        let met = conditions.iter().all(|(name, expected)| {
+            variables.iter().find(|v| &v.name == name).and_then(|v| v.value.as_ref()).is_some_and(|v| &v.value == expected)
+        });
+        met.then(|| conditions.iter().map(|(name, expected)| format!("'{name}' is '{expected}'")).collect::<Vec<_>>().join(" and "))
+    }
+
+    /// Checks a value against the constraints, describing the first one it breaks.
+    /// The description never includes the value, since it may be a secret.
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        if let Some(value_type) = self.value_type {
+            if !value_type.accepts(value) {
+                return Err(format!("the value is not {value_type}"));
+            }
+        }
+        if let Some(allowed_values) = &self.allowed_values {
+            if !allowed_values.iter().any(|v| v == value) {
+                return Err(format!("the value is not one of the allowed values ({})", allowed_values.join(", ")));
+            }
+        }
+        let length = value.chars().count();
+        if let Some(min_length) = self.min_length.filter(|min| length < *min) {
+            return Err(format!("the value is {length} characters long, but must be at least {min_length}"));
+        }
+        if let Some(max_length) = self.max_length.filter(|max| length > *max) {
+            return Err(format!("the value is {length} characters long, but must be at most {max_length}"));
+        }
+        if self.min.is_some() || self.max.is_some() {
+            let Ok(number) = value.parse::<f64>() else {
+                return Err("the value is not a number".to_owned());
+            };
+            if let Some(min) = self.min.filter(|min| number < *min) {
+                return Err(format!("the value is less than the minimum of {min}"));
+            }
+            if let Some(max) = self.max.filter(|max| number > *max) {
+                return Err(format!("the value is more than the maximum of {max}"));
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            let regex = regex::Regex::new(&format!("^(?:{pattern})$")).map_err(|e| format!("the schema pattern '{pattern}' is not valid: {e}"))?;
+            if !regex.is_match(value) {
+                return Err(format!("the value does not match the pattern '{pattern}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ValueType {
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Bool => value == "true" || value == "false",
+            Self::Url => url::Url::parse(value).is_ok(),
+            Self::Port => value.parse::<u16>().is_ok_and(|port| port > 0),
+            Self::Duration => is_duration(value),
+        }
+    }
+}
+
+fn is_duration(value: &str) -> bool {
+    let unit_start = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    !number.is_empty() && ["ms", "s", "m", "h", "d"].contains(&unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(toml: &str) -> VariableSchema {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn types_are_checked() {
+        let port = constraints(r#"type = "port""#);
+        assert!(port.validate("8080").is_ok());
+        assert!(port.validate("0").is_err());
+        assert!(port.validate("65536").is_err());
+
+        let duration = constraints(r#"type = "duration""#);
+        assert!(duration.validate("500ms").is_ok());
+        assert!(duration.validate("30").is_err());
+        assert!(duration.validate("s").is_err());
+
+        let flag = constraints(r#"type = "bool""#);
+        assert!(flag.validate("true").is_ok());
+        assert!(flag.validate("yes").is_err());
+    }
+
+    #[test]
+    fn allowed_values_are_checked() {
+        let level = constraints(r#"allowed_values = ["debug", "info"]"#);
+        assert!(level.validate("info").is_ok());
+        assert_eq!(level.validate("trace").unwrap_err(), "the value is not one of the allowed values (debug, info)");
+    }
+
+    #[test]
+    fn lengths_are_in_characters() {
+        let name = constraints("min_length = 2\nmax_length = 3");
+        assert!(name.validate("éé").is_ok());
+        assert!(name.validate("é").is_err());
+        assert!(name.validate("éééé").is_err());
+    }
+
+    #[test]
+    fn ranges_are_inclusive() {
+        let count = constraints("min = 1\nmax = 10");
+        assert!(count.validate("1").is_ok());
+        assert!(count.validate("10").is_ok());
+        assert!(count.validate("0.5").is_err());
+        assert!(count.validate("11").is_err());
+        assert_eq!(count.validate("many").unwrap_err(), "the value is not a number");
+    }
+
+    #[test]
+    fn patterns_match_the_whole_value() {
+        let tenant = constraints(r#"pattern = "[a-z]{3}-[0-9]{4}""#);
+        assert!(tenant.validate("abc-1234").is_ok());
+        assert!(tenant.validate("xabc-1234").is_err());
+        assert!(tenant.validate("abc-12345").is_err());
+        assert!(constraints(r#"pattern = "(""#).validate("x").unwrap_err().starts_with("the schema pattern"));
+    }
+
+    #[test]
+    fn errors_do_not_include_the_value() {
+        let secret = "hunter2-sekrit";
+        let schemas = [
+            r#"type = "int""#,
+            r#"allowed_values = ["a", "b"]"#,
+            "max_length = 3",
+            "min = 0",
+            r#"pattern = "[0-9]+""#,
+        ];
+        for schema in schemas {
+            let error = constraints(schema).validate(secret).unwrap_err();
+            assert!(!error.contains(secret), "'{error}' reveals the value");
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(toml::from_str::<VariableSchema>("maximum = 3").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+//! Saved copies of the variable environment, for reproducing an environment that
+//! worked at some earlier time.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::VariableInfo;
+use crate::env;
+use crate::format::shell_quote;
+use crate::profiles::profiles_dir;
+
+/// The environment variables with a prefix, as they were when the snapshot was taken.
+/// Values are stored as they are, so snapshots of secrets should be kept private.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    /// When the snapshot was taken, in RFC 3339 form.
+    pub taken_at: String,
+    pub env_prefix: String,
+    /// Environment variable names and their values.
+    pub values: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    /// Captures the process environment variables that have the prefix. If `declared`
+    /// is given, only those for the declared variables are captured.
+    pub fn capture(env_prefix: &str, declared: Option<&[VariableInfo]>) -> Self {
+        let prefix = format!("{env_prefix}_");
+        let declared_names: Option<Vec<_>> = declared.map(|variables| variables.iter().map(|v| env::env_var_name(env_prefix, &v.name)).collect());
+        let values = std::env::vars()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .filter(|(name, _)| declared_names.as_ref().map_or(true, |names| names.contains(name)))
+            .collect();
+        Self {
+            taken_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            env_prefix: env_prefix.to_owned(),
+            values,
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("{} is not a valid snapshot", path.display()))
+    }
+
+    /// Saves the snapshot in the application's snapshots directory, named by the time
+    /// it was taken, and returns the path.
+    pub fn save(&self, app_dir: &Path) -> anyhow::Result<PathBuf> {
+        let taken_at = chrono::DateTime::parse_from_rfc3339(&self.taken_at).context("The snapshot time is not valid")?;
+        let path = snapshot_path(app_dir, &taken_at.format("%Y%m%dT%H%M%SZ").to_string());
+        let dir = snapshots_dir(app_dir);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        std::fs::write(&path, toml::to_string(self)?).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// A script that sets the environment variables again when sourced.
+    pub fn script(&self) -> String {
+        let mut lines = vec![format!("# Snapshot of {}_* taken at {}", self.env_prefix, self.taken_at)];
+        lines.extend(self.values.iter().map(|(name, value)| format!("export {name}={}", shell_quote(value))));
+        lines.join("\n")
+    }
+}
+
+/// Where snapshots are stored for the application in the given directory.
+pub fn snapshots_dir(app_dir: &Path) -> PathBuf {
+    profiles_dir(app_dir).join("snapshots")
+}
+
+pub fn snapshot_path(app_dir: &Path, name: &str) -> PathBuf {
+    snapshots_dir(app_dir).join(format!("{name}.toml"))
+}
+
+/// The names of the application's snapshots, oldest first. Since they are named
+/// by time, the last is the latest.
+pub fn snapshot_names(app_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let dir = snapshots_dir(app_dir);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut names = vec![];
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                names.push(stem.to_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
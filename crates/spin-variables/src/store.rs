@@ -0,0 +1,98 @@
+//! A local database of variable values for an application, as a structured
+//! alternative to dotenv files for development and test values.
+//!
+//! The database is a SQLite file under `.spin/variables`, with one row per variable.
+//! Values are stored in plaintext, so production secrets belong elsewhere.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::profiles::profiles_dir;
+
+/// Where the application's store is kept.
+pub fn store_path(app_dir: &Path) -> PathBuf {
+    profiles_dir(app_dir).join("store.db")
+}
+
+/// A stored value, with when it was last set in RFC 3339 form.
+pub struct StoredValue {
+    pub name: String,
+    pub value: String,
+    pub updated_at: String,
+}
+
+pub struct ValueStore {
+    connection: rusqlite::Connection,
+}
+
+impl ValueStore {
+    /// Opens the application's store, creating it if it doesn't exist.
+    pub fn open(app_dir: &Path) -> anyhow::Result<Self> {
+        let path = store_path(app_dir);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let connection = rusqlite::Connection::open(&path).with_context(|| format!("Failed to open store {}", path.display()))?;
+        connection.execute("CREATE TABLE IF NOT EXISTS variable_values (name TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at TEXT NOT NULL)", ())?;
+        Ok(Self { connection })
+    }
+
+    /// Sets a variable's value, replacing any existing value.
+    pub fn set(&self, name: &str, value: &str) -> anyhow::Result<()> {
+        let updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        self.connection.execute(
+            "INSERT INTO variable_values (name, value, updated_at) VALUES (?1, ?2, ?3) ON CONFLICT(name) DO UPDATE SET value = ?2, updated_at = ?3",
+            (name, value, updated_at),
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> anyhow::Result<Option<String>> {
+        use rusqlite::OptionalExtension;
+        Ok(self.connection.query_row("SELECT value FROM variable_values WHERE name = ?1", [name], |row| row.get(0)).optional()?)
+    }
+
+    /// Removes a variable's value, returning whether there was one.
+    pub fn remove(&self, name: &str) -> anyhow::Result<bool> {
+        Ok(self.connection.execute("DELETE FROM variable_values WHERE name = ?1", [name])? > 0)
+    }
+
+    /// Every stored value, in name order.
+    pub fn list(&self) -> anyhow::Result<Vec<StoredValue>> {
+        let mut statement = self.connection.prepare("SELECT name, value, updated_at FROM variable_values ORDER BY name")?;
+        let rows = statement.query_map((), |row| Ok(StoredValue {
+            name: row.get(0)?,
+            value: row.get(1)?,
+            updated_at: row.get(2)?,
+        }))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Looks up values in an application's store. Connections can't be shared between
+/// threads, so the store is opened for each lookup; an application without a store
+/// has no values.
+#[derive(Debug)]
+pub struct StoreProvider {
+    app_dir: PathBuf,
+}
+
+impl StoreProvider {
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            app_dir: app_dir.to_owned(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for StoreProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        if !store_path(&self.app_dir).exists() {
+            return Ok(None);
+        }
+        ValueStore::open(&self.app_dir)?.get(key.as_str())
+    }
+}
@@ -0,0 +1,143 @@
+//! Spin's `{{ variable }}` template syntax, as used in component variables,
+//! `allowed_outbound_hosts` and trigger settings.
+
+use crate::app::VariableInfo;
+
+pub enum Part<'a> {
+    Literal(&'a str),
+    Expression(&'a str),
+}
+
+pub fn parse(template: &str) -> anyhow::Result<Vec<Part<'_>>> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            parts.push(Part::Literal(&rest[..start]));
+        }
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            anyhow::bail!("unterminated template expression in '{template}'");
+        };
+        parts.push(Part::Expression(after_open[..end].trim()));
+        rest = &after_open[end + 2..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest));
+    }
+    Ok(parts)
+}
+
+/// Renders a template using the resolved values of application variables.
+pub fn expand(template: &str, variables: &[VariableInfo]) -> Result<String, ExpansionError> {
+    let parts = parse(template).map_err(|e| ExpansionError::Syntax(e.to_string()))?;
+    let mut expanded = String::new();
+    for part in parts {
+        match part {
+            Part::Literal(text) => expanded.push_str(text),
+            Part::Expression(name) => {
+                let Some(variable) = variables.iter().find(|v| v.name == name) else {
+                    return Err(ExpansionError::Undefined(name.to_owned()));
+                };
+                let Some(value) = &variable.value else {
+                    return Err(ExpansionError::NoValue(name.to_owned()));
+                };
+                expanded.push_str(&value.value);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+pub enum ExpansionError {
+    Syntax(String),
+    Undefined(String),
+    NoValue(String),
+}
+
+impl std::fmt::Display for ExpansionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(message) => write!(f, "invalid template: {message}"),
+            Self::Undefined(name) => write!(f, "'{name}' is not an application variable"),
+            Self::NoValue(name) => write!(f, "'{name}' has no value"),
+        }
+    }
+}
+
+/// Whether a string contains template expressions or environment variable references.
+/// A `$` on its own, as in a password or regular expression, is not a reference.
+pub fn is_templated(s: &str) -> bool {
+    s.contains("{{") || find_env_ref(s).is_some()
+}
+
+/// Expands `${env:NAME}` references from the process environment.
+pub fn expand_env_refs(s: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = s;
+    while let Some((start, name, len)) = find_env_ref(rest) {
+        expanded.push_str(&rest[..start]);
+        let value = std::env::var(name).map_err(|_| format!("environment variable {name} is not set"))?;
+        expanded.push_str(&value);
+        rest = &rest[start + len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Finds the first `${env:NAME}` reference, returning its start offset, the
+/// variable name, and the length of the whole reference.
+fn find_env_ref(s: &str) -> Option<(usize, &str, usize)> {
+    const OPEN: &str = "${env:";
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    for (start, _) in s.match_indices(OPEN) {
+        let after = &s[start + OPEN.len()..];
+        if let Some(end) = after.find('}') {
+            let name = &after[..end];
+            if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(is_name_char) {
+                return Some((start, name, OPEN.len() + end + 1));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::{ResolvedValue, ValueSource};
+
+    #[test]
+    fn literal_dollars_are_not_templates() {
+        assert!(!is_templated("pa$$word"));
+        assert!(!is_templated("$HOME/data"));
+        assert!(!is_templated("^[a-z]+$"));
+        assert!(!is_templated("${HOME}"));
+        assert!(!is_templated("${env:}"));
+        assert!(!is_templated("${env:1ABC}"));
+        assert_eq!(expand_env_refs("cost: $5 or ${PRICE}").unwrap(), "cost: $5 or ${PRICE}");
+    }
+
+    #[test]
+    fn env_refs_are_explicit() {
+        assert!(is_templated("${env:HOME}/data"));
+        assert!(is_templated("{{ base }}/v1"));
+        std::env::set_var("SPIN_VARIABLES_TEST_REGION", "eu");
+        assert_eq!(expand_env_refs("db.${env:SPIN_VARIABLES_TEST_REGION}.example.com:$1").unwrap(), "db.eu.example.com:$1");
+        assert!(expand_env_refs("${env:SPIN_VARIABLES_TEST_UNSET}").unwrap_err().contains("SPIN_VARIABLES_TEST_UNSET"));
+    }
+
+    #[test]
+    fn templates_expand_resolved_values() {
+        let variables = vec![VariableInfo {
+            name: "host".to_owned(),
+            default_value: None,
+            required: true,
+            secret: false,
+            value: Some(ResolvedValue { value: "example.com".to_owned(), source: ValueSource::Set }),
+        }];
+        assert_eq!(expand("https://{{ host }}/{{host}}", &variables).ok().unwrap(), "https://example.com/example.com");
+        assert!(matches!(expand("{{ port }}", &variables), Err(ExpansionError::Undefined(name)) if name == "port"));
+        assert!(matches!(expand("{{ host", &variables), Err(ExpansionError::Syntax(_))));
+    }
+}
@@ -0,0 +1,102 @@
+//! What is known about each trigger type's settings, such as which of them Spin
+//! expands templates in.
+//!
+//! Each trigger type is described by a [`TriggerKnowledge`]. The [`TriggerRegistry`]
+//! holds the built-in trigger types, and custom triggers can be registered
+//! alongside them.
+
+/// Knowledge of one trigger type's settings.
+pub trait TriggerKnowledge {
+    /// The trigger type, as in `[[trigger.<type>]]`.
+    fn trigger_type(&self) -> &str;
+
+    /// Whether templates in the setting are expanded. `setting` is the dotted path
+    /// within the trigger config, as in [`TriggerInfo::settings`](crate::app::TriggerInfo::settings).
+    fn supports_templates(&self, setting: &str) -> bool;
+}
+
+/// A trigger type whose templated settings are a fixed list.
+pub struct TemplateSettings {
+    trigger_type: String,
+    settings: Vec<String>,
+}
+
+impl TemplateSettings {
+    pub fn new(trigger_type: impl Into<String>, settings: &[&str]) -> Self {
+        Self {
+            trigger_type: trigger_type.into(),
+            settings: settings.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    pub fn http() -> Self {
+        Self::new("http", &[])
+    }
+
+    pub fn redis() -> Self {
+        Self::new("redis", &["address", "channel"])
+    }
+
+    pub fn cron() -> Self {
+        Self::new("cron", &[])
+    }
+
+    pub fn sqs() -> Self {
+        Self::new("sqs", &[])
+    }
+}
+
+impl TriggerKnowledge for TemplateSettings {
+    fn trigger_type(&self) -> &str {
+        &self.trigger_type
+    }
+
+    fn supports_templates(&self, setting: &str) -> bool {
+        self.settings.iter().any(|s| s == setting)
+    }
+}
+
+/// The trigger types whose settings are known, looked up by type.
+pub struct TriggerRegistry {
+    triggers: Vec<Box<dyn TriggerKnowledge>>,
+}
+
+impl TriggerRegistry {
+    /// A registry with no trigger types.
+    pub fn empty() -> Self {
+        Self { triggers: vec![] }
+    }
+
+    /// A registry with the trigger types built into Spin, and common plugins.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::empty();
+        registry.register(TemplateSettings::http());
+        registry.register(TemplateSettings::redis());
+        registry.register(TemplateSettings::cron());
+        registry.register(TemplateSettings::sqs());
+        registry
+    }
+
+    /// Adds a trigger type. If one with the same type is already registered, the new
+    /// knowledge replaces it.
+    pub fn register(&mut self, trigger: impl TriggerKnowledge + 'static) {
+        self.triggers.retain(|t| t.trigger_type() != trigger.trigger_type());
+        self.triggers.push(Box::new(trigger));
+    }
+
+    pub fn get(&self, trigger_type: &str) -> Option<&dyn TriggerKnowledge> {
+        self.triggers.iter().find(|t| t.trigger_type() == trigger_type).map(|t| t.as_ref())
+    }
+
+    /// Whether templates in a setting are expanded, or `None` if the trigger type
+    /// isn't known.
+    pub fn supports_templates(&self, trigger_type: &str, setting: &str) -> Option<bool> {
+        self.get(trigger_type).map(|t| t.supports_templates(setting))
+    }
+}
+
+impl Default for TriggerRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
@@ -0,0 +1,106 @@
+//! Where an application's templates are, and which variables they refer to.
+
+use crate::app::AppInfo;
+use crate::templates::{self, Part};
+
+/// A place in the application that contains a template.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TemplateSite<'a> {
+    pub kind: SiteKind,
+    /// The component or trigger that owns the template.
+    pub owner: String,
+    /// The component variable name, host, or trigger setting path.
+    pub key: String,
+    pub template: &'a str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteKind {
+    ComponentVariable,
+    OutboundHost,
+    TriggerSetting,
+    /// The default of an application variable, which may refer to other variables.
+    VariableDefault,
+}
+
+impl std::fmt::Display for TemplateSite<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            SiteKind::ComponentVariable => write!(f, "component '{}' variable '{}'", self.owner, self.key),
+            SiteKind::OutboundHost => write!(f, "component '{}' allowed outbound host '{}'", self.owner, self.key),
+            SiteKind::TriggerSetting => write!(f, "trigger {} setting '{}'", self.owner, self.key),
+            SiteKind::VariableDefault => write!(f, "the default of variable '{}'", self.owner),
+        }
+    }
+}
+
+pub fn template_sites(app: &AppInfo) -> Vec<TemplateSite<'_>> {
+    let mut sites = vec![];
+
+    for component in &app.components {
+        sites.extend(component.variables.iter().map(|(name, template)| TemplateSite {
+            kind: SiteKind::ComponentVariable,
+            owner: component.id.clone(),
+            key: name.clone(),
+            template,
+        }));
+        sites.extend(component.allowed_outbound_hosts.iter().map(|host| TemplateSite {
+            kind: SiteKind::OutboundHost,
+            owner: component.id.clone(),
+            key: host.clone(),
+            template: host,
+        }));
+    }
+
+    sites.extend(app.variables.iter()
+        .filter_map(|variable| Some((variable, variable.default_value.as_deref().filter(|d| d.contains("{{"))?)))
+        .map(|(variable, default)| TemplateSite {
+            kind: SiteKind::VariableDefault,
+            owner: variable.name.clone(),
+            key: "default".to_owned(),
+            template: default,
+        }));
+
+    for trigger in &app.triggers {
+        sites.extend(trigger.settings.iter().filter(|(_, value)| value.contains("{{")).map(|(setting, value)| TemplateSite {
+            kind: SiteKind::TriggerSetting,
+            owner: trigger.display_name(),
+            key: setting.clone(),
+            template: value,
+        }));
+    }
+
+    sites
+}
+
+/// The places that refer to a variable.
+pub fn sites_using<'a>(app: &'a AppInfo, variable_name: &str) -> Vec<TemplateSite<'a>> {
+    template_sites(app).into_iter().filter(|site| references(site.template).contains(&variable_name)).collect()
+}
+
+/// The variables that templates refer to but the application doesn't declare,
+/// in order of first use, with where each is first used.
+pub fn undeclared_references(app: &AppInfo) -> Vec<(&str, TemplateSite<'_>)> {
+    let mut undeclared: Vec<(&str, TemplateSite)> = vec![];
+    for site in template_sites(app) {
+        for name in references(site.template) {
+            if !app.variables.iter().any(|v| v.name == name) && !undeclared.iter().any(|(n, _)| *n == name) {
+                undeclared.push((name, site.clone()));
+            }
+        }
+    }
+    undeclared
+}
+
+/// The variable names a template refers to. Templates with syntax errors are
+/// treated as referring to nothing.
+pub fn references(template: &str) -> Vec<&str> {
+    match templates::parse(template) {
+        Ok(parts) => parts.into_iter().filter_map(|part| match part {
+            Part::Expression(name) => Some(name),
+            Part::Literal(_) => None,
+        }).collect(),
+        Err(_) => vec![],
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::app::VariableInfo;
+use crate::encryption;
+use crate::profiles::Profile;
+use crate::providers::ProviderChain;
+use crate::templates;
+
+/// A set of variable values loaded from a YAML, TOML or JSON file.
+pub struct ValuesFile {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl ValuesFile {
+    #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read values file {}", path.display()))?;
+        if encryption::is_sops_encrypted(path, &text) {
+            text = encryption::sops_decrypt(path)?;
+        }
+        let values = parse_values(path, &text)
+            .with_context(|| format!("Failed to parse values file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            values,
+        })
+    }
+
+    /// Values that were read from a file in some other way, such as from a dotenv file.
+    pub fn from_values(path: impl Into<PathBuf>, values: BTreeMap<String, String>) -> Self {
+        Self {
+            path: path.into(),
+            values,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.values.get(name)
+    }
+
+    pub fn values(&self) -> &BTreeMap<String, String> {
+        &self.values
+    }
+
+    /// Decrypts any encrypted values, using an age identity file.
+    pub fn decrypt(&mut self, identity_file: Option<&Path>) -> anyhow::Result<()> {
+        for (name, value) in self.values.iter_mut().filter(|(_, v)| encryption::is_encrypted(v)) {
+            let Some(identity_file) = identity_file else {
+                anyhow::bail!("Values file {} has an encrypted value for '{name}'. Pass --age-identity to decrypt it.", self.path.display());
+            };
+            *value = encryption::decrypt(value, identity_file).with_context(|| format!("Failed to decrypt '{name}' from {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Things about a value that are usually mistakes, such as a trailing newline or
+/// quotes copied from a shell command, described for warnings. These are the usual
+/// causes of a variable that is set but doesn't work.
+pub fn suspicious_patterns(value: &str) -> Vec<&'static str> {
+    let mut patterns = vec![];
+    if value.contains('\r') {
+        patterns.push("a carriage return (is it from a file with CRLF line endings?)");
+    }
+    if value.starts_with(char::is_whitespace) {
+        patterns.push("leading whitespace");
+    }
+    if value.ends_with(char::is_whitespace) {
+        patterns.push("trailing whitespace");
+    }
+    let quoted = |quote: char| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if quoted('"') || quoted('\'') {
+        patterns.push("surrounding quotes");
+    }
+    patterns
+}
+
+/// A variable value together with where it came from.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ResolvedValue {
+    pub value: String,
+    pub source: ValueSource,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum ValueSource {
+    Set,
+    ValuesFile(PathBuf),
+    Profile(String),
+    Environment(String),
+    Dotenv(PathBuf),
+    Provider(String),
+    /// A random value, generated because the secret variable had none.
+    Generated,
+    Default,
+    ExpandedDefault,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Set => f.write_str("--set"),
+            Self::ValuesFile(path) => write!(f, "values file {}", path.display()),
+            Self::Profile(name) => write!(f, "profile {name}"),
+            Self::Environment(env_var_name) => write!(f, "environment variable {env_var_name}"),
+            Self::Dotenv(path) => write!(f, "dotenv file {}", path.display()),
+            Self::Provider(name) => f.write_str(name),
+            Self::Generated => f.write_str("generated"),
+            Self::Default => f.write_str("manifest default"),
+            Self::ExpandedDefault => f.write_str("manifest default (expanded)"),
+        }
+    }
+}
+
+fn parse_values(path: &Path, text: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let raw: BTreeMap<String, ScalarValue> = match extension.as_str() {
+        "yaml" | "yml" => serde_yaml::from_str(text)?,
+        "toml" => toml::from_str(text)?,
+        "json" => serde_json::from_str(text)?,
+        _ => anyhow::bail!("Unknown values file format: expected a .yaml, .toml or .json extension"),
+    };
+    Ok(raw.into_iter().map(|(name, value)| (name, value.to_string())).collect())
+}
+
+/// Renders values in the format that `ValuesFile::load` would read from the path,
+/// based on its extension.
+pub fn serialize_values(path: &Path, values: &BTreeMap<String, String>) -> anyhow::Result<String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "yaml" | "yml" => Ok(serde_yaml::to_string(values)?),
+        "toml" => Ok(toml::to_string(values)?),
+        "json" => Ok(serde_json::to_string_pretty(values)?),
+        _ => anyhow::bail!("Unknown values file format: expected a .yaml, .toml or .json extension"),
+    }
+}
+
+pub fn write_values(path: &Path, values: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    let text = serialize_values(path, values)?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write values file {}", path.display()))
+}
+
+/// Values files are a flat map of names to values. We accept non-string scalars
+/// because YAML and TOML authors will naturally write `port: 8080` or `debug = true`.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ScalarValue {
+    String(String),
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+}
+
+impl std::fmt::Display for ScalarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) => s.fmt(f),
+            Self::Bool(b) => b.fmt(f),
+            Self::Integer(i) => i.fmt(f),
+            Self::Float(x) => x.fmt(f),
+        }
+    }
+}
+
+/// The places variable values can come from, other than the manifest.
+pub struct Sources<'a> {
+    pub set: &'a [(String, String)],
+    pub values_files: &'a [ValuesFile],
+    pub profile: Option<&'a Profile>,
+    pub providers: &'a ProviderChain,
+}
+
+/// Works out the value each variable will take. In order of precedence:
+/// `--set` values, values files (later files first), the profile, the providers
+/// (such as the environment and dotenv files) in chain order, and manifest defaults.
+#[tracing::instrument(level = "info", skip_all, fields(variables = variables.len()))]
+pub async fn resolve_values(variables: &mut [VariableInfo], overrides: &Sources) -> anyhow::Result<()> {
+    for (name, _) in overrides.set {
+        if !is_declared(variables, name) {
+            anyhow::bail!("--set refers to variable '{name}', which is not declared by the application");
+        }
+    }
+
+    for values_file in overrides.values_files.iter().chain(overrides.profile.map(|p| &p.values)) {
+        for name in values_file.values.keys() {
+            if !is_declared(variables, name) {
+                tracing::warn!(path = %values_file.path.display(), variable = %name, "values file sets a variable which is not declared by the application");
+            }
+        }
+    }
+
+    for variable in variables.iter_mut() {
+        let set_value = overrides.set.iter().rev().find(|(name, _)| name == &variable.name).map(|(_, value)| ResolvedValue {
+            value: value.clone(),
+            source: ValueSource::Set,
+        });
+        let file_value = || overrides.values_files.iter().rev().find_map(|f| f.get(&variable.name).map(|value| ResolvedValue {
+            value: value.clone(),
+            source: ValueSource::ValuesFile(f.path.clone()),
+        }));
+        let profile_value = || overrides.profile.and_then(|p| p.values.get(&variable.name).map(|value| ResolvedValue {
+            value: value.clone(),
+            source: ValueSource::Profile(p.name.clone()),
+        }));
+        let default_value = || variable.default_value.clone().map(|value| ResolvedValue {
+            value,
+            source: ValueSource::Default,
+        });
+        let override_value = set_value.or_else(file_value).or_else(profile_value);
+        let provider_value = match override_value {
+            Some(value) => Some(value),
+            None => overrides.providers.get(&variable.name).await?,
+        };
+        variable.value = provider_value.or_else(default_value);
+        match &variable.value {
+            Some(resolved) => tracing::debug!(variable = %variable.name, source = %resolved.source, "resolved"),
+            None => tracing::debug!(variable = %variable.name, "no value"),
+        }
+    }
+
+    expand_templated_defaults(variables);
+
+    Ok(())
+}
+
+/// Defaults which refer to other variables or to environment variables are expanded
+/// once everything else is resolved. Defaults can refer to other templated defaults,
+/// so we keep going until no more can be expanded. A default that can't be expanded
+/// leaves the variable without a value, rather than with the raw template text.
+fn expand_templated_defaults(variables: &mut [VariableInfo]) {
+    let templated: Vec<_> = variables.iter().enumerate()
+        .filter(|(_, v)| matches!(&v.value, Some(ResolvedValue { source: ValueSource::Default, .. })))
+        .filter(|(_, v)| v.default_value.as_deref().is_some_and(templates::is_templated))
+        .map(|(index, _)| index)
+        .collect();
+
+    for &index in &templated {
+        variables[index].value = None;
+    }
+
+    loop {
+        let mut expanded_any = false;
+        for &index in &templated {
+            if variables[index].value.is_some() {
+                continue;
+            }
+            let template = variables[index].default_value.clone().unwrap_or_default();
+            let Ok(with_env) = templates::expand_env_refs(&template) else {
+                continue;
+            };
+            if let Ok(value) = templates::expand(&with_env, variables) {
+                variables[index].value = Some(ResolvedValue {
+                    value,
+                    source: ValueSource::ExpandedDefault,
+                });
+                expanded_any = true;
+            }
+        }
+        if !expanded_any {
+            break;
+        }
+    }
+}
+
+fn is_declared(variables: &[VariableInfo], name: &str) -> bool {
+    variables.iter().any(|v| v.name == name)
+}
+
+pub fn parse_name_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) => Ok((name.to_owned(), value.to_owned())),
+        None => Err(format!("expected NAME=VALUE but got '{s}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::tests::StaticProvider;
+    use crate::providers::ProviderLabel;
+
+    fn variable(name: &str, default_value: Option<&str>) -> VariableInfo {
+        VariableInfo {
+            name: name.to_owned(),
+            default_value: default_value.map(|d| d.to_owned()),
+            required: default_value.is_none(),
+            secret: false,
+            value: None,
+        }
+    }
+
+    fn values_file(path: &str, values: &[(&str, &str)]) -> ValuesFile {
+        ValuesFile::from_values(path, values.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect())
+    }
+
+    fn resolved(variables: &[VariableInfo], name: &str) -> Option<(String, ValueSource)> {
+        let value = variables.iter().find(|v| v.name == name)?.value.clone()?;
+        Some((value.value, value.source))
+    }
+
+    #[tokio::test]
+    async fn values_are_resolved_in_precedence_order() {
+        let mut variables: Vec<_> = ["from_set", "from_file", "from_profile", "from_provider", "from_default"].iter()
+            .map(|name| variable(name, Some("default")))
+            .chain([variable("unset", None)])
+            .collect();
+        let set = [("from_set".to_owned(), "set".to_owned()), ("from_set".to_owned(), "set again".to_owned())];
+        let values_files = [
+            values_file("a.toml", &[("from_set", "a"), ("from_file", "a")]),
+            values_file("b.toml", &[("from_file", "b")]),
+        ];
+        let profile = Profile {
+            name: "staging".to_owned(),
+            values: values_file("staging.toml", &[("from_set", "profile"), ("from_file", "profile"), ("from_profile", "profile")]),
+        };
+        let mut providers = ProviderChain::new();
+        providers.push(ProviderLabel::Named("static".to_owned()), StaticProvider(vec![("from_set", "provider"), ("from_profile", "provider"), ("from_provider", "provider")]));
+
+        let sources = Sources { set: &set, values_files: &values_files, profile: Some(&profile), providers: &providers };
+        resolve_values(&mut variables, &sources).await.unwrap();
+
+        assert_eq!(resolved(&variables, "from_set"), Some(("set again".to_owned(), ValueSource::Set)));
+        assert_eq!(resolved(&variables, "from_file"), Some(("b".to_owned(), ValueSource::ValuesFile("b.toml".into()))));
+        assert_eq!(resolved(&variables, "from_profile"), Some(("profile".to_owned(), ValueSource::Profile("staging".to_owned()))));
+        assert_eq!(resolved(&variables, "from_provider"), Some(("provider".to_owned(), ValueSource::Provider("static".to_owned()))));
+        assert_eq!(resolved(&variables, "from_default"), Some(("default".to_owned(), ValueSource::Default)));
+        assert_eq!(resolved(&variables, "unset"), None);
+    }
+
+    #[tokio::test]
+    async fn set_must_refer_to_declared_variables() {
+        let set = [("nope".to_owned(), "x".to_owned())];
+        let sources = Sources { set: &set, values_files: &[], profile: None, providers: &ProviderChain::new() };
+        let error = resolve_values(&mut [variable("region", None)], &sources).await.unwrap_err();
+        assert_eq!(error.to_string(), "--set refers to variable 'nope', which is not declared by the application");
+    }
+
+    #[test]
+    fn templated_defaults_are_expanded_from_resolved_values() {
+        let with_value = |name: &str, default_value: Option<&str>, value: &str, source: ValueSource| VariableInfo {
+            value: Some(ResolvedValue { value: value.to_owned(), source }),
+            ..variable(name, default_value)
+        };
+        let mut variables = [
+            with_value("endpoint", Some("{{ url }}/v1"), "{{ url }}/v1", ValueSource::Default),
+            with_value("url", Some("https://{{ host }}/{{ path }}"), "https://{{ host }}/{{ path }}", ValueSource::Default),
+            with_value("host", None, "example.com", ValueSource::Set),
+            with_value("path", Some("api"), "api", ValueSource::Default),
+            with_value("broken", Some("{{ nope }}"), "{{ nope }}", ValueSource::Default),
+            with_value("overridden", Some("{{ host }}"), "mine", ValueSource::Set),
+        ];
+        expand_templated_defaults(&mut variables);
+
+        assert_eq!(resolved(&variables, "endpoint"), Some(("https://example.com/api/v1".to_owned(), ValueSource::ExpandedDefault)));
+        assert_eq!(resolved(&variables, "url"), Some(("https://example.com/api".to_owned(), ValueSource::ExpandedDefault)));
+        assert_eq!(resolved(&variables, "path"), Some(("api".to_owned(), ValueSource::Default)));
+        assert_eq!(resolved(&variables, "broken"), None);
+        assert_eq!(resolved(&variables, "overridden"), Some(("mine".to_owned(), ValueSource::Set)));
+    }
+
+    #[test]
+    fn suspicious_patterns_are_found() {
+        assert!(suspicious_patterns("hunter2").is_empty());
+        assert!(suspicious_patterns("'").is_empty());
+        assert_eq!(suspicious_patterns("hunter2\r"), ["a carriage return (is it from a file with CRLF line endings?)", "trailing whitespace"]);
+        assert_eq!(suspicious_patterns(" hunter2"), ["leading whitespace"]);
+        assert_eq!(suspicious_patterns("\"hunter2\""), ["surrounding quotes"]);
+        assert_eq!(suspicious_patterns("'hunter2'"), ["surrounding quotes"]);
+    }
+
+    #[test]
+    fn scalar_values_are_read_as_strings() {
+        let values = parse_values(Path::new("values.yaml"), "port: 8080\ndebug: true\nratio: 0.5\nname: web\n").unwrap();
+        let values: Vec<_> = values.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(values, [("debug", "true"), ("name", "web"), ("port", "8080"), ("ratio", "0.5")]);
+        assert!(parse_values(Path::new("values.ini"), "port = 8080").is_err());
+    }
+
+    #[test]
+    fn serialized_values_read_back() {
+        let values: BTreeMap<_, _> = [("greeting".to_owned(), "hello\nworld".to_owned())].into();
+        for path in ["values.yaml", "values.toml", "values.json"] {
+            let text = serialize_values(Path::new(path), &values).unwrap();
+            assert_eq!(parse_values(Path::new(path), &text).unwrap(), values, "{path}");
+        }
+    }
+
+    #[test]
+    fn encrypted_values_need_an_identity() {
+        let mut plain = values_file("dev.yaml", &[("region", "eu")]);
+        assert!(plain.decrypt(None).is_ok());
+
+        let mut encrypted = values_file("prod.yaml", &[("api_key", "-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----\n")]);
+        let error = encrypted.decrypt(None).unwrap_err().to_string();
+        assert_eq!(error, "Values file prod.yaml has an encrypted value for 'api_key'. Pass --age-identity to decrypt it.");
+    }
+
+    #[test]
+    fn set_arguments_split_at_the_first_equals() {
+        assert_eq!(parse_name_value("url=https://example.com/?a=b"), Ok(("url".to_owned(), "https://example.com/?a=b".to_owned())));
+        assert!(parse_name_value("url").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Looking up variable values in HashiCorp Vault's KV version 2 secrets engine,
+//! configured the same way as Spin's `vault` variables provider.
+//!
+//! Each variable is read from the `value` field of the secret at the prefix followed
+//! by the variable name, such as `my-app/api_key` for the prefix `my-app`.
+
+use std::sync::Mutex;
+
+use anyhow::Context;
+use spin_expressions::{Key, Provider};
+
+use crate::providers::{ProviderChain, ProviderLabel};
+
+/// The settings of a `vault` variables provider in a runtime config file. The
+/// token may be left out when logging in with AppRole or when `VAULT_TOKEN` is set.
+#[derive(Clone, serde::Deserialize)]
+pub struct VaultConfig {
+    pub url: String,
+    pub token: Option<String>,
+    pub mount: String,
+    pub prefix: Option<String>,
+}
+
+/// Settings beyond what Spin's runtime config has, for Vault installations that
+/// don't accept plain tokens or that use namespaces. These apply to every Vault
+/// provider.
+#[derive(Clone, Debug, Default)]
+pub struct VaultOptions {
+    /// The Vault Enterprise namespace to work in.
+    pub namespace: Option<String>,
+    /// The secrets engine mount to read from, instead of the provider's `mount`.
+    pub mount: Option<String>,
+    pub approle: Option<AppRole>,
+}
+
+/// AppRole credentials, for logging in rather than using a token.
+#[derive(Clone)]
+pub struct AppRole {
+    pub role_id: String,
+    pub secret_id: String,
+    /// Where the AppRole auth method is mounted, usually `approle`.
+    pub mount: String,
+}
+
+impl std::fmt::Debug for AppRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppRole").field("role_id", &self.role_id).field("mount", &self.mount).finish_non_exhaustive()
+    }
+}
+
+pub struct VaultProvider {
+    client: reqwest::Client,
+    url: String,
+    mount: String,
+    prefix: Option<String>,
+    namespace: Option<String>,
+    approle: Option<AppRole>,
+    token: Mutex<Option<String>>,
+}
+
+impl VaultProvider {
+    pub fn new(config: VaultConfig, options: &VaultOptions) -> anyhow::Result<Self> {
+        let token = config.token.or_else(|| std::env::var("VAULT_TOKEN").ok());
+        if token.is_none() && options.approle.is_none() {
+            anyhow::bail!("Vault {} has no token. Set one in the runtime config or VAULT_TOKEN, or log in with AppRole", config.url);
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url: config.url.trim_end_matches('/').to_owned(),
+            mount: options.mount.clone().unwrap_or(config.mount).trim_matches('/').to_owned(),
+            prefix: config.prefix.map(|p| p.trim_matches('/').to_owned()).filter(|p| !p.is_empty()),
+            namespace: options.namespace.clone(),
+            // A token from AppRole replaces any configured token
+            token: Mutex::new(token.filter(|_| options.approle.is_none())),
+            approle: options.approle.clone(),
+        })
+    }
+
+    pub fn add_to(self, chain: &mut ProviderChain) {
+        chain.push(ProviderLabel::Named(format!("Vault {}", self.url)), self);
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}/v1/{path}", self.url));
+        match &self.namespace {
+            Some(namespace) => request.header("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
+
+    async fn token(&self) -> anyhow::Result<String> {
+        let cached = self.token.lock().unwrap().clone();
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+        let Some(approle) = &self.approle else {
+            anyhow::bail!("Vault {} has no token", self.url);
+        };
+        let body = serde_json::json!({ "role_id": approle.role_id, "secret_id": approle.secret_id });
+        let response = self.request(reqwest::Method::POST, &format!("auth/{}/login", approle.mount.trim_matches('/')))
+            .body(body.to_string())
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to log in to Vault {} with AppRole", self.url))?;
+        let login: LoginResponse = serde_json::from_str(&response.text().await?).context("Unexpected response to Vault login")?;
+        *self.token.lock().unwrap() = Some(login.auth.client_token.clone());
+        Ok(login.auth.client_token)
+    }
+
+    fn secret_path(&self, name: &str) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}/data/{prefix}/{name}", self.mount),
+            None => format!("{}/data/{name}", self.mount),
+        }
+    }
+}
+
+impl std::fmt::Debug for VaultProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultProvider")
+            .field("url", &self.url)
+            .field("mount", &self.mount)
+            .field("prefix", &self.prefix)
+            .field("namespace", &self.namespace)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(serde::Deserialize)]
+struct LoginAuth {
+    client_token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct SecretResponse {
+    data: SecretData,
+}
+
+#[derive(serde::Deserialize)]
+struct SecretData {
+    data: Secret,
+}
+
+#[derive(serde::Deserialize)]
+struct Secret {
+    value: String,
+}
+
+#[async_trait::async_trait]
+impl Provider for VaultProvider {
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<String>> {
+        let token = self.token().await?;
+        let response = self.request(reqwest::Method::GET, &self.secret_path(key.as_str()))
+            .header("X-Vault-Token", token)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let text = response.error_for_status()?.text().await?;
+        let secret: SecretResponse = serde_json::from_str(&text).context("Unexpected response from Vault: secrets should have a 'value' field")?;
+        Ok(Some(secret.data.data.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(prefix: Option<&str>) -> VaultConfig {
+        VaultConfig {
+            url: "https://vault.internal:8200/".to_owned(),
+            token: Some("root".to_owned()),
+            mount: "secret".to_owned(),
+            prefix: prefix.map(|p| p.to_owned()),
+        }
+    }
+
+    #[test]
+    fn secret_paths_follow_spin() {
+        let provider = VaultProvider::new(config(None), &VaultOptions::default()).unwrap();
+        assert_eq!(provider.secret_path("api_key"), "secret/data/api_key");
+        let provider = VaultProvider::new(config(Some("my-app/")), &VaultOptions::default()).unwrap();
+        assert_eq!(provider.secret_path("api_key"), "secret/data/my-app/api_key");
+    }
+
+    #[test]
+    fn options_override_the_runtime_config() {
+        let options = VaultOptions {
+            namespace: Some("team-a".to_owned()),
+            mount: Some("/kv/".to_owned()),
+            approle: Some(AppRole { role_id: "role".to_owned(), secret_id: "s3cret".to_owned(), mount: "approle".to_owned() }),
+        };
+        let provider = VaultProvider::new(config(None), &options).unwrap();
+        assert_eq!(provider.secret_path("api_key"), "kv/data/api_key");
+        assert!(provider.token.lock().unwrap().is_none());
+        assert!(!format!("{provider:?} {options:?}").contains("s3cret"));
+    }
+}
@@ -0,0 +1,28 @@
+//! Finding out from a component's Wasm whether it reads variables, so that code
+//! and manifest can be checked against each other.
+
+/// The interfaces through which components read variables: the Spin 2 `variables`
+/// interface, the Spin 1 `config` interface under its old and new names, and WASI
+/// config.
+const VARIABLES_INTERFACES: &[&str] = &["fermyon:spin/variables", "fermyon:spin/config", "spin-config", "wasi:config/store"];
+
+/// Whether a Wasm module or component imports any of the interfaces for reading
+/// variables, whatever their versions.
+pub fn imports_variables(wasm: &[u8]) -> anyhow::Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        let names: Vec<&str> = match payload? {
+            wasmparser::Payload::ImportSection(imports) => imports.into_iter().map(|i| i.map(|i| i.module)).collect::<Result<_, _>>()?,
+            wasmparser::Payload::ComponentImportSection(imports) => imports.into_iter().map(|i| i.map(|i| i.name.0)).collect::<Result<_, _>>()?,
+            _ => continue,
+        };
+        if names.into_iter().any(is_variables_interface) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn is_variables_interface(name: &str) -> bool {
+    let unversioned = name.split_once('@').map(|(name, _)| name).unwrap_or(name);
+    VARIABLES_INTERFACES.contains(&unversioned)
+}
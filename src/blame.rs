@@ -0,0 +1,78 @@
+use spin_variables::format::Versioned;
+use spin_variables::locations::ManifestLocator;
+
+use crate::git::Commit;
+use crate::history;
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct BlameCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// The variables to show. The default is all the variables the manifest declares.
+    variables: Vec<String>,
+
+    /// How to output the commits: table, or json (a document with the commit, if any,
+    /// for each variable).
+    #[clap(short = 'o', long = "output", default_value = "table", value_parser = ["table", "json"])]
+    output: String,
+}
+
+/// The commit that introduced a variable's current declaration.
+#[derive(serde::Serialize)]
+struct BlameEntry {
+    name: String,
+    /// The line of the declaration in the manifest (1-based).
+    line: usize,
+    /// `None` if the declaration hasn't been committed yet.
+    commit: Option<Commit>,
+}
+
+impl BlameCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let Some(manifest_path) = self.app.manifest_path()? else {
+            anyhow::bail!("blame needs a manifest file in a git repository, not a registry reference or OCI layout");
+        };
+        let app = self.app.load_app().await?;
+        let locator = ManifestLocator::load(&manifest_path)?;
+
+        for name in &self.variables {
+            if !app.variables.iter().any(|v| &v.name == name) {
+                anyhow::bail!("Variable '{name}' is not declared in {}", manifest_path.display());
+            }
+        }
+        let names = app.variables.iter().map(|v| &v.name).filter(|name| self.variables.is_empty() || self.variables.contains(*name));
+
+        let history = history::history(&manifest_path)?;
+        let mut entries = vec![];
+        for name in names {
+            let Some(location) = locator.variable(name) else {
+                eprintln!("Warning: could not find the declaration of '{name}' in {}", manifest_path.display());
+                continue;
+            };
+            // A variable that was removed and declared again was introduced by the last addition
+            let added = history.iter().rev().find(|e| e.changes.iter().any(|c| &c.name == name && c.change == "added"));
+            let commit = added.map(|e| e.commit.clone());
+            entries.push(BlameEntry { name: name.clone(), line: location.line, commit });
+        }
+
+        if self.output == "json" {
+            println!("{}", serde_json::to_string_pretty(&Versioned::new(serde_json::json!({ "variables": entries })))?);
+            return Ok(());
+        }
+
+        let mut table = comfy_table::Table::new();
+        table.set_header(comfy_table::Row::from(vec!["Name", "Line", "Commit", "Author", "Date", "Subject"]));
+        table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+        for entry in &entries {
+            let line = entry.line.to_string();
+            match &entry.commit {
+                Some(commit) => table.add_row(vec![entry.name.as_str(), line.as_str(), commit.short_hash(), commit.author.as_str(), commit.date.as_str(), commit.subject.as_str()]),
+                None => table.add_row(vec![entry.name.as_str(), line.as_str(), "", "", "", "Not committed yet"]),
+            };
+        }
+        println!("{table}");
+        Ok(())
+    }
+}
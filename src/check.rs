@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use spin_variables::app::VariableInfo;
+use spin_variables::locations::ManifestLocator;
+use spin_variables::providers::ProviderChain;
+use spin_variables::schema::Schema;
+use spin_variables::snapshots::Snapshot;
+use spin_variables::values::{self, ValuesFile};
+use spin_variables::{check, env, reports};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct CheckCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// A schema file of constraints on the variables' values. If omitted, this is
+    /// `variables.schema.toml` in the application directory, if it exists.
+    #[clap(long = "schema", value_name = "FILE")]
+    schema: Option<PathBuf>,
+
+    /// Write a report in the given format (github, junit or tap) for CI systems to display,
+    /// to FILE if given or else to standard output. `gha-output` instead appends GitHub
+    /// Actions step outputs to FILE, or by default to the file named by `GITHUB_OUTPUT`.
+    #[clap(long = "report", value_names = ["FORMAT", "FILE"], num_args = 1..=2)]
+    report: Vec<String>,
+
+    /// Check the values in this file instead of those from the environment and other
+    /// sources: a snapshot, a dotenv file, or a values file. Entries for variables the
+    /// application doesn't declare are reported. Encrypted values in a values file
+    /// are decrypted with `--age-identity`.
+    #[clap(long = "against", value_name = "FILE", conflicts_with_all = ["set", "values", "profile", "dotenv_path", "runtime_config_file", "keychain", "store", "kube_secrets", "kube_configmaps", "kube_spinapp", "consul_address", "aws_secrets_manager", "aws_parameter_store"])]
+    against: Option<PathBuf>,
+}
+
+impl CheckCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        let (env_prefix, extras) = match &self.against {
+            Some(file) => {
+                let env_prefix = self.values.env_prefix.clone().unwrap_or_else(|| env::DEFAULT_PREFIX.to_owned());
+                let extras = resolve_against(&mut app.variables, file, &env_prefix, self.values.age_identity.as_deref()).await?;
+                (env_prefix, extras)
+            }
+            None => {
+                let env_providers = self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+                (env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned(), vec![])
+            }
+        };
+        let env_prefix = env_prefix.as_str();
+
+        let schema = Schema::load_for_app(&self.app.app_dir()?, self.schema.as_deref())?;
+        let results = check::check(&app.variables, env_prefix, &schema);
+
+        let report_file = match self.report.as_slice() {
+            [] => None,
+            [format, file @ ..] if format == "gha-output" => {
+                let file = match file.first() {
+                    Some(file) => PathBuf::from(file),
+                    None => std::env::var_os("GITHUB_OUTPUT").map(PathBuf::from).context("GITHUB_OUTPUT is not set, so give the file to write outputs to after gha-output")?,
+                };
+                let outputs = reports::github_outputs(&results)?;
+                let mut output_file = std::fs::OpenOptions::new().create(true).append(true).open(&file).with_context(|| format!("Failed to open {}", file.display()))?;
+                std::io::Write::write_all(&mut output_file, outputs.as_bytes()).with_context(|| format!("Failed to write outputs to {}", file.display()))?;
+                Some(file)
+            }
+            [format, file @ ..] => {
+                let report = match format.as_str() {
+                    "junit" => reports::junit(&results, &app.name),
+                    "tap" => reports::tap(&results),
+                    "github" => {
+                        let locator = self.app.manifest_path()?.map(ManifestLocator::load).transpose()?;
+                        reports::github_check(&results, locator.as_ref())
+                    }
+                    _ => anyhow::bail!("Unknown report format '{format}'. The available formats are: gha-output, github, junit, tap"),
+                };
+                match file.first() {
+                    Some(file) => {
+                        let file = PathBuf::from(file);
+                        std::fs::write(&file, report).with_context(|| format!("Failed to write report {}", file.display()))?;
+                        Some(file)
+                    }
+                    None => {
+                        println!("{report}");
+                        return exit_status(&results);
+                    }
+                }
+            }
+        };
+
+        if let Some(file) = &self.against {
+            for name in &extras {
+                println!("warning: {} sets '{name}', which is not declared by the application", file.display());
+            }
+        }
+        for result in &results {
+            if let Some(problem) = &result.problem {
+                println!("error: {problem}");
+            } else if let Some(warning) = &result.warning {
+                println!("warning: {warning}");
+            }
+        }
+        if results.iter().all(|r| r.passed()) {
+            println!("All {} variable(s) are satisfied", results.len());
+        }
+        if let Some(file) = report_file {
+            println!("Wrote report to {}", file.display());
+        }
+
+        exit_status(&results)
+    }
+}
+
+/// Gives the variables the values from the `--against` file, or their defaults,
+/// and returns the names the file sets that the application doesn't declare.
+async fn resolve_against(variables: &mut [VariableInfo], file: &Path, env_prefix: &str, age_identity: Option<&Path>) -> anyhow::Result<Vec<String>> {
+    let (declared, extras): (BTreeMap<_, _>, BTreeMap<_, _>) = against_values(file, env_prefix, age_identity)?.into_iter()
+        .partition(|(name, _)| variables.iter().any(|v| &v.name == name));
+    let sources = values::Sources {
+        set: &[],
+        values_files: &[ValuesFile::from_values(file, declared)],
+        profile: None,
+        providers: &ProviderChain::new(),
+    };
+    values::resolve_values(variables, &sources).await?;
+    Ok(extras.into_keys().collect())
+}
+
+/// The values in a file, keyed by variable name. Dotenv files and snapshots are keyed
+/// by environment variable, so entries without the prefix are left out. Encrypted
+/// entries in values files are decrypted, so that the plain values are checked.
+fn against_values(file: &Path, env_prefix: &str, age_identity: Option<&Path>) -> anyhow::Result<BTreeMap<String, String>> {
+    let is_dotenv = file.extension().is_some_and(|ext| ext == "env") || file.file_name().is_some_and(|name| name.to_string_lossy().starts_with(".env"));
+    if is_dotenv {
+        return Ok(env::variables_in_dotenv(file, env_prefix)?.into_iter().collect());
+    }
+    if let Ok(snapshot) = Snapshot::load(file) {
+        let prefix = format!("{}_", snapshot.env_prefix);
+        return Ok(snapshot.values.into_iter().filter_map(|(name, value)| Some((name.strip_prefix(&prefix)?.to_ascii_lowercase(), value))).collect());
+    }
+    let mut values_file = ValuesFile::load(file)?;
+    values_file.decrypt(age_identity)?;
+    Ok(values_file.values().clone())
+}
+
+fn exit_status(results: &[check::CheckResult]) -> anyhow::Result<()> {
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    if failures > 0 {
+        anyhow::bail!("Check found {failures} problem(s)");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_against_values_need_an_identity() {
+        let file = std::env::temp_dir().join(format!("spin-variables-against-{}.toml", std::process::id()));
+        std::fs::write(&file, "region = \"eu\"\napi_key = \"\"\"\n-----BEGIN AGE ENCRYPTED FILE-----\nYWdlLWVuY3J5cHRpb24ub3JnL3YxCg==\n-----END AGE ENCRYPTED FILE-----\n\"\"\"\n").unwrap();
+        let result = against_values(&file, env::DEFAULT_PREFIX, None);
+        std::fs::remove_file(&file).unwrap();
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("encrypted value for 'api_key'"), "{error}");
+        assert!(error.contains("--age-identity"), "{error}");
+    }
+}
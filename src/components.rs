@@ -0,0 +1,98 @@
+use spin_variables::app::{AppInfo, ComponentInfo};
+use spin_variables::templates::{self, ExpansionError};
+use spin_variables::{env, format, usage};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct ComponentsCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// Show the environment variables that each component variable is derived from,
+    /// as Spin's environment variables provider reads them.
+    #[clap(long = "env", num_args = 0)]
+    env: bool,
+}
+
+impl ComponentsCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        let env_providers = self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+        // Only whether templates expand is shown, so secret values are never needed
+        format::redact_secrets(&mut app.variables);
+
+        let env_prefixes: Vec<_> = match env_providers.as_slice() {
+            [] => vec![env::DEFAULT_PREFIX],
+            providers => providers.iter().map(|p| p.prefix()).collect(),
+        };
+        println!("{}", format_components(&app, self.env.then_some(env_prefixes.as_slice())));
+
+        let ready = app.components.iter().filter(|c| is_ready(c, &app)).count();
+        println!("{ready} of {} component(s) have all their variables satisfied", app.components.len());
+
+        Ok(())
+    }
+}
+
+/// The components' variables, with where they come from and whether they can be
+/// expanded. If `env_prefixes` are given, this includes the environment variables
+/// which supply each one.
+fn format_components(app: &AppInfo, env_prefixes: Option<&[&str]>) -> impl std::fmt::Display {
+    let mut table = comfy_table::Table::new();
+    let mut header = vec!["Component", "Variable", "Derived from"];
+    if env_prefixes.is_some() {
+        header.push("Environment variables");
+    }
+    header.push("Status");
+    table.set_header(comfy_table::Row::from(header));
+    table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+
+    for component in &app.components {
+        if component.variables.is_empty() {
+            let mut row = vec![component.id.as_str(), "", ""];
+            if env_prefixes.is_some() {
+                row.push("");
+            }
+            row.push("No variables");
+            table.add_row(row);
+            continue;
+        }
+
+        for (name, template) in &component.variables {
+            let sources = usage::references(template);
+            let derived_from = if sources.is_empty() {
+                "(literal value)".to_owned()
+            } else {
+                sources.join(", ")
+            };
+            let mut row = vec![component.id.clone(), name.clone(), derived_from];
+            if let Some(prefixes) = env_prefixes {
+                let env_var_names: Vec<_> = sources.iter()
+                    .flat_map(|source| prefixes.iter().map(|prefix| env::env_var_name(prefix, source)))
+                    .collect();
+                row.push(env_var_names.join(", "));
+            }
+            row.push(status_text(template, app));
+            table.add_row(row);
+        }
+    }
+
+    table
+}
+
+fn status_text(template: &str, app: &AppInfo) -> String {
+    match templates::expand(template, &app.variables) {
+        Ok(_) => "Satisfied".to_owned(),
+        Err(ExpansionError::NoValue(name)) => format!("Missing value for '{name}'"),
+        Err(ExpansionError::Undefined(name)) => format!("'{name}' is not declared"),
+        Err(ExpansionError::Syntax(message)) => format!("Invalid template: {message}"),
+    }
+}
+
+fn is_ready(component: &ComponentInfo, app: &AppInfo) -> bool {
+    component.variables.iter().all(|(_, template)| templates::expand(template, &app.variables).is_ok())
+}
@@ -0,0 +1,41 @@
+use spin_variables::consistency::inconsistencies;
+
+use crate::MultiAppOptions;
+
+#[derive(clap::Args)]
+pub struct ConsistencyCommand {
+    #[clap(flatten)]
+    apps: MultiAppOptions,
+}
+
+impl ConsistencyCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let apps = self.apps.load_apps().await?;
+        let inconsistencies = inconsistencies(&apps);
+
+        if inconsistencies.is_empty() {
+            println!("No inconsistencies found across {} application(s)", apps.len());
+            return Ok(());
+        }
+
+        let mut table = comfy_table::Table::new();
+        table.set_header(comfy_table::Row::from(vec!["Name", "Application", "Required?", "Default value", "Secret?"]));
+        table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+
+        for (name, declarations) in &inconsistencies {
+            for (app, variable) in declarations {
+                table.add_row(vec![
+                    name.as_str(),
+                    app.as_str(),
+                    if variable.required { "Required" } else { "Optional" },
+                    variable.default_value.as_deref().unwrap_or_default(),
+                    if variable.secret { "Secret" } else { "" },
+                ]);
+            }
+        }
+
+        println!("{table}");
+        println!("{} variable(s) are declared differently across applications", inconsistencies.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::IsTerminal;
+
+use anyhow::Context;
+use futures::StreamExt;
+use spin_variables::app::AppInfo;
+use spin_variables::{env, format};
+
+use crate::{AppOptions, MultiAppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct DashboardCommand {
+    #[clap(flatten)]
+    apps: MultiAppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// Include the values of secret variables when showing an application's variables.
+    /// Otherwise they are replaced with REDACTED.
+    #[clap(long = "reveal-secrets", num_args = 0)]
+    reveal_secrets: bool,
+}
+
+/// An application on the dashboard, with its variables resolved and ready to show.
+struct AppStatus {
+    source: String,
+    loaded: anyhow::Result<(AppInfo, format::FormatContext)>,
+}
+
+impl AppStatus {
+    fn summary(&self, source_width: usize) -> String {
+        let status = match &self.loaded {
+            Ok((app, _)) => {
+                let unset = app.variables.iter().filter(|v| v.required && v.value.is_none()).count();
+                match unset {
+                    0 => format!("ready ({} variable(s))", app.variables.len()),
+                    unset => format!("{unset} required variable(s) unset, of {}", app.variables.len()),
+                }
+            }
+            Err(_) => "error (pick to see it)".to_owned(),
+        };
+        format!("{:source_width$}  {status}", self.source)
+    }
+}
+
+impl DashboardCommand {
+    /// Lists the applications with how many of their required variables have no value,
+    /// and shows the variables of whichever one is picked, until none is picked.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        if !std::io::stderr().is_terminal() {
+            anyhow::bail!("dashboard is interactive, and needs a terminal. Use scan or check for scripts");
+        }
+
+        let loads = self.apps.sources()?.into_iter().map(|source| async move {
+            let loaded = self.load(&source).await;
+            AppStatus { source, loaded }
+        });
+        let apps: Vec<_> = futures::stream::iter(loads).buffered(self.apps.jobs.into()).collect().await;
+        if apps.is_empty() {
+            println!("No applications found");
+            return Ok(());
+        }
+
+        let source_width = apps.iter().map(|a| a.source.chars().count()).max().unwrap_or_default();
+        let summaries: Vec<_> = apps.iter().map(|a| a.summary(source_width)).collect();
+        let mut selected = 0;
+        loop {
+            let selection = dialoguer::FuzzySelect::new()
+                .with_prompt("Application (Esc to quit)")
+                .items(&summaries)
+                .default(selected)
+                .interact_opt()?;
+            let Some(index) = selection else {
+                return Ok(());
+            };
+            selected = index;
+
+            let app = &apps[index];
+            println!("{}:", app.source);
+            match &app.loaded {
+                Ok((app, context)) => println!("{}", format::table(&app.variables, context)),
+                Err(e) => println!("Error: {e:#}"),
+            }
+            println!();
+        }
+    }
+
+    async fn load(&self, source: &str) -> anyhow::Result<(AppInfo, format::FormatContext)> {
+        let app_options = AppOptions {
+            from: Some(source.to_owned()),
+            registry: self.apps.registry.clone(),
+        };
+        let mut app = app_options.load_app().await.with_context(|| format!("Failed to load {source}"))?;
+        let env_providers = self.values.resolve(&mut app.variables, &app_options.app_dir()?).await
+            .with_context(|| format!("Failed to resolve the variables of {source}"))?;
+
+        let context = format::FormatContext {
+            env_prefix: env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned(),
+            app_name: app.name.clone(),
+            schema: app_options.schema()?,
+            value_lengths: format::value_lengths(&app.variables),
+            ..Default::default()
+        };
+        if !self.reveal_secrets {
+            format::redact_secrets(&mut app.variables);
+        }
+        Ok((app, context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_count_unset_required_variables() {
+        let manifest = "spin_manifest_version = 2\n\n[application]\nname = \"api\"\n\n[variables]\ntoken = { required = true }\nregion = { default = \"eu\" }\n\n[[trigger.http]]\nroute = \"/...\"\ncomponent = \"api\"\n\n[component.api]\nsource = \"api.wasm\"\n";
+        let app = spin_variables::app::app_from_toml_str(manifest).unwrap();
+        let status = |loaded| AppStatus { source: "api/spin.toml".to_owned(), loaded };
+
+        assert_eq!(status(Ok((app, Default::default()))).summary(16), "api/spin.toml     1 required variable(s) unset, of 2");
+        assert_eq!(status(Err(anyhow::anyhow!("bad manifest"))).summary(0), "api/spin.toml  error (pick to see it)");
+    }
+}
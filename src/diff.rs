@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use spin_variables::changes;
+use spin_variables::cluster::{self, Provision};
+use spin_variables::format::Versioned;
+
+use crate::{kubectl, AppOptions, RegistryFlags};
+
+#[derive(clap::Args)]
+pub struct DiffCommand {
+    /// The earlier version of the application: a manifest file, a directory
+    /// containing a spin.toml file, or a registry reference. With --cluster, this
+    /// is the application to compare with the cluster, defaulting to spin.toml.
+    old: Option<String>,
+
+    /// The later version of the application, in the same forms.
+    #[clap(conflicts_with = "cluster")]
+    new: Option<String>,
+
+    /// Compare the application with the variables that this SpinApp, in the current
+    /// kube context, provides (including its referenced Secrets and ConfigMaps).
+    #[clap(long = "cluster", value_name = "SPINAPP")]
+    cluster: Option<String>,
+
+    /// The namespace of the SpinApp. If omitted, this is the kube context's namespace.
+    #[clap(long = "namespace", requires = "cluster")]
+    namespace: Option<String>,
+
+    /// How to output the differences: text, json (a document of added, removed
+    /// and modified variables), or json-patch (an RFC 6902 JSON Patch that turns the
+    /// `-o json` output of the earlier version into that of the later one).
+    #[clap(short = 'o', long = "output", default_value = "text", value_parser = ["text", "json", "json-patch"])]
+    output: String,
+
+    #[clap(flatten)]
+    registry: RegistryFlags,
+}
+
+impl DiffCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        if let Some(spinapp) = &self.cluster {
+            return self.run_against_cluster(spinapp).await;
+        }
+
+        let (Some(old), Some(new)) = (&self.old, &self.new) else {
+            anyhow::bail!("Specify two versions of the application to compare, or --cluster");
+        };
+        let old = self.load(old).await?;
+        let new = self.load(new).await?;
+        let changes = changes::changes(&old.variables, &new.variables);
+
+        match self.output.as_str() {
+            "json" => println!("{}", serde_json::to_string_pretty(&Versioned::new(changes::json_diff(&changes)))?),
+            "json-patch" => println!("{}", serde_json::to_string_pretty(&changes::json_patch(&old.variables, &new.variables, &Default::default())?)?),
+            _ if changes.is_empty() => println!("No differences in variables"),
+            _ => {
+                for change in &changes {
+                    println!("{change}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_against_cluster(&self, spinapp_name: &str) -> anyhow::Result<()> {
+        let source = self.old.as_deref().unwrap_or(spin_variables::app::DEFAULT_MANIFEST_FILE);
+        let app = self.load(source).await?;
+
+        let namespace = self.namespace.as_deref();
+        let Some(spinapp) = kubectl::get("spinapp", spinapp_name, namespace)? else {
+            anyhow::bail!("SpinApp '{spinapp_name}' not found");
+        };
+        let provided = cluster::spinapp_variables(&spinapp);
+
+        let mut resources = HashMap::new();
+        for provision in provided.iter().map(|(_, p)| p) {
+            let (kind, name) = match provision {
+                Provision::SecretKey { name, .. } => ("secret", name),
+                Provision::ConfigMapKey { name, .. } => ("configmap", name),
+                Provision::Value | Provision::Other => continue,
+            };
+            if !resources.contains_key(&(kind, name)) {
+                resources.insert((kind, name), kubectl::get(kind, name, namespace)?);
+            }
+        }
+        let key_exists = |provision: &Provision| {
+            let (kind, name, key) = match provision {
+                Provision::SecretKey { name, key } => ("secret", name, key),
+                Provision::ConfigMapKey { name, key } => ("configmap", name, key),
+                Provision::Value | Provision::Other => return true,
+            };
+            resources.get(&(kind, name)).and_then(|r| r.as_ref()).is_some_and(|r| cluster::has_key(r, key))
+        };
+
+        let gaps = cluster::gaps(&app.variables, &provided, key_exists);
+        if gaps.is_empty() {
+            println!("SpinApp '{spinapp_name}' provides all the variables the application needs");
+        }
+        for gap in &gaps {
+            let severity = if gap.is_error() { "error" } else { "warning" };
+            println!("{severity}: {gap}");
+        }
+
+        let errors = gaps.iter().filter(|g| g.is_error()).count();
+        if errors > 0 {
+            anyhow::bail!("The cluster is missing {errors} variable(s) the application needs");
+        }
+        Ok(())
+    }
+
+    async fn load(&self, source: &str) -> anyhow::Result<spin_variables::AppInfo> {
+        let app = AppOptions {
+            from: Some(source.to_owned()),
+            registry: self.registry.clone(),
+        };
+        app.load_app().await
+    }
+}
@@ -0,0 +1,321 @@
+use std::path::PathBuf;
+
+use spin_variables::app;
+use spin_variables::edit::{Declaration, ManifestEditor};
+use spin_variables::env;
+use spin_variables::usage;
+
+use crate::AppOptions;
+
+/// The manifest to edit, which must be a local file rather than a registry reference.
+fn manifest_editor(app: &AppOptions) -> anyhow::Result<ManifestEditor> {
+    match app.manifest_path()? {
+        Some(path) => ManifestEditor::load(path),
+        None => anyhow::bail!("Only manifest files can be edited, not registry references"),
+    }
+}
+
+#[derive(clap::Args)]
+pub struct SetDefaultCommand {
+    /// The variable whose default to set. It must already be declared.
+    name: String,
+
+    /// The new default value.
+    value: String,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl SetDefaultCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut editor = manifest_editor(&self.app)?;
+        let was_required = editor.set_default(&self.name, &self.value)?;
+        editor.save()?;
+
+        println!("Set the default of '{}'", self.name);
+        if was_required {
+            println!("'{}' is no longer required, since it now has a default", self.name);
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+#[clap(group(clap::ArgGroup::new("flags").required(true).multiple(true).args(["secret", "no_secret", "required", "optional"])))]
+pub struct ModifyCommand {
+    /// The variable to change. It must already be declared.
+    name: String,
+
+    /// Mark the variable as secret.
+    #[clap(long = "secret", num_args = 0, conflicts_with = "no_secret")]
+    secret: bool,
+
+    /// Mark the variable as not secret.
+    #[clap(long = "no-secret", num_args = 0)]
+    no_secret: bool,
+
+    /// Make the variable required. This is refused if it has a default.
+    #[clap(long = "required", num_args = 0, conflicts_with = "optional")]
+    required: bool,
+
+    /// Make the variable optional.
+    #[clap(long = "optional", num_args = 0)]
+    optional: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl ModifyCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let flag = |on: bool, off: bool| (on || off).then_some(on);
+
+        let mut editor = manifest_editor(&self.app)?;
+        editor.set_flags(&self.name, flag(self.secret, self.no_secret), flag(self.required, self.optional))?;
+        editor.save()?;
+
+        println!("Updated '{}'", self.name);
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct ImportEnvCommand {
+    /// The dotenv file to import variables from.
+    file: PathBuf,
+
+    /// The prefix of the entries to import. Each entry PREFIX_NAME becomes a
+    /// variable called name.
+    #[clap(long = "env-prefix", default_value = env::DEFAULT_PREFIX)]
+    env_prefix: String,
+
+    /// Accept the guesses of which variables are secret, based on their names,
+    /// instead of asking about each one.
+    #[clap(long = "yes", short = 'y', num_args = 0)]
+    yes: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl ImportEnvCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut editor = manifest_editor(&self.app)?;
+        let entries = env::variables_in_dotenv(&self.file, &self.env_prefix)?;
+        if entries.is_empty() {
+            println!("{} has no {}_ entries to import", self.file.display(), self.env_prefix);
+            return Ok(());
+        }
+
+        let mut imported = vec![];
+        for (name, value) in entries {
+            if editor.is_declared(&name) {
+                println!("Skipping '{name}', which is already declared");
+                continue;
+            }
+            if let Err(e) = app::validate_variable_name(&name) {
+                println!("Skipping '{name}', which is not a valid variable name: {e}");
+                continue;
+            }
+
+            let secret = if self.yes {
+                app::looks_secret(&name)
+            } else {
+                dialoguer::Confirm::new()
+                    .with_prompt(format!("Is '{name}' a secret?"))
+                    .default(app::looks_secret(&name))
+                    .interact()?
+            };
+
+            // Secret values stay in the dotenv file rather than being copied into the manifest
+            let declaration = if secret {
+                Declaration { default: None, required: true, secret: true }
+            } else {
+                Declaration { default: Some(value), required: false, secret: false }
+            };
+            editor.declare(&name, &declaration)?;
+            imported.push(name);
+        }
+
+        editor.save()?;
+        println!("Imported {} variable(s): {}", imported.len(), imported.join(", "));
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct InitCommand {
+    /// The first variable to declare. If omitted, you are asked about it.
+    #[clap(long = "name")]
+    name: Option<String>,
+
+    /// A default for the variable. Without one, the variable is required.
+    #[clap(long = "default", requires = "name")]
+    default: Option<String>,
+
+    /// Mark the variable as secret.
+    #[clap(long = "secret", num_args = 0, requires = "name", conflicts_with = "default")]
+    secret: bool,
+
+    /// The component to pass the variable to. If omitted, this is the only component,
+    /// if there is just one.
+    #[clap(long = "component", requires = "name")]
+    component: Option<String>,
+
+    /// The name of the component variable that refers to the variable. If omitted, the
+    /// names are the same.
+    #[clap(long = "component-variable", requires = "name")]
+    component_variable: Option<String>,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+/// The choices for the first variable.
+struct FirstVariable {
+    name: String,
+    declaration: Declaration,
+    component: Option<(String, String)>,
+}
+
+impl InitCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let app = self.app.load_app().await?;
+        let mut editor = manifest_editor(&self.app)?;
+        if editor.has_variables() {
+            anyhow::bail!("The manifest already has a [variables] table. Use set-default or modify to change it");
+        }
+
+        let component_ids: Vec<_> = app.components.iter().map(|c| c.id.clone()).collect();
+        let first = match &self.name {
+            Some(name) => self.from_flags(name, &component_ids),
+            None => ask(&component_ids)?,
+        };
+
+        editor.declare(&first.name, &first.declaration)?;
+        if let Some((component, component_variable)) = &first.component {
+            editor.set_component_variable(component, component_variable, &format!("{{{{ {} }}}}", first.name))?;
+        }
+        editor.save()?;
+
+        println!("Declared '{}'", first.name);
+        if let Some((component, component_variable)) = &first.component {
+            println!("Component '{component}' reads it as '{component_variable}', through the template {{{{ {} }}}}", first.name);
+        }
+        Ok(())
+    }
+
+    fn from_flags(&self, name: &str, component_ids: &[String]) -> FirstVariable {
+        let component = self.component.clone().or_else(|| match component_ids {
+            [only] => Some(only.clone()),
+            _ => None,
+        });
+        FirstVariable {
+            name: name.to_owned(),
+            declaration: Declaration {
+                default: self.default.clone(),
+                required: self.default.is_none(),
+                secret: self.secret,
+            },
+            component: component.map(|c| (c, self.component_variable.clone().unwrap_or_else(|| name.to_owned()))),
+        }
+    }
+}
+
+fn ask(component_ids: &[String]) -> anyhow::Result<FirstVariable> {
+    let name: String = dialoguer::Input::new()
+        .with_prompt("Variable name")
+        .validate_with(|name: &String| app::validate_variable_name(name))
+        .interact_text()?;
+    let secret = dialoguer::Confirm::new()
+        .with_prompt(format!("Is '{name}' a secret?"))
+        .default(app::looks_secret(&name))
+        .interact()?;
+    // Secrets don't get defaults, since those would be stored in the manifest
+    let default = match secret {
+        true => None,
+        false => {
+            let default: String = dialoguer::Input::new()
+                .with_prompt("Default value (leave empty to make it required)")
+                .allow_empty(true)
+                .interact_text()?;
+            Some(default).filter(|d| !d.is_empty())
+        }
+    };
+
+    let component = match component_ids {
+        [] => None,
+        [only] => Some(only.clone()),
+        _ => {
+            let index = dialoguer::Select::new()
+                .with_prompt("Which component should use it?")
+                .items(component_ids)
+                .default(0)
+                .interact()?;
+            Some(component_ids[index].clone())
+        }
+    };
+    let component = match component {
+        Some(component) => {
+            let component_variable: String = dialoguer::Input::new()
+                .with_prompt(format!("Name of the variable in component '{component}'"))
+                .default(name.clone())
+                .validate_with(|name: &String| app::validate_variable_name(name))
+                .interact_text()?;
+            Some((component, component_variable))
+        }
+        None => None,
+    };
+
+    Ok(FirstVariable {
+        declaration: Declaration {
+            required: default.is_none(),
+            default,
+            secret,
+        },
+        name,
+        component,
+    })
+}
+
+#[derive(clap::Args)]
+pub struct ScaffoldCommand {
+    /// Show the declarations that would be added, without changing the manifest.
+    #[clap(long = "dry-run", num_args = 0)]
+    dry_run: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl ScaffoldCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let app = self.app.load_app().await?;
+        let undeclared = usage::undeclared_references(&app);
+        if undeclared.is_empty() {
+            println!("Every variable used in templates is declared");
+            return Ok(());
+        }
+
+        let mut editor = manifest_editor(&self.app)?;
+        for (name, site) in &undeclared {
+            // Required, so that nothing runs until someone decides on a value or a default
+            let declaration = Declaration {
+                default: None,
+                required: true,
+                secret: app::looks_secret(name),
+            };
+            editor.declare(name, &declaration)?;
+            let verb = if self.dry_run { "Would declare" } else { "Declared" };
+            println!("{verb} '{name}' (used by {site}){}", if declaration.secret { " as a secret" } else { "" });
+        }
+
+        if self.dry_run {
+            println!("Dry run: the manifest was not changed");
+        } else {
+            editor.save()?;
+        }
+        Ok(())
+    }
+}
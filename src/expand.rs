@@ -0,0 +1,150 @@
+use spin_variables::app::AppInfo;
+use spin_variables::format;
+use spin_variables::templates;
+use spin_variables::triggers::TriggerRegistry;
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct ExpandCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// Include the values of secret variables in expansions. Otherwise they are
+    /// replaced with REDACTED.
+    #[clap(long = "reveal-secrets", num_args = 0)]
+    reveal_secrets: bool,
+}
+
+impl ExpandCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+        if !self.reveal_secrets {
+            let redacted = format::redact_secrets(&mut app.variables);
+            if !redacted.is_empty() {
+                eprintln!("Warning: the values of secret variables {} were redacted. Pass --reveal-secrets to include them", redacted.join(", "));
+            }
+        }
+
+        let sections = [
+            ("Component variables", format_component_variables(&app)),
+            ("Allowed outbound hosts", format_outbound_hosts(&app)),
+            ("Trigger settings", format_trigger_settings(&app, &TriggerRegistry::with_builtins())),
+        ];
+
+        let mut printed_any = false;
+        for (title, table) in sections {
+            if let Some(table) = table {
+                if printed_any {
+                    println!();
+                }
+                println!("{title}:");
+                println!("{table}");
+                printed_any = true;
+            }
+        }
+
+        if !printed_any {
+            println!("The application has no templates to expand");
+        }
+
+        Ok(())
+    }
+}
+
+fn format_component_variables(app: &AppInfo) -> Option<comfy_table::Table> {
+    let mut rows = vec![];
+
+    for component in &app.components {
+        for (name, template) in &component.variables {
+            rows.push(vec![
+                component.id.clone(),
+                name.clone(),
+                template.clone(),
+                expansion_text(template, app),
+            ]);
+        }
+    }
+
+    table_of(vec!["Component", "Variable", "Template", "Expands to"], rows)
+}
+
+fn format_outbound_hosts(app: &AppInfo) -> Option<comfy_table::Table> {
+    let mut rows = vec![];
+
+    for component in &app.components {
+        for host in &component.allowed_outbound_hosts {
+            rows.push(vec![
+                component.id.clone(),
+                host.clone(),
+                expansion_text(host, app),
+            ]);
+        }
+    }
+
+    table_of(vec!["Component", "Allowed outbound host", "Expands to"], rows)
+}
+
+/// Templates in settings that the trigger type doesn't expand are reported as not
+/// being expanded. Trigger types the registry doesn't know have unknown schemas, so
+/// we report their templates on a best-effort basis.
+fn format_trigger_settings(app: &AppInfo, triggers: &TriggerRegistry) -> Option<comfy_table::Table> {
+    let mut rows = vec![];
+    let mut unknown_types = vec![];
+
+    for trigger in &app.triggers {
+        for (setting, value) in &trigger.settings {
+            if !value.contains("{{") {
+                continue;
+            }
+            let expansion = match triggers.supports_templates(&trigger.trigger_type, setting) {
+                Some(false) => "NOT EXPANDED: this setting does not support templates".to_owned(),
+                Some(true) => expansion_text(value, app),
+                None => {
+                    if !unknown_types.contains(&trigger.trigger_type) {
+                        unknown_types.push(trigger.trigger_type.clone());
+                    }
+                    expansion_text(value, app)
+                }
+            };
+            rows.push(vec![
+                trigger.display_name(),
+                setting.clone(),
+                value.clone(),
+                expansion,
+            ]);
+        }
+    }
+
+    for trigger_type in unknown_types {
+        eprintln!("Warning: the settings of '{trigger_type}' triggers are not known, so expansions shown for them assume every setting supports templates");
+    }
+
+    table_of(vec!["Trigger", "Setting", "Template", "Expands to"], rows)
+}
+
+/// Builds a table of the given rows, or `None` if there are no rows to show.
+fn table_of(header: Vec<&str>, rows: Vec<Vec<String>>) -> Option<comfy_table::Table> {
+    if rows.is_empty() {
+        return None;
+    }
+
+    let mut table = comfy_table::Table::new();
+    table.set_header(comfy_table::Row::from(header));
+    table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+    for row in rows {
+        table.add_row(row);
+    }
+    Some(table)
+}
+
+fn expansion_text(template: &str, app: &AppInfo) -> String {
+    match templates::expand(template, &app.variables) {
+        Ok(expanded) => expanded,
+        Err(e) => format!("ERROR: {e}"),
+    }
+}
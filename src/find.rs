@@ -0,0 +1,67 @@
+use std::io::IsTerminal;
+
+use spin_variables::app::{AppInfo, VariableInfo};
+use spin_variables::usage;
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct FindCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+}
+
+impl FindCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        if !std::io::stderr().is_terminal() {
+            anyhow::bail!("find is interactive, and needs a terminal");
+        }
+
+        let mut app = self.app.load_app().await?;
+        self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+        if app.variables.is_empty() {
+            println!("The application has no variables");
+            return Ok(());
+        }
+
+        let names: Vec<_> = app.variables.iter().map(|v| v.name.as_str()).collect();
+        let selection = dialoguer::FuzzySelect::new()
+            .with_prompt("Variable")
+            .items(&names)
+            .default(0)
+            .interact_opt()?;
+
+        if let Some(index) = selection {
+            println!("{}", details(&app, &app.variables[index]));
+        }
+        Ok(())
+    }
+}
+
+/// Everything about a variable: its declaration, its value, and where it's used.
+fn details(app: &AppInfo, variable: &VariableInfo) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut lines = vec![
+        format!("Name:      {}", variable.name),
+        format!("Required:  {}", yes_no(variable.required)),
+        format!("Secret:    {}", yes_no(variable.secret)),
+        format!("Default:   {}", variable.default_value.as_deref().unwrap_or("(none)")),
+    ];
+    lines.push(match &variable.value {
+        Some(resolved) if variable.secret => format!("Value:     (hidden) from {}", resolved.source),
+        Some(resolved) => format!("Value:     {} from {}", resolved.value, resolved.source),
+        None => "Value:     (none)".to_owned(),
+    });
+
+    let sites = usage::sites_using(app, &variable.name);
+    if sites.is_empty() {
+        lines.push("Used by:   nothing".to_owned());
+    } else {
+        lines.push("Used by:".to_owned());
+        lines.extend(sites.iter().map(|site| format!("  {site}: {}", site.template)));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Context;
+
+/// A commit, as shown in history and blame output.
+#[derive(Clone, serde::Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    /// The author date, as YYYY-MM-DD.
+    pub date: String,
+    pub subject: String,
+}
+
+impl Commit {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+}
+
+impl std::fmt::Display for Commit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}: {}", self.short_hash(), self.date, self.author, self.subject)
+    }
+}
+
+/// The `git log` format that [`parse_commit`] reads: fields separated by the ASCII
+/// unit separator, which won't appear in names or subjects.
+const COMMIT_FORMAT: &str = "--format=%H%x1f%an%x1f%as%x1f%s";
+
+/// The commits that changed a file, oldest first. Renames aren't followed.
+pub fn log(path: &Path) -> anyhow::Result<Vec<Commit>> {
+    let (dir, file) = split(path)?;
+    let output = run(dir, &["log", "--reverse", COMMIT_FORMAT, "--", file])?;
+    output.lines().map(parse_commit).collect()
+}
+
+/// A file's content at a commit, or `None` if it didn't exist then, such as in the
+/// commit that deleted it.
+pub fn show(path: &Path, commit: &str) -> anyhow::Result<Option<String>> {
+    let (dir, file) = split(path)?;
+    let output = git(dir, &["show", &format!("{commit}:./{file}")])?;
+    Ok(output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+fn parse_commit(line: &str) -> anyhow::Result<Commit> {
+    let mut fields = line.splitn(4, '\x1f').map(|f| f.to_owned());
+    let mut field = || fields.next().with_context(|| format!("Unexpected git log output '{line}'"));
+    Ok(Commit {
+        hash: field()?,
+        author: field()?,
+        date: field()?,
+        subject: field()?,
+    })
+}
+
+/// The directory to run git in, and the file name within it.
+fn split(path: &Path) -> anyhow::Result<(&Path, &str)> {
+    let file = path.file_name().and_then(|f| f.to_str()).with_context(|| format!("{} is not a file name git can use", path.display()))?;
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Ok((dir, file))
+}
+
+fn run(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = git(dir, args)?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<std::process::Output> {
+    Command::new("git").arg("-C").arg(dir).args(args).output().context("Failed to run git. Is it installed and on your PATH?")
+}
@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use spin_variables::app;
+use spin_variables::changes::{self, Change};
+use spin_variables::format::Versioned;
+
+use crate::git::{self, Commit};
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct HistoryCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// Show only the history of this variable.
+    variable: Option<String>,
+
+    /// How to output the history: text, or json (a document of commits and the
+    /// variable changes in each).
+    #[clap(short = 'o', long = "output", default_value = "text", value_parser = ["text", "json"])]
+    output: String,
+}
+
+/// A commit that changed the variables, with the changes in it.
+#[derive(serde::Serialize)]
+pub struct HistoryEntry {
+    #[serde(flatten)]
+    pub commit: Commit,
+    pub changes: Vec<VariableChange>,
+}
+
+#[derive(serde::Serialize)]
+pub struct VariableChange {
+    pub name: String,
+    /// `added`, `removed` or `modified`.
+    pub change: &'static str,
+    /// The declaration for added variables, or what changed for modified ones.
+    details: Vec<String>,
+}
+
+impl std::fmt::Display for VariableChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self.change {
+            "added" => "+",
+            "removed" => "-",
+            _ => "~",
+        };
+        write!(f, "{symbol} {}", self.name)?;
+        if !self.details.is_empty() {
+            write!(f, ": {}", self.details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl HistoryCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let Some(manifest_path) = self.app.manifest_path()? else {
+            anyhow::bail!("history needs a manifest file in a git repository, not a registry reference or OCI layout");
+        };
+
+        let mut entries = history(&manifest_path)?;
+        if let Some(name) = &self.variable {
+            for entry in &mut entries {
+                entry.changes.retain(|c| &c.name == name);
+            }
+            entries.retain(|e| !e.changes.is_empty());
+        }
+
+        if self.output == "json" {
+            println!("{}", serde_json::to_string_pretty(&Versioned::new(serde_json::json!({ "commits": entries })))?);
+            return Ok(());
+        }
+        if entries.is_empty() {
+            println!("No commits change the variables of {}", manifest_path.display());
+        }
+        for entry in &entries {
+            println!("{}", entry.commit);
+            for change in &entry.changes {
+                println!("  {change}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The commits that changed the manifest's variable declarations, oldest first.
+pub fn history(manifest_path: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let mut entries = vec![];
+    let mut previous = vec![];
+    for commit in git::log(manifest_path)? {
+        let variables = match git::show(manifest_path, &commit.hash)? {
+            Some(text) => match app::app_from_toml_str(&text) {
+                Ok(app) => app.variables,
+                Err(e) => {
+                    eprintln!("Warning: skipping {}, where the manifest can't be read: {e:#}", commit.short_hash());
+                    continue;
+                }
+            },
+            None => vec![],
+        };
+        let changes: Vec<_> = changes::changes(&previous, &variables).iter().filter_map(variable_change).collect();
+        if !changes.is_empty() {
+            entries.push(HistoryEntry { commit, changes });
+        }
+        previous = variables;
+    }
+    Ok(entries)
+}
+
+/// A change to record, or `None` if only the value changed, since the history is of
+/// declarations.
+fn variable_change(change: &Change) -> Option<VariableChange> {
+    let name = change.name().to_owned();
+    let (kind, details) = match change {
+        Change::Added(variable) => ("added", vec![changes::describe_declaration(variable)]),
+        Change::Removed(_) => ("removed", vec![]),
+        Change::Modified { before, after } => ("modified", Some(changes::declaration_changes(before, after)).filter(|d| !d.is_empty())?),
+    };
+    Some(VariableChange { name, change: kind, details })
+}
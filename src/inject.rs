@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use spin_variables::{env, format, inject};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct InjectCommand {
+    /// The Kubernetes manifest to update, containing a SpinApp or Deployment.
+    #[clap(long = "into", value_name = "FILE")]
+    into: PathBuf,
+
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+}
+
+impl InjectCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        let env_providers = self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+        let context = format::FormatContext {
+            env_prefix: env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned(),
+            app_name: app.name.clone(),
+            ..Default::default()
+        };
+
+        let manifest = std::fs::read_to_string(&self.into).with_context(|| format!("Failed to read {}", self.into.display()))?;
+        let updated = inject::inject(&manifest, &app.variables, &context).with_context(|| format!("Failed to update {}", self.into.display()))?;
+        std::fs::write(&self.into, updated).with_context(|| format!("Failed to write {}", self.into.display()))?;
+        println!("Updated {}", self.into.display());
+        Ok(())
+    }
+}
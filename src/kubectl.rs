@@ -0,0 +1,50 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::Context;
+
+/// Applies Kubernetes resources to the current kube context. With a dry run
+/// strategy (`server` or `client`), kubectl validates them without making changes.
+pub fn apply(resources: &str, dry_run: Option<&str>) -> anyhow::Result<()> {
+    let mut command = Command::new("kubectl");
+    command.args(["apply", "-f", "-"]);
+    if let Some(strategy) = dry_run {
+        command.arg(format!("--dry-run={strategy}"));
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to run kubectl. Is it installed and on your PATH?")?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open kubectl input")?
+        .write_all(resources.as_bytes())
+        .context("Failed to send resources to kubectl")?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("kubectl apply failed ({status})");
+    }
+    Ok(())
+}
+
+/// Gets a resource from the current kube context as JSON, or `None` if it doesn't
+/// exist.
+pub fn get(kind: &str, name: &str, namespace: Option<&str>) -> anyhow::Result<Option<serde_json::Value>> {
+    let mut command = Command::new("kubectl");
+    command.args(["get", kind, name, "-o", "json", "--ignore-not-found"]);
+    if let Some(namespace) = namespace {
+        command.args(["--namespace", namespace]);
+    }
+
+    let output = command.output().context("Failed to run kubectl. Is it installed and on your PATH?")?;
+    if !output.status.success() {
+        anyhow::bail!("kubectl get {kind} {name} failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    if output.stdout.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&output.stdout).with_context(|| format!("Failed to parse {kind} {name} from kubectl"))?))
+}
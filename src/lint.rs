@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use spin_variables::format::Versioned;
+use spin_variables::lint::{self, Level, Rule, Severity};
+use spin_variables::reports;
+use spin_variables::locations::ManifestLocator;
+
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct LintCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// Print the findings as a JSON document instead of text.
+    #[clap(long = "json", num_args = 0)]
+    json: bool,
+
+    /// Write a report of the findings in the given format (github or sarif) for code
+    /// scanning tools, to FILE if given or else to standard output.
+    #[clap(long = "report", value_names = ["FORMAT", "FILE"], num_args = 1..=2, conflicts_with = "json")]
+    report: Vec<String>,
+
+    /// Change how a rule's findings are reported, in the form RULE=LEVEL where LEVEL
+    /// is off, warning or error, such as secret-default=warning. May be repeated.
+    #[clap(long = "rule", value_name = "RULE=LEVEL", value_parser = lint::parse_rule_level)]
+    rules: Vec<(Rule, Level)>,
+
+    /// Also check each component's built Wasm against the manifest, for components
+    /// that read variables without being given any, or the other way round.
+    #[clap(long = "wasm", num_args = 0)]
+    wasm: bool,
+
+    /// Run as a pre-commit hook: lint only the spin.toml files among FILES (doing
+    /// nothing if there are none), print just the findings, and fail on errors.
+    #[clap(long = "hook", num_args = 0, conflicts_with_all = ["json", "report"])]
+    hook: bool,
+
+    /// With --hook, the files that changed, as passed by pre-commit.
+    #[clap(value_name = "FILES", requires = "hook")]
+    files: Vec<PathBuf>,
+}
+
+impl LintCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        if self.hook {
+            return self.run_hook().await;
+        }
+
+        let findings = self.findings(&self.app).await?;
+
+        if let [format, file @ ..] = self.report.as_slice() {
+            let report = match format.as_str() {
+                "sarif" => reports::sarif(&findings)?,
+                "github" => reports::github_lint(&findings),
+                _ => anyhow::bail!("Unknown report format '{format}'. The available formats are: github, sarif"),
+            };
+            match file.first() {
+                Some(file) => {
+                    std::fs::write(file, report).with_context(|| format!("Failed to write report {file}"))?;
+                    println!("Wrote report to {file}");
+                }
+                None => println!("{report}"),
+            }
+        } else if self.json {
+            let document = Versioned::new(serde_json::json!({ "findings": &findings }));
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        } else if findings.is_empty() {
+            println!("No problems found");
+            return Ok(());
+        } else {
+            for finding in &findings {
+                println!("{finding}");
+            }
+        }
+
+        exit_status(&findings)
+    }
+
+    /// Only manifests are linted, so the hook can be run on every commit and stays
+    /// fast when spin.toml hasn't changed. If pre-commit passes no files, the
+    /// application from `--from` is linted.
+    async fn run_hook(&self) -> anyhow::Result<()> {
+        let manifests: Vec<_> = self.files.iter().filter(|f| f.file_name().is_some_and(|name| name == "spin.toml")).collect();
+        let apps = match (self.files.is_empty(), manifests.is_empty()) {
+            (true, _) => vec![self.app.clone()],
+            (false, true) => return Ok(()),
+            (false, false) => manifests.into_iter().map(|manifest| AppOptions {
+                from: Some(manifest.to_string_lossy().into_owned()),
+                ..self.app.clone()
+            }).collect(),
+        };
+
+        let mut findings = vec![];
+        for app in &apps {
+            findings.extend(self.findings(app).await?);
+        }
+        for finding in &findings {
+            println!("{finding}");
+        }
+        exit_status(&findings)
+    }
+
+    async fn findings(&self, app_options: &AppOptions) -> anyhow::Result<Vec<lint::Finding>> {
+        let locator = match app_options.manifest_path()? {
+            Some(path) => Some(ManifestLocator::load(path)?),
+            None => None,
+        };
+
+        if self.wasm && locator.is_none() {
+            anyhow::bail!("--wasm needs a local manifest, since it reads the components' Wasm files");
+        }
+
+        let findings = match app_options.load_app().await {
+            Ok(app) => {
+                let mut findings = lint::lint(&app, locator.as_ref());
+                if let (true, Some(locator)) = (self.wasm, &locator) {
+                    findings.extend(lint::variables_import_mismatches(&app, locator));
+                }
+                findings
+            }
+            // Spin rejects manifests with invalid names, but we can still report
+            // them with their locations from the raw TOML.
+            Err(e) => match &locator {
+                Some(locator) => lint::lint_unloadable(locator, e),
+                None => return Err(e),
+            },
+        };
+        Ok(lint::configure(findings, &self.rules))
+    }
+}
+
+fn exit_status(findings: &[lint::Finding]) -> anyhow::Result<()> {
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    if errors > 0 {
+        anyhow::bail!("Lint found {errors} error(s)");
+    }
+    Ok(())
+}
@@ -1,156 +1,771 @@
 use std::path::{Path, PathBuf};
 
+use anyhow::Context;
+use spin_variables::app::{self, AppInfo, AppSource, VariableInfo};
+use spin_variables::locations::ManifestLocator;
+use spin_variables::providers::{ProviderChain, ProviderLabel};
+use spin_variables::registry::{self, RegistryOptions};
+use spin_variables::schema::Schema;
+use spin_variables::{cluster, env, format, generate, keychain, oci_layout, values};
+
+mod blame;
+mod check;
+mod components;
+mod consistency;
+mod dashboard;
+mod diff;
+mod edit;
+mod expand;
+mod find;
+mod git;
+mod history;
+mod inject;
+mod kubectl;
+mod lint;
+mod mcp;
+mod pager;
+mod preflight;
+mod scan;
+mod serve;
+mod set;
+mod snapshots;
+mod store;
+mod profiles;
+mod prompt;
+mod tags;
+mod watch;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     use clap::Parser;
     let cmd = VariablesCommand::parse();
+    init_tracing(cmd.verbose);
     cmd.run().await
 }
 
+/// Sends diagnostics to standard error, with the time each step took. `-v` shows
+/// the main steps, `-vv` shows each variable's resolution, and `-vvv` adds Spin's
+/// own diagnostics. `RUST_LOG` overrides this.
+fn init_tracing(verbosity: u8) {
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    let directives = match verbosity {
+        0 => "warn".to_owned(),
+        1 => "warn,spin_variables=info,variables=info".to_owned(),
+        2 => "warn,spin_variables=debug,variables=debug".to_owned(),
+        _ => "debug".to_owned(),
+    };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(directives));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 #[derive(clap::Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
 struct VariablesCommand {
-    /// The application whose variables to view. This may be a manifest (spin.toml) file, a
-    /// directory containing a spin.toml file, or a remote registry reference.
-    /// If omitted, it defaults to "spin.toml".
-    #[clap(short = 'f', long = "from")]
-    from: Option<String>,
+    #[clap(subcommand)]
+    subcommand: Option<Subcommand>,
 
-    /// Ignore server certificate errors from a registry
-    #[clap(short = 'k', long = "insecure", num_args = 0)]
-    insecure: bool,
+    #[clap(flatten)]
+    list: ListCommand,
+
+    /// Show diagnostics, with timings, on standard error. Repeat for more detail.
+    #[clap(short = 'v', long = "verbose", action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Manage named sets of variable values, such as per-environment values.
+    #[clap(subcommand)]
+    Profile(profiles::ProfileCommand),
+    /// Show how component variables, allowed outbound hosts and trigger settings expand with
+    /// the current values.
+    Expand(expand::ExpandCommand),
+    /// Check that every required variable has a value, for example before deploying.
+    Check(check::CheckCommand),
+    /// Pick a variable from a searchable list, and show its details and where it is used.
+    Find(find::FindCommand),
+    /// Check the application's variables for likely mistakes, such as invalid names or templates,
+    /// variables that are never used, or templates that refer to undeclared variables.
+    Lint(lint::LintCommand),
+    /// Show, for each component, the variables it sees, the application variables they are
+    /// derived from, and whether they can be satisfied with the current values.
+    Components(components::ComponentsCommand),
+    /// Show how the variables differ between two versions of an application.
+    Diff(diff::DiffCommand),
+    /// Show, from the git log of the manifest, when each variable was added or removed, or
+    /// had whether it is required or secret, or its default, changed.
+    History(history::HistoryCommand),
+    /// Show the commit, author and date that introduced each variable's declaration in
+    /// the manifest, from its git history.
+    Blame(blame::BlameCommand),
+    /// Report variables that several applications share, but declare differently.
+    Consistency(consistency::ConsistencyCommand),
+    /// List the variables of several applications, such as all those in a workspace, as each
+    /// application is loaded.
+    Scan(scan::ScanCommand),
+    /// Pick from several applications, such as all those in a workspace, each shown with how
+    /// many of its required variables have no value, and show the variables of the one picked.
+    /// Dotenv files and profiles are looked up in each application's own directory.
+    Dashboard(dashboard::DashboardCommand),
+    /// Set the application's variables in an existing Kubernetes SpinApp or Deployment manifest.
+    Inject(inject::InjectCommand),
+    /// Ask for the values of variables that don't have them, and save them to a values file.
+    Prompt(prompt::PromptCommand),
+    /// Keep watching the manifest and values files, printing the variables that change.
+    Watch(watch::WatchCommand),
+    /// Set a variable's default value in the manifest, keeping its comments and layout.
+    SetDefault(edit::SetDefaultCommand),
+    /// Add a [variables] table to a manifest that has none, with a first variable passed to a
+    /// component, to show how variables reach code.
+    Init(edit::InitCommand),
+    /// Change whether a variable is secret or required in the manifest.
+    Modify(edit::ModifyCommand),
+    /// Declare a variable in the manifest for each SPIN_VARIABLE_* entry in a dotenv file.
+    ImportEnv(edit::ImportEnvCommand),
+    /// Declare, as required variables, any variables that templates use but the manifest
+    /// doesn't declare.
+    Scaffold(edit::ScaffoldCommand),
+    /// Store a variable's value for the application outside the manifest, such as in the
+    /// OS keychain.
+    Set(set::SetCommand),
+    /// Keep development and test values in a local database under .spin, instead of in
+    /// dotenv files.
+    #[clap(subcommand)]
+    Store(store::StoreCommand),
+    /// Resolve every variable and template the way `spin up` would, and report what would stop
+    /// it from starting.
+    Preflight(preflight::PreflightCommand),
+    /// Serve the application's variables, their usage and check results as JSON over HTTP,
+    /// for dashboards, editors and other tools.
+    Serve(serve::ServeCommand),
+    /// Save the current SPIN_VARIABLE_* environment variables to a timestamped snapshot file.
+    Snapshot(snapshots::SnapshotCommand),
+    /// Print a script that sets the environment variables from a snapshot again, for use
+    /// with `source` or `eval`.
+    Restore(snapshots::RestoreCommand),
+    /// Serve the Model Context Protocol over standard input and output, so AI coding assistants
+    /// can list, check and expand the application's variables. Secret values are never shown.
+    Mcp(mcp::McpCommand),
+}
+
+impl VariablesCommand {
+    async fn run(&self) -> anyhow::Result<()> {
+        match &self.subcommand {
+            None => self.list.run().await,
+            Some(Subcommand::Profile(cmd)) => cmd.run().await,
+            Some(Subcommand::Expand(cmd)) => cmd.run().await,
+            Some(Subcommand::Check(cmd)) => cmd.run().await,
+            Some(Subcommand::Find(cmd)) => cmd.run().await,
+            Some(Subcommand::Lint(cmd)) => cmd.run().await,
+            Some(Subcommand::Components(cmd)) => cmd.run().await,
+            Some(Subcommand::Consistency(cmd)) => cmd.run().await,
+            Some(Subcommand::Scan(cmd)) => cmd.run().await,
+            Some(Subcommand::Dashboard(cmd)) => cmd.run().await,
+            Some(Subcommand::Diff(cmd)) => cmd.run().await,
+            Some(Subcommand::History(cmd)) => cmd.run(),
+            Some(Subcommand::Blame(cmd)) => cmd.run().await,
+            Some(Subcommand::Inject(cmd)) => cmd.run().await,
+            Some(Subcommand::Prompt(cmd)) => cmd.run().await,
+            Some(Subcommand::Watch(cmd)) => cmd.run().await,
+            Some(Subcommand::SetDefault(cmd)) => cmd.run(),
+            Some(Subcommand::Init(cmd)) => cmd.run().await,
+            Some(Subcommand::Modify(cmd)) => cmd.run(),
+            Some(Subcommand::ImportEnv(cmd)) => cmd.run(),
+            Some(Subcommand::Scaffold(cmd)) => cmd.run().await,
+            Some(Subcommand::Set(cmd)) => cmd.run().await,
+            Some(Subcommand::Store(cmd)) => cmd.run().await,
+            Some(Subcommand::Preflight(cmd)) => cmd.run().await,
+            Some(Subcommand::Serve(cmd)) => cmd.run().await,
+            Some(Subcommand::Snapshot(cmd)) => cmd.run().await,
+            Some(Subcommand::Restore(cmd)) => cmd.run(),
+            Some(Subcommand::Mcp(cmd)) => cmd.run().await,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct ListCommand {
+    #[clap(flatten)]
+    app: AppOptions,
 
     /// How to output the variables. The available options are:
     /// 
+    /// * az - `az containerapp` commands setting secrets and environment variables
     /// * bash - a bash script which can be saved, edited, and used to export values
+    /// * cloud - `spin cloud variables set` commands for an app deployed to Fermyon Cloud
+    /// * cloudrun - a `gcloud run deploy` command setting environment variables and secrets
+    /// * consul - `consul kv put` commands storing the values in Consul's key/value store
+    /// * deploy - a `spin deploy` command passing the values as --variable arguments
+    /// * dokku - a `dokku config:set` command
+    /// * ecs - the environment and secrets of an ECS container definition, as JSON
+    /// * env-example - a .env.example file listing every variable, for committing as documentation
+    /// * fly - an [env] table for fly.toml, and a `fly secrets set` command for secrets
+    /// * gh - `gh secret set` and `gh variable set` commands for a GitHub repository
+    /// * heroku - a `heroku config:set` command
+    /// * json - a JSON document for use by other tools
+    /// * kube - a Kubernetes Secret and ConfigMap holding the values
+    /// * markdown - a Markdown table documenting the variables, for READMEs
+    /// * scaffold - a `spin kube scaffold` command passing the values as --variable arguments
     /// * table - a human-readable tabular display format
     /// 
     /// The default is table.
     #[clap(short = 'o', long = "output", default_value = "table")]
-    output: OutputFormat,
+    output: String,
+
+    /// With `-o kube`, apply the resources to the current Kubernetes context using
+    /// kubectl, instead of printing them.
+    #[clap(long = "apply", num_args = 0)]
+    apply: bool,
+
+    /// Fill in secret variables that have no value with random values, for setting up
+    /// development and test environments. The form is LENGTH[/CHARSET], where CHARSET
+    /// is alnum (the default), hex or base64, such as 64/hex. The default is 32.
+    #[clap(long = "generate-secrets", value_name = "SPEC", num_args = 0..=1, require_equals = true, default_missing_value = "32")]
+    generate_secrets: Option<generate::SecretSpec>,
+
+    /// With `-o markdown`, replace the content between the `<!-- spin-variables:begin -->`
+    /// and `<!-- spin-variables:end -->` markers in this file, instead of printing it.
+    #[clap(long = "inject", value_name = "FILE", conflicts_with = "apply")]
+    inject: Option<PathBuf>,
+
+    /// Include the values of secret variables in the output. Otherwise they are
+    /// replaced with REDACTED, except with --apply, where they go only to kubectl.
+    #[clap(long = "reveal-secrets", num_args = 0)]
+    reveal_secrets: bool,
+
+    /// Show the values of secret variables as digests instead of REDACTED, such as
+    /// `sha256:2c26b4...`, to compare them across environments without revealing them.
+    /// The only algorithm is sha256.
+    #[clap(long = "hash-values", value_name = "ALGORITHM", conflicts_with_all = ["reveal_secrets", "apply"])]
+    hash_values: Option<format::HashAlgorithm>,
+
+    /// With `--apply`, have kubectl validate the resources without changing anything.
+    /// The strategy is `server` (the default) or `client`.
+    #[clap(long = "dry-run", value_name = "STRATEGY", num_args = 0..=1, require_equals = true, default_missing_value = "server", value_parser = ["server", "client"], requires = "apply")]
+    dry_run: Option<String>,
+
+    /// Whether to show long output through a pager (`$PAGER`, or `less`) when
+    /// writing to a terminal.
+    #[clap(long = "pager", value_enum, default_value = "auto")]
+    pager: pager::PagerMode,
+
+    /// For a registry application, show which variables exist in every tag of its
+    /// repository, instead of listing the variables of one tag.
+    #[clap(long = "all-tags", num_args = 0)]
+    all_tags: bool,
+
+    /// List the variables in name order. Otherwise they are in the order the manifest
+    /// declares them (name order for registry applications, which don't keep one).
+    #[clap(long = "sort", num_args = 0)]
+    sort: bool,
+
+    /// Show long values in full, rather than cutting them short in the table.
+    #[clap(long = "full", num_args = 0, conflicts_with = "max_width")]
+    full: bool,
+
+    /// The longest a value may be in the table before it is cut short, in characters.
+    #[clap(long = "max-width", default_value_t = format::DEFAULT_MAX_VALUE_WIDTH)]
+    max_width: usize,
+
+    #[clap(flatten)]
+    values: ValueOptions,
 }
 
-impl VariablesCommand {
+impl ListCommand {
     async fn run(&self) -> anyhow::Result<()> {
-        let app_source = infer_app_source(&self.from)?;
+        if self.all_tags {
+            let AppSource::Registry(reference) = app::infer_app_source(&self.app.from)? else {
+                anyhow::bail!("--all-tags needs a registry reference");
+            };
+            let matrix = tags::all_tags_matrix(&reference, &self.app.registry.options(), 4).await?;
+            pager::print(&matrix.to_string(), self.pager);
+            return Ok(());
+        }
+
+        let mut app = self.app.load_app().await?;
+        if self.sort {
+            app.variables.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        let env_providers = self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
 
-        let variables = match app_source {
-            AppSource::File(manifest_file) => variables_from_toml(&manifest_file).await?,
-            AppSource::Registry(reference) => variables_from_registry_app(&reference, self.insecure).await?,
+        let mut context = format::FormatContext {
+            env_prefix: env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned(),
+            app_name: app.name.clone(),
+            schema: self.app.schema()?,
+            max_value_width: (!self.full).then_some(self.max_width),
+            value_lengths: Default::default(),
         };
 
-        println!("{}", self.format_variables(&variables));
+        let registry = format::FormatterRegistry::with_builtins();
+        let Some(formatter) = registry.get(&self.output) else {
+            let available = registry.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ");
+            anyhow::bail!("Unknown output format '{}'. The available formats are: {available}", self.output);
+        };
+        if let Some(spec) = &self.generate_secrets {
+            let generated = generate::generate_missing_secrets(&mut app.variables, spec);
+            if !generated.is_empty() {
+                eprintln!("Generated values for {}", generated.join(", "));
+            }
+        }
+        for warning in app.variables.iter().filter_map(spin_variables::check::suspicious_value) {
+            eprintln!("Warning: {warning}");
+        }
+        context.value_lengths = format::value_lengths(&app.variables);
+        if let Some(algorithm) = self.hash_values {
+            format::hash_secrets(&mut app.variables, algorithm);
+        } else if !self.reveal_secrets && !self.apply {
+            let redacted = format::redact_secrets(&mut app.variables);
+            if !redacted.is_empty() {
+                eprintln!("Warning: the values of secret variables {} were redacted. Pass --reveal-secrets to include them", redacted.join(", "));
+            }
+        }
+        let output = formatter.format(&app.variables, &context)?;
 
-        Ok(())
-    }
+        if let Some(path) = &self.inject {
+            if formatter.name() != "markdown" {
+                anyhow::bail!("--inject is only supported with -o markdown");
+            }
+            let document = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let updated = format::markdown::inject_between_markers(&document, &output).with_context(|| format!("Failed to update {}", path.display()))?;
+            if updated == document {
+                println!("{} is already up to date", path.display());
+            } else {
+                std::fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("Updated {}", path.display());
+            }
+            return Ok(());
+        }
 
-    fn format_variables(&self, variables: &[VariableInfo]) -> Box<dyn std::fmt::Display> {
-        match self.output {
-            OutputFormat::Table => Box::new(format_table(variables)),
-            OutputFormat::Bash => Box::new(format_bash(variables)),
+        if self.apply {
+            if formatter.name() != "kube" {
+                anyhow::bail!("--apply is only supported with -o kube");
+            }
+            let missing: Vec<_> = app.variables.iter().filter(|v| matches!(format::setting(v), format::Setting::Placeholder)).map(|v| v.name.as_str()).collect();
+            if !missing.is_empty() {
+                anyhow::bail!("Required variables {} have no values, so the resources would hold the placeholder {}. Give them values before applying", missing.join(", "), format::PLACEHOLDER);
+            }
+            return kubectl::apply(&output, self.dry_run.as_deref());
         }
+
+        pager::print(&output, self.pager);
+
+        Ok(())
     }
 }
 
-async fn variables_from_toml(path: impl AsRef<Path>) -> anyhow::Result<Vec<VariableInfo>> {
-    let manifest = spin_manifest::manifest_from_file(path)?;
-    let variables = manifest.variables.into_iter().map(|(name, variable)| VariableInfo {
-        name: name.to_string(),
-        default_value: variable.default,
-        required: variable.required,
-        secret: variable.secret,
-    }).collect();
-    Ok(variables)
-}
+/// Options for choosing the application to work with.
+#[derive(Clone, clap::Args)]
+struct AppOptions {
+    /// The application whose variables to view. This may be a manifest (spin.toml) file, a
+    /// directory containing a spin.toml file, a remote registry reference, or an OCI layout
+    /// as oci:DIR or oci-archive:FILE (optionally followed by :TAG).
+    /// If omitted, it defaults to "spin.toml".
+    #[clap(short = 'f', long = "from")]
+    from: Option<String>,
 
-async fn variables_from_registry_app(reference: &str, insecure: bool) -> anyhow::Result<Vec<VariableInfo>> {
-    let working_dir = tempfile::TempDir::with_prefix("spin-variables-")?;
+    #[clap(flatten)]
+    registry: RegistryFlags,
+}
 
-    let mut client = spin_oci::Client::new(insecure, None).await?;
+/// Options for talking to a registry.
+#[derive(Clone, clap::Args)]
+struct RegistryFlags {
+    /// Ignore server certificate errors from a registry
+    #[clap(short = 'k', long = "insecure", num_args = 0)]
+    insecure: bool,
 
-    let locked_app = spin_oci::OciLoader::new(working_dir.path())
-        .load_app(&mut client, reference)
-        .await?;
+    /// How long each attempt at a registry operation may take, in seconds, before
+    /// it is abandoned. 0 waits indefinitely.
+    #[clap(long = "timeout", value_name = "SECONDS", default_value_t = registry::DEFAULT_TIMEOUT.as_secs())]
+    timeout: u64,
 
-    let variables = locked_app.variables.into_iter().map(|(name, variable)| VariableInfo {
-        name,
-        required: variable.default.is_none(),
-        default_value: variable.default,
-        secret: variable.secret,
-    }).collect();
+    /// How many times to retry a failed registry operation, waiting longer before
+    /// each retry.
+    #[clap(long = "retries", default_value_t = registry::DEFAULT_RETRIES)]
+    retries: u32,
 
-    Ok(variables)
+    /// A PEM file of a certificate authority to trust for registry access, such as
+    /// an internal CA. This is a safer alternative to --insecure. May be repeated.
+    #[clap(long = "ca-cert", value_name = "PEM")]
+    ca_certs: Vec<PathBuf>,
 }
 
-fn format_table(variables: &[VariableInfo]) -> impl std::fmt::Display {
-    let mut table = comfy_table::Table::new();
-    table.set_header(comfy_table::Row::from(vec!["Name", "Required?", "Default value", "Secret?"]));
-    table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
-
-    for variable in variables {
-        let default_value = variable.default_value.as_ref().map(|v| v.as_str()).unwrap_or_default();
+impl RegistryFlags {
+    fn options(&self) -> RegistryOptions {
+        RegistryOptions {
+            insecure: self.insecure,
+            timeout: (self.timeout > 0).then(|| std::time::Duration::from_secs(self.timeout)),
+            retries: self.retries,
+            ca_certs: self.ca_certs.clone(),
+        }
+    }
+}
 
-        let required = if variable.required {
-            "Required"
-        } else {
-            "Optional"
-        };
+impl AppOptions {
+    async fn load_app(&self) -> anyhow::Result<AppInfo> {
+        match app::infer_app_source(&self.from)? {
+            AppSource::File(manifest_file) => app::app_from_toml(&manifest_file).await,
+            AppSource::Registry(reference) => app::app_from_registry(&reference, &self.registry.options()).await,
+            AppSource::OciLayout { path, tag } => oci_layout::app_from_oci_layout(&path, tag.as_deref()),
+            AppSource::OciArchive { path, tag } => oci_layout::app_from_oci_archive(&path, tag.as_deref()),
+        }
+    }
 
-        let secret = if variable.secret {
-            "Secret"
-        } else {
-            ""
-        };
+    /// The manifest file, if the application comes from one.
+    fn manifest_path(&self) -> anyhow::Result<Option<PathBuf>> {
+        match app::infer_app_source(&self.from)? {
+            AppSource::File(manifest_file) => Ok(Some(manifest_file)),
+            AppSource::Registry(_) | AppSource::OciLayout { .. } | AppSource::OciArchive { .. } => Ok(None),
+        }
+    }
 
-        table.add_row(vec![
-            variable.name.as_str(),
-            required,
-            default_value,
-            secret,
-        ]);
+    /// The application's schema, with descriptions from comments in the manifest
+    /// for variables the schema file doesn't describe.
+    fn schema(&self) -> anyhow::Result<Schema> {
+        let mut schema = Schema::load_for_app(&self.app_dir()?, None)?;
+        if let Some(path) = self.manifest_path()? {
+            schema.add_descriptions(ManifestLocator::load(path)?.variable_descriptions());
+        }
+        Ok(schema)
     }
 
-    table
+    /// The directory holding local state for the application, such as profiles.
+    /// For registry applications, this is the current directory.
+    fn app_dir(&self) -> anyhow::Result<PathBuf> {
+        match app::infer_app_source(&self.from)? {
+            AppSource::File(manifest_file) => Ok(app::parent_dir(&manifest_file)),
+            AppSource::Registry(_) | AppSource::OciLayout { .. } | AppSource::OciArchive { .. } => Ok(PathBuf::from(".")),
+        }
+    }
 }
 
-fn format_bash(variables: &[VariableInfo]) -> impl std::fmt::Display {
-    let mut lines = vec![
-        "# You may `source` this or reference it in your runtime-config.toml via the `dotenv_path` field".to_owned(),
-        "".to_owned(),
-    ];
-    lines.extend(variables.iter().map(format_one_bash));
-    lines.join("\n")
+/// Options for choosing several applications to work with at once.
+#[derive(clap::Args)]
+struct MultiAppOptions {
+    /// An application to include. This may be a manifest (spin.toml) file, a directory
+    /// containing a spin.toml file, a remote registry reference, or an OCI layout (oci:DIR
+    /// or oci-archive:FILE). May be repeated.
+    #[clap(short = 'f', long = "from")]
+    from: Vec<String>,
+
+    /// Include every application whose spin.toml is found under this directory.
+    #[clap(long = "workspace", value_name = "DIR")]
+    workspace: Option<PathBuf>,
+
+    #[clap(flatten)]
+    registry: RegistryFlags,
+
+    /// The maximum number of applications to load at once.
+    #[clap(long = "jobs", default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
 }
 
-fn format_one_bash(variable: &VariableInfo) -> String {
-    let env_var_name = format!("SPIN_VARIABLE_{}", variable.name.to_ascii_uppercase());
-    match &variable.default_value {
-        Some(default_value) => format!("# export {env_var_name}=\"{default_value}\"  # optional"),
-        None => format!("export {env_var_name}=TO-DO  # required"),
+impl MultiAppOptions {
+    /// The sources of the selected applications, defaulting to spin.toml in the
+    /// current directory if none are given.
+    fn sources(&self) -> anyhow::Result<Vec<String>> {
+        let mut sources = self.from.clone();
+        if let Some(workspace) = &self.workspace {
+            sources.extend(app::find_manifests(workspace)?.into_iter().map(|p| p.display().to_string()));
+        }
+        if sources.is_empty() && self.workspace.is_none() {
+            sources.push(app::DEFAULT_MANIFEST_FILE.to_owned());
+        }
+        Ok(sources)
+    }
+
+    /// Loads the selected applications, labelled by their sources and in the order
+    /// given. Loading is concurrent, since registry pulls can be slow.
+    async fn load_apps(&self) -> anyhow::Result<Vec<(String, AppInfo)>> {
+        use futures::{StreamExt, TryStreamExt};
+
+        let loads = self.sources()?.into_iter().map(|source| async move {
+            let app = self.load_one(&source).await?;
+            anyhow::Ok((source, app))
+        });
+        futures::stream::iter(loads).buffered(self.jobs.into()).try_collect().await
+    }
+
+    /// Loads the selected applications, yielding each with its source as soon as it
+    /// has loaded (or failed to), rather than in the order given.
+    fn load_apps_as_ready(&self) -> anyhow::Result<impl futures::Stream<Item = (String, anyhow::Result<AppInfo>)> + '_> {
+        use futures::StreamExt;
+
+        let loads = self.sources()?.into_iter().map(|source| async move {
+            let app = self.load_one(&source).await;
+            (source, app)
+        });
+        Ok(futures::stream::iter(loads).buffer_unordered(self.jobs.into()))
+    }
+
+    async fn load_one(&self, source: &str) -> anyhow::Result<AppInfo> {
+        let app_options = AppOptions {
+            from: Some(source.to_owned()),
+            registry: self.registry.clone(),
+        };
+        app_options.load_app().await.with_context(|| format!("Failed to load {source}"))
     }
 }
 
-struct VariableInfo {
-    name: String,
-    default_value: Option<String>,
-    required: bool,
-    secret: bool,
+/// Splits a `[NAMESPACE/]NAME` argument.
+fn namespaced(s: &str) -> (Option<&str>, &str) {
+    match s.split_once('/') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, s),
+    }
 }
 
-enum AppSource {
-    File(PathBuf),
-    Registry(String),
+/// Options for supplying variable values.
+#[derive(clap::Args)]
+struct ValueOptions {
+    /// Set the value of a variable, in the form NAME=VALUE. This takes precedence
+    /// over the manifest default. May be repeated.
+    #[clap(long = "set", value_name = "NAME=VALUE", value_parser = values::parse_name_value)]
+    set: Vec<(String, String)>,
+
+    /// A file of variable values to apply, in YAML, TOML or JSON format (detected
+    /// from the file extension). Values from later files override earlier ones,
+    /// and `--set` overrides all files. May be repeated.
+    #[clap(long = "values", value_name = "FILE")]
+    values: Vec<PathBuf>,
+
+    /// A named profile of values to apply, stored in `.spin/variables/<PROFILE>.toml`.
+    /// Values files and `--set` override the profile.
+    #[clap(long = "profile")]
+    profile: Option<String>,
+
+    /// The prefix of the environment variables to read values from. If omitted,
+    /// this comes from the runtime config file, or is Spin's default of SPIN_VARIABLE.
+    #[clap(long = "env-prefix")]
+    env_prefix: Option<String>,
+
+    /// A dotenv file to read values from. If omitted, this comes from the runtime
+    /// config file, or is a `.env` file in the application directory.
+    #[clap(long = "dotenv-path")]
+    dotenv_path: Option<PathBuf>,
+
+    /// A Spin runtime config file. Its `env` variables providers determine the
+    /// environment variable prefix and dotenv file used to look up values, and
+    /// values are also looked up in the Vault and Azure Key Vault providers it lists.
+    #[clap(long = "runtime-config-file")]
+    runtime_config_file: Option<PathBuf>,
+
+    /// An age identity file for decrypting encrypted values in values files and
+    /// profiles.
+    #[clap(long = "age-identity", value_name = "FILE")]
+    age_identity: Option<PathBuf>,
+
+    /// Also look up values stored in the OS keychain with `set --keychain`. The
+    /// environment and dotenv files take precedence over the keychain.
+    #[clap(long = "keychain", num_args = 0)]
+    keychain: bool,
+
+    /// Also look up values kept in the application's local store with `store set`.
+    /// The environment, dotenv files and keychain take precedence over the store.
+    #[clap(long = "store", num_args = 0)]
+    store: bool,
+
+    /// Also look up values in a Secret in the current kube context, given as
+    /// [NAMESPACE/]NAME, whose keys are variable names (as from `-o kube`). May be repeated.
+    #[clap(long = "kube-secret", value_name = "[NAMESPACE/]NAME")]
+    kube_secrets: Vec<String>,
+
+    /// Also look up values in a ConfigMap in the current kube context, in the same
+    /// form as --kube-secret. May be repeated.
+    #[clap(long = "kube-configmap", value_name = "[NAMESPACE/]NAME")]
+    kube_configmaps: Vec<String>,
+
+    /// Also look up the values a SpinApp in the current kube context gives its variables,
+    /// including from the Secrets and ConfigMaps it refers to, to see what a deployed
+    /// application is running with.
+    #[clap(long = "kube-spinapp", value_name = "[NAMESPACE/]NAME")]
+    kube_spinapp: Option<String>,
+
+    /// Also look up values in Consul's key/value store, at this agent address. If the
+    /// address is omitted, this is the local agent.
+    #[clap(long = "consul-address", value_name = "URL", num_args = 0..=1, require_equals = true, default_missing_value = spin_variables::consul::DEFAULT_ADDRESS)]
+    consul_address: Option<String>,
+
+    /// The ACL token for Consul. If omitted, this comes from CONSUL_HTTP_TOKEN, if set.
+    #[clap(long = "consul-token", value_name = "TOKEN", requires = "consul_address")]
+    consul_token: Option<String>,
+
+    /// The path prefix of the keys in Consul, such as `apps/my-app/`. Each variable is
+    /// read from the prefix followed by its name.
+    #[clap(long = "consul-prefix", value_name = "PATH", default_value = "", requires = "consul_address")]
+    consul_prefix: String,
+
+    /// Also look up values in AWS Secrets Manager, reading each variable from the secret
+    /// named by this prefix followed by the variable name. Uses the `aws` CLI.
+    #[clap(long = "aws-secrets-manager", value_name = "PREFIX", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    aws_secrets_manager: Option<String>,
+
+    /// Also look up values in AWS SSM Parameter Store, reading each variable from the
+    /// parameter named by this path prefix followed by the variable name, such as
+    /// `/my-app/`. Uses the `aws` CLI.
+    #[clap(long = "aws-parameter-store", value_name = "PATH", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    aws_parameter_store: Option<String>,
+
+    /// The AWS CLI profile to read from Secrets Manager and Parameter Store with. If
+    /// omitted, this comes from AWS_PROFILE or the default profile.
+    #[clap(long = "aws-profile", value_name = "PROFILE")]
+    aws_profile: Option<String>,
+
+    /// The AWS region to read from Secrets Manager and Parameter Store in. If omitted,
+    /// this comes from AWS_REGION or the profile.
+    #[clap(long = "aws-region", value_name = "REGION")]
+    aws_region: Option<String>,
+
+    /// A role to assume, using the --aws-profile credentials, before reading from
+    /// Secrets Manager and Parameter Store, such as the role the deployment runs as.
+    #[clap(long = "aws-role-arn", value_name = "ARN")]
+    aws_role_arn: Option<String>,
+
+    /// The Vault namespace for the runtime config's Vault providers. If omitted,
+    /// this comes from VAULT_NAMESPACE, if set.
+    #[clap(long = "vault-namespace", value_name = "NAMESPACE")]
+    vault_namespace: Option<String>,
+
+    /// The KV secrets engine mount for the runtime config's Vault providers to read
+    /// from, instead of their `mount` setting.
+    #[clap(long = "vault-mount", value_name = "PATH")]
+    vault_mount: Option<String>,
+
+    /// Log in to the runtime config's Vault providers with AppRole, with this role ID,
+    /// instead of using a token.
+    #[clap(long = "vault-role-id", value_name = "ROLE_ID")]
+    vault_role_id: Option<String>,
+
+    /// The AppRole secret ID. If omitted, this comes from VAULT_SECRET_ID.
+    #[clap(long = "vault-secret-id", value_name = "SECRET_ID", requires = "vault_role_id")]
+    vault_secret_id: Option<String>,
+
+    /// Where the AppRole auth method is mounted.
+    #[clap(long = "vault-approle-mount", value_name = "PATH", default_value = "approle", requires = "vault_role_id")]
+    vault_approle_mount: String,
 }
 
-fn infer_app_source(provided: &Option<String>) -> anyhow::Result<AppSource> {
-    match provided {
-        None => Ok(AppSource::File(spin_common::paths::DEFAULT_MANIFEST_FILE.into())),
-        Some(provided) if spin_oci::is_probably_oci_reference(provided) => Ok(AppSource::Registry(provided.clone())),
-        Some(provided) => Ok(AppSource::File(spin_common::paths::resolve_manifest_file_path(provided)?)),
+impl ValueOptions {
+    fn env_providers(&self, app_dir: &Path) -> anyhow::Result<Vec<env::EnvProvider>> {
+        env::env_providers(
+            app_dir,
+            self.runtime_config_file.as_deref(),
+            self.env_prefix.as_deref(),
+            self.dotenv_path.as_deref(),
+        )
+    }
+
+    /// The files that values are read from: values files, the profile, the runtime
+    /// config and the dotenv files of the environment providers.
+    fn value_files(&self, app_dir: &Path, env_providers: &[env::EnvProvider]) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = self.values.clone();
+        if let Some(profile) = &self.profile {
+            files.push(spin_variables::profiles::profile_path(app_dir, profile)?);
+        }
+        files.extend(self.runtime_config_file.clone());
+        files.extend(env_providers.iter().filter_map(|p| p.dotenv_path()).map(|p| p.to_owned()));
+        Ok(files)
+    }
+
+    /// Adds providers for the cluster resources given on the command line, in the order
+    /// SpinApp, Secrets, ConfigMaps. Their values are fetched up front with kubectl.
+    fn add_kube_providers(&self, providers: &mut ProviderChain) -> anyhow::Result<()> {
+        if let Some(spinapp) = &self.kube_spinapp {
+            let (namespace, name) = namespaced(spinapp);
+            let Some(resource) = kubectl::get("spinapp", name, namespace)? else {
+                anyhow::bail!("SpinApp '{spinapp}' not found");
+            };
+            let values = cluster::spinapp_values(&resource, |kind, name| kubectl::get(kind, name, namespace))?;
+            providers.push(ProviderLabel::Named(format!("SpinApp {spinapp}")), cluster::KubeProvider::new(values));
+        }
+        let resources = self.kube_secrets.iter().map(|s| ("secret", "Secret", s)).chain(self.kube_configmaps.iter().map(|c| ("configmap", "ConfigMap", c)));
+        for (kind, kind_name, resource) in resources {
+            let (namespace, name) = namespaced(resource);
+            let Some(resource_json) = kubectl::get(kind, name, namespace)? else {
+                anyhow::bail!("{kind_name} '{resource}' not found");
+            };
+            providers.push(ProviderLabel::Named(format!("{kind_name} {resource}")), cluster::KubeProvider::new(cluster::resource_values(&resource_json)));
+        }
+        Ok(())
+    }
+
+    fn vault_options(&self) -> anyhow::Result<spin_variables::vault::VaultOptions> {
+        let approle = match &self.vault_role_id {
+            Some(role_id) => {
+                let Some(secret_id) = self.vault_secret_id.clone().or_else(|| std::env::var("VAULT_SECRET_ID").ok()) else {
+                    anyhow::bail!("--vault-role-id needs a secret ID, from --vault-secret-id or VAULT_SECRET_ID");
+                };
+                Some(spin_variables::vault::AppRole {
+                    role_id: role_id.clone(),
+                    secret_id,
+                    mount: self.vault_approle_mount.clone(),
+                })
+            }
+            None => None,
+        };
+        Ok(spin_variables::vault::VaultOptions {
+            namespace: self.vault_namespace.clone().or_else(|| std::env::var("VAULT_NAMESPACE").ok()),
+            mount: self.vault_mount.clone(),
+            approle,
+        })
+    }
+
+    /// Adds providers for the AWS services given on the command line, Secrets Manager first.
+    fn add_aws_providers(&self, providers: &mut ProviderChain) {
+        use spin_variables::aws::{AwsOptions, AwsProvider, AwsService};
+
+        let options = AwsOptions {
+            profile: self.aws_profile.clone(),
+            region: self.aws_region.clone(),
+            role_arn: self.aws_role_arn.clone(),
+        };
+        let services = [(AwsService::SecretsManager, &self.aws_secrets_manager), (AwsService::ParameterStore, &self.aws_parameter_store)];
+        for (service, prefix) in services {
+            if let Some(prefix) = prefix {
+                AwsProvider::new(service, prefix, options.clone()).add_to(providers);
+            }
+        }
     }
-}
 
-#[derive(Clone, Debug, clap::ValueEnum)]
-enum OutputFormat {
-    Table,
-    Bash,
+    /// Resolves variable values, returning the environment providers that were consulted.
+    async fn resolve(&self, variables: &mut [VariableInfo], app_dir: &Path) -> anyhow::Result<Vec<env::EnvProvider>> {
+        let env_providers = self.env_providers(app_dir)?;
+        let mut providers = ProviderChain::new();
+        for env_provider in &env_providers {
+            env_provider.add_to(&mut providers);
+        }
+        if let Some(runtime_config_file) = &self.runtime_config_file {
+            spin_variables::runtime_config::add_secret_stores(runtime_config_file, &self.vault_options()?, &mut providers)?;
+        }
+        if self.keychain {
+            providers.push(ProviderLabel::Named("OS keychain".to_owned()), keychain::KeychainProvider::new(app_dir));
+        }
+        if self.store {
+            providers.push(ProviderLabel::Named("local store".to_owned()), spin_variables::store::StoreProvider::new(app_dir));
+        }
+        self.add_kube_providers(&mut providers)?;
+        if let Some(address) = &self.consul_address {
+            let token = self.consul_token.clone().or_else(|| std::env::var("CONSUL_HTTP_TOKEN").ok());
+            providers.push(ProviderLabel::Named(format!("Consul {address}")), spin_variables::consul::ConsulProvider::new(address, token, &self.consul_prefix));
+        }
+        self.add_aws_providers(&mut providers);
+        let mut profile = self.profile.as_ref().map(|name| spin_variables::profiles::Profile::load(app_dir, name)).transpose()?;
+        let mut values_files = self.values.iter().map(values::ValuesFile::load).collect::<anyhow::Result<Vec<_>>>()?;
+        for values_file in values_files.iter_mut().chain(profile.as_mut().map(|p| &mut p.values)) {
+            values_file.decrypt(self.age_identity.as_deref())?;
+        }
+        let sources = values::Sources {
+            set: &self.set,
+            values_files: &values_files,
+            profile: profile.as_ref(),
+            providers: &providers,
+        };
+        values::resolve_values(variables, &sources).await?;
+        Ok(env_providers)
+    }
 }
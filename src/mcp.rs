@@ -0,0 +1,122 @@
+use std::io::BufRead;
+
+use serde_json::{json, Value};
+use spin_variables::format::{self, Versioned};
+use spin_variables::{templates, usage};
+
+use crate::serve::{check_document, load_resolved, variables_document};
+use crate::{AppOptions, ValueOptions};
+
+/// The protocol version implemented.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+#[derive(clap::Args)]
+pub struct McpCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+}
+
+impl McpCommand {
+    /// Serves JSON-RPC messages, one per line, on standard input and output. Diagnostics
+    /// go to standard error so they don't corrupt the protocol stream.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        for line in std::io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message).await,
+                Err(e) => Some(error_response(Value::Null, -32700, &format!("Invalid JSON: {e}"))),
+            };
+            if let Some(response) = response {
+                println!("{response}");
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to a message, or `None` for notifications, which get no response.
+    async fn handle(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let method = message.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "spin-variables", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "ping" => json!({}),
+            "tools/list" => json!({ "tools": tools() }),
+            "tools/call" => {
+                let params = message.get("params").cloned().unwrap_or_default();
+                let name = params.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                let arguments = params.get("arguments").cloned().unwrap_or_default();
+                match self.call_tool(name, &arguments).await {
+                    Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                    Err(e) => json!({ "content": [{ "type": "text", "text": format!("{e:#}") }], "isError": true }),
+                }
+            }
+            _ => return Some(error_response(id, -32601, &format!("Unknown method '{method}'"))),
+        };
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    /// Secret values are always redacted, so they never reach the assistant.
+    async fn call_tool(&self, name: &str, arguments: &Value) -> anyhow::Result<String> {
+        match name {
+            "list_variables" => variables_document(&self.app, &self.values).await,
+            "check_variables" => check_document(&self.app, &self.values).await,
+            "expand_templates" => {
+                let (mut app, _) = load_resolved(&self.app, &self.values).await?;
+                format::redact_secrets(&mut app.variables);
+                let expansions: Vec<_> = match arguments.get("template").and_then(|t| t.as_str()) {
+                    Some(template) => vec![expansion(None, template, &app.variables)],
+                    None => usage::template_sites(&app).iter().map(|site| expansion(Some(site.to_string()), site.template, &app.variables)).collect(),
+                };
+                Ok(serde_json::to_string_pretty(&Versioned::new(json!({ "expansions": expansions })))?)
+            }
+            _ => anyhow::bail!("Unknown tool '{name}'"),
+        }
+    }
+}
+
+fn tools() -> Value {
+    let no_arguments = json!({ "type": "object", "properties": {} });
+    json!([
+        {
+            "name": "list_variables",
+            "description": "List the Spin application's variables, with whether they are required or secret, their defaults, and their current values. Secret values are redacted.",
+            "inputSchema": no_arguments.clone(),
+        },
+        {
+            "name": "check_variables",
+            "description": "Check that every required variable has a value and that values meet the schema, as `spin variables check` does.",
+            "inputSchema": no_arguments,
+        },
+        {
+            "name": "expand_templates",
+            "description": "Expand the application's templates (component variables, allowed outbound hosts and trigger settings) with the current values, or a given template. Secret values appear as REDACTED.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "template": { "type": "string", "description": "A template to expand, such as \"https://{{ host }}\". If omitted, every template in the application is expanded." },
+                },
+            },
+        },
+    ])
+}
+
+fn expansion(site: Option<String>, template: &str, variables: &[spin_variables::VariableInfo]) -> Value {
+    match templates::expand(template, variables) {
+        Ok(expanded) => json!({ "site": site, "template": template, "expanded": expanded }),
+        Err(e) => json!({ "site": site, "template": template, "error": e.to_string() }),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -0,0 +1,39 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum PagerMode {
+    /// Use a pager when writing to a terminal.
+    Auto,
+    Never,
+}
+
+/// Prints the output, through the user's pager if appropriate. As with git, the
+/// pager is `$PAGER` or else `less`, with `LESS=FRX` so that output which fits on
+/// one screen is printed and left there as usual.
+pub fn print(output: &str, mode: PagerMode) {
+    if matches!(mode, PagerMode::Auto) && std::io::stdout().is_terminal() && page(output).is_ok() {
+        return;
+    }
+    println!("{output}");
+}
+
+fn page(output: &str) -> std::io::Result<()> {
+    let pager = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()).unwrap_or_else(|| "less".to_owned());
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    let mut command = Command::new(program);
+    command.args(words).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything
+        let _ = writeln!(stdin, "{output}");
+    }
+    child.wait()?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use spin_variables::providers::ProviderChain;
+use spin_variables::triggers::TriggerRegistry;
+use spin_variables::{env, preflight, values};
+
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct PreflightCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// The runtime config file that will be passed to `spin up`. Its variables
+    /// providers are used to resolve values, as Spin would.
+    #[clap(long = "runtime-config-file")]
+    runtime_config_file: Option<PathBuf>,
+}
+
+impl PreflightCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        let app_dir = self.app.app_dir()?;
+
+        // Only what `spin up` would consult: no --set, values files or profiles
+        let env_providers = env::env_providers(&app_dir, self.runtime_config_file.as_deref(), None, None)?;
+        let mut providers = ProviderChain::new();
+        for env_provider in &env_providers {
+            env_provider.add_to(&mut providers);
+        }
+        if let Some(runtime_config_file) = &self.runtime_config_file {
+            spin_variables::runtime_config::add_secret_stores(runtime_config_file, &Default::default(), &mut providers)?;
+        }
+        let sources = values::Sources {
+            set: &[],
+            values_files: &[],
+            profile: None,
+            providers: &providers,
+        };
+        values::resolve_values(&mut app.variables, &sources).await?;
+
+        let labels: Vec<_> = providers.labels().map(|l| l.to_string()).collect();
+        println!("Resolving from: {}", labels.join(", "));
+
+        let failures = preflight::preflight(&app, &TriggerRegistry::with_builtins());
+        if failures.is_empty() {
+            println!("spin up should start: all {} variable(s) and every template resolve", app.variables.len());
+            return Ok(());
+        }
+
+        for failure in &failures {
+            println!("error: {failure}");
+        }
+        anyhow::bail!("spin up would fail to start, with {} problem(s)", failures.len());
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+
+use anyhow::Context;
+use spin_variables::profiles::{profile_names, profile_path, profiles_dir, skeleton, Profile};
+
+use crate::AppOptions;
+
+#[derive(clap::Subcommand)]
+pub enum ProfileCommand {
+    /// List the profiles defined for the application.
+    List(ListProfiles),
+    /// Create a new profile, either as a commented skeleton of the application's
+    /// variables or as a copy of an existing profile.
+    Create(CreateProfile),
+    /// Show the differences in values between two profiles.
+    Diff(DiffProfiles),
+}
+
+impl ProfileCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        match self {
+            Self::List(cmd) => cmd.run(),
+            Self::Create(cmd) => cmd.run().await,
+            Self::Diff(cmd) => cmd.run(),
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct ListProfiles {
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl ListProfiles {
+    fn run(&self) -> anyhow::Result<()> {
+        let app_dir = self.app.app_dir()?;
+        let names = profile_names(&app_dir)?;
+        if names.is_empty() {
+            println!("No profiles found in {}", profiles_dir(&app_dir).display());
+        }
+        for name in names {
+            println!("{name}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct CreateProfile {
+    /// The name of the profile to create.
+    name: String,
+
+    /// Copy the values of an existing profile instead of creating a skeleton.
+    #[clap(long = "copy-from")]
+    copy_from: Option<String>,
+
+    /// Overwrite the profile if it already exists.
+    #[clap(long = "force", num_args = 0)]
+    force: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl CreateProfile {
+    async fn run(&self) -> anyhow::Result<()> {
+        let app_dir = self.app.app_dir()?;
+        let path = profile_path(&app_dir, &self.name)?;
+        if path.exists() && !self.force {
+            anyhow::bail!("Profile '{}' already exists. Use --force to overwrite it.", self.name);
+        }
+
+        let content = match &self.copy_from {
+            Some(source) => {
+                let source_path = profile_path(&app_dir, source)?;
+                std::fs::read_to_string(&source_path)
+                    .with_context(|| format!("Failed to read profile '{source}' from {}", source_path.display()))?
+            }
+            None => skeleton(&self.name, &self.app.load_app().await?.variables),
+        };
+
+        std::fs::create_dir_all(profiles_dir(&app_dir))?;
+        std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Created profile '{}' at {}", self.name, path.display());
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct DiffProfiles {
+    /// The first profile to compare.
+    first: String,
+
+    /// The second profile to compare.
+    second: String,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl DiffProfiles {
+    fn run(&self) -> anyhow::Result<()> {
+        let app_dir = self.app.app_dir()?;
+        let first = Profile::load(&app_dir, &self.first)?;
+        let second = Profile::load(&app_dir, &self.second)?;
+
+        let names: BTreeSet<_> = first.values.values().keys().chain(second.values.values().keys()).collect();
+
+        let mut table = comfy_table::Table::new();
+        table.set_header(comfy_table::Row::from(vec!["Name", first.name.as_str(), second.name.as_str()]));
+        table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+
+        let mut differences = 0;
+        for name in names {
+            let first_value = first.values.get(name);
+            let second_value = second.values.get(name);
+            if first_value != second_value {
+                differences += 1;
+                table.add_row(vec![
+                    name.as_str(),
+                    first_value.map(|v| v.as_str()).unwrap_or("(not set)"),
+                    second_value.map(|v| v.as_str()).unwrap_or("(not set)"),
+                ]);
+            }
+        }
+
+        if differences == 0 {
+            println!("Profiles '{}' and '{}' have the same values", first.name, second.name);
+        } else {
+            println!("{table}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use spin_variables::app::VariableInfo;
+use spin_variables::encryption::Encryption;
+use spin_variables::generate::{self, SecretSpec};
+use spin_variables::schema::{Schema, VariableSchema};
+use spin_variables::values::{self, ValuesFile};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct PromptCommand {
+    /// The values file to write, in YAML, TOML or JSON format (detected from the
+    /// file extension). If omitted, the values are printed as TOML.
+    #[clap(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Prompt for every variable, not just those without values.
+    #[clap(long = "all", num_args = 0)]
+    all: bool,
+
+    /// A values file of answers to use instead of prompting. Only variables it
+    /// doesn't answer are prompted for.
+    #[clap(long = "answers", value_name = "FILE")]
+    answers: Option<PathBuf>,
+
+    /// Fail instead of prompting if there are variables without values or answers.
+    #[clap(long = "no-input", num_args = 0)]
+    no_input: bool,
+
+    /// Generate random values for secret variables instead of asking for them, in
+    /// the form LENGTH[/CHARSET] as for listing with --generate-secrets.
+    #[clap(long = "generate-secrets", value_name = "SPEC", num_args = 0..=1, require_equals = true, default_missing_value = "32")]
+    generate_secrets: Option<SecretSpec>,
+
+    /// Encrypt secret values in the output, in the form age:<RECIPIENT>. Pass
+    /// --age-identity when using the file to decrypt them.
+    #[clap(long = "encrypt", value_name = "METHOD")]
+    encrypt: Option<Encryption>,
+
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+}
+
+impl PromptCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut app = self.app.load_app().await?;
+        let app_dir = self.app.app_dir()?;
+        self.values.resolve(&mut app.variables, &app_dir).await?;
+        let schema = Schema::load_for_app(&app_dir, None)?;
+
+        let answers_file = self.answers.as_ref().map(ValuesFile::load).transpose()?;
+
+        let mut answers = BTreeMap::new();
+        let mut unanswered = vec![];
+        for variable in app.variables.iter().filter(|v| self.all || v.value.is_none()) {
+            let constraints = schema.get(&variable.name).cloned().unwrap_or_default();
+            if let Some(answer) = answers_file.as_ref().and_then(|f| f.get(&variable.name)) {
+                constraints.validate(answer).map_err(|e| anyhow::anyhow!("The answer for '{}' is invalid: {e}", variable.name))?;
+                answers.insert(variable.name.clone(), answer.clone());
+            } else if let Some(spec) = self.generate_secrets.as_ref().filter(|_| variable.secret) {
+                let value = generate::generate(spec);
+                constraints.validate(&value).map_err(|e| anyhow::anyhow!("The generated value for '{}' doesn't satisfy the schema ({e}). Choose a different --generate-secrets", variable.name))?;
+                answers.insert(variable.name.clone(), value);
+            } else if self.no_input {
+                if variable.value.is_none() {
+                    unanswered.push(variable.name.as_str());
+                }
+            } else if let Some(value) = ask(variable, &constraints)? {
+                answers.insert(variable.name.clone(), value);
+            }
+        }
+        if !unanswered.is_empty() {
+            anyhow::bail!("No value or answer for {}, and --no-input prevents prompting", unanswered.join(", "));
+        }
+
+        if let Some(encryption) = &self.encrypt {
+            for variable in app.variables.iter().filter(|v| v.secret) {
+                if let Some(value) = answers.get_mut(&variable.name) {
+                    *value = encryption.encrypt(value)?;
+                }
+            }
+        }
+
+        match &self.output {
+            Some(path) => {
+                values::write_values(path, &answers)?;
+                eprintln!("Wrote {} value(s) to {}", answers.len(), path.display());
+            }
+            None => print!("{}", toml::to_string(&answers)?),
+        }
+        Ok(())
+    }
+}
+
+/// Asks for a variable's value, re-prompting until it satisfies the schema. Secret
+/// values are hidden and must be typed twice. Returns `None` if the user leaves an
+/// optional value empty.
+fn ask(variable: &VariableInfo, constraints: &VariableSchema) -> anyhow::Result<Option<String>> {
+    let prompt = match &variable.value {
+        Some(resolved) if !variable.secret => format!("{} (currently '{}' from {})", variable.name, resolved.value, resolved.source),
+        _ => variable.name.clone(),
+    };
+    let validate = |input: &String| -> Result<(), String> {
+        if input.is_empty() {
+            return if variable.required && variable.value.is_none() { Err("A value is required".to_owned()) } else { Ok(()) };
+        }
+        constraints.validate(input)
+    };
+
+    let value = if let Some(allowed_values) = &constraints.allowed_values {
+        let current = variable.value.as_ref().and_then(|v| allowed_values.iter().position(|a| a == &v.value));
+        let index = dialoguer::Select::new()
+            .with_prompt(&prompt)
+            .items(allowed_values)
+            .default(current.unwrap_or_default())
+            .interact()?;
+        allowed_values[index].clone()
+    } else if variable.secret {
+        dialoguer::Password::new()
+            .with_prompt(&prompt)
+            .with_confirmation("Retype to confirm", "The values don't match")
+            .allow_empty_password(true)
+            .validate_with(validate)
+            .interact()?
+    } else {
+        dialoguer::Input::<String>::new()
+            .with_prompt(&prompt)
+            .allow_empty(true)
+            .validate_with(validate)
+            .interact_text()?
+    };
+
+    Ok(Some(value).filter(|v| !v.is_empty()))
+}
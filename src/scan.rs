@@ -0,0 +1,64 @@
+use futures::StreamExt;
+
+use crate::MultiAppOptions;
+
+#[derive(clap::Args)]
+pub struct ScanCommand {
+    #[clap(flatten)]
+    apps: MultiAppOptions,
+
+    /// How to output the variables. `jsonl` prints one JSON object per variable (or per
+    /// application that fails to load), as each application is loaded, for piping into
+    /// tools such as jq. `table` prints a table per application.
+    #[clap(short = 'o', long = "output", default_value = "jsonl", value_parser = ["jsonl", "table"])]
+    output: String,
+}
+
+impl ScanCommand {
+    /// Applications are output as they finish loading, so the order may differ from
+    /// run to run. A failure to load one application doesn't stop the others.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let mut apps = std::pin::pin!(self.apps.load_apps_as_ready()?);
+        let mut failures = 0;
+
+        while let Some((source, result)) = apps.next().await {
+            let app = match result {
+                Ok(app) => app,
+                Err(e) => {
+                    failures += 1;
+                    match self.output.as_str() {
+                        "jsonl" => println!("{}", serde_json::json!({ "app": source, "error": format!("{e:#}") })),
+                        _ => eprintln!("Error: {e:#}"),
+                    }
+                    continue;
+                }
+            };
+
+            match self.output.as_str() {
+                "jsonl" => {
+                    for variable in &app.variables {
+                        let line = serde_json::json!({
+                            "app": source,
+                            "app_name": app.name,
+                            "name": variable.name,
+                            "required": variable.required,
+                            "secret": variable.secret,
+                            "default_value": variable.default_value,
+                        });
+                        println!("{line}");
+                    }
+                }
+                _ => {
+                    println!("{source}:");
+                    println!("{}", spin_variables::format::table(&app.variables, &Default::default()));
+                    println!();
+                }
+            }
+        }
+
+        if failures > 0 {
+            anyhow::bail!("{failures} application(s) could not be loaded");
+        }
+        Ok(())
+    }
+}
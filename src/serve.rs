@@ -0,0 +1,120 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Method, Request, Response, StatusCode};
+use spin_variables::format::{self, OutputFormatter, Versioned};
+use spin_variables::schema::Schema;
+use spin_variables::{check, env, usage, AppInfo};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct ServeCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// The address to listen on. Anyone who can reach it can see the application's
+    /// configuration, so keep it to the local machine unless that's intended.
+    #[clap(long = "listen", value_name = "ADDRESS", default_value = "127.0.0.1:3000")]
+    listen: SocketAddr,
+}
+
+impl ServeCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let listener = tokio::net::TcpListener::bind(self.listen).await.with_context(|| format!("Failed to listen on {}", self.listen))?;
+        println!("Serving /variables, /usage and /check on http://{}", self.listen);
+
+        // Requests are handled one at a time, reloading the application for each, so
+        // responses always reflect the files as they are now.
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let service = hyper::service::service_fn(|request| self.handle(request));
+            let connection = hyper::server::conn::http1::Builder::new()
+                .keep_alive(false)
+                .serve_connection(hyper_util::rt::TokioIo::new(stream), service);
+            if let Err(e) = connection.await {
+                eprintln!("Warning: error serving a request: {e}");
+            }
+        }
+    }
+
+    async fn handle(&self, request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+        if request.method() != Method::GET {
+            return Ok(error_response(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported"));
+        }
+        let result = match request.uri().path() {
+            "/variables" => variables_document(&self.app, &self.values).await,
+            "/usage" => usage_document(&self.app).await,
+            "/check" => check_document(&self.app, &self.values).await,
+            path => return Ok(error_response(StatusCode::NOT_FOUND, &format!("No endpoint {path}. The endpoints are /variables, /usage and /check"))),
+        };
+        Ok(match result {
+            Ok(body) => json_response(StatusCode::OK, body),
+            Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("{e:#}")),
+        })
+    }
+}
+
+/// Loads the application and resolves its values, returning it with the prefix of
+/// the environment variables consulted.
+pub async fn load_resolved(app_options: &AppOptions, values: &ValueOptions) -> anyhow::Result<(AppInfo, String)> {
+    let mut app = app_options.load_app().await?;
+    let env_providers = values.resolve(&mut app.variables, &app_options.app_dir()?).await?;
+    let env_prefix = env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned();
+    Ok((app, env_prefix))
+}
+
+/// The same document as `-o json`, with secret values redacted.
+pub async fn variables_document(app_options: &AppOptions, values: &ValueOptions) -> anyhow::Result<String> {
+    let (mut app, env_prefix) = load_resolved(app_options, values).await?;
+    format::redact_secrets(&mut app.variables);
+    let context = format::FormatContext {
+        env_prefix,
+        app_name: app.name.clone(),
+        schema: app_options.schema()?,
+        max_value_width: None,
+        value_lengths: Default::default(),
+    };
+    format::JsonFormatter.format(&app.variables, &context)
+}
+
+/// Where each variable is used, and templates that refer to undeclared variables.
+pub async fn usage_document(app_options: &AppOptions) -> anyhow::Result<String> {
+    let app = app_options.load_app().await?;
+    let variables: Vec<_> = app.variables.iter()
+        .map(|v| serde_json::json!({ "name": v.name, "used_by": usage::sites_using(&app, &v.name) }))
+        .collect();
+    let undeclared: Vec<_> = usage::undeclared_references(&app).into_iter()
+        .map(|(name, site)| serde_json::json!({ "name": name, "site": site }))
+        .collect();
+    let document = Versioned::new(serde_json::json!({ "variables": variables, "undeclared": undeclared }));
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// The results of `check`, one per variable.
+pub async fn check_document(app_options: &AppOptions, values: &ValueOptions) -> anyhow::Result<String> {
+    let (app, env_prefix) = load_resolved(app_options, values).await?;
+    let schema = Schema::load_for_app(&app_options.app_dir()?, None)?;
+    let results: Vec<_> = check::check(&app.variables, &env_prefix, &schema).iter()
+        .map(|r| serde_json::json!({ "name": r.variable.name, "passed": r.passed(), "problem": r.problem, "warning": r.warning }))
+        .collect();
+    let document = Versioned::new(serde_json::json!({ "results": results }));
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json_response(status, serde_json::json!({ "error": message }).to_string())
+}
@@ -0,0 +1,60 @@
+use spin_variables::keychain;
+use spin_variables::schema::Schema;
+
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct SetCommand {
+    /// The variable to store a value for. It must be declared by the application.
+    name: String,
+
+    /// The value to store. If omitted, you are asked for it, without it being shown.
+    value: Option<String>,
+
+    /// Store the value in the OS keychain. Values stored this way are used when
+    /// resolving with --keychain. For values that needn't be kept secret, `store set`
+    /// keeps them in a local database instead.
+    #[clap(long = "keychain", num_args = 0, required = true)]
+    keychain: bool,
+
+    /// Remove the stored value instead of setting one.
+    #[clap(long = "remove", num_args = 0, conflicts_with = "value")]
+    remove: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl SetCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let app = self.app.load_app().await?;
+        let app_dir = self.app.app_dir()?;
+        if !app.variables.iter().any(|v| v.name == self.name) {
+            anyhow::bail!("Variable '{}' is not declared by the application", self.name);
+        }
+
+        if self.remove {
+            if keychain::remove(&app_dir, &self.name)? {
+                println!("Removed the stored value of '{}'", self.name);
+            } else {
+                println!("'{}' has no stored value", self.name);
+            }
+            return Ok(());
+        }
+
+        let value = match &self.value {
+            Some(value) => value.clone(),
+            None => dialoguer::Password::new()
+                .with_prompt(&self.name)
+                .with_confirmation("Retype to confirm", "The values don't match")
+                .interact()?,
+        };
+        if let Some(constraints) = Schema::load_for_app(&app_dir, None)?.get(&self.name) {
+            constraints.validate(&value).map_err(|e| anyhow::anyhow!("The value for '{}' is invalid: {e}", self.name))?;
+        }
+
+        keychain::store(&app_dir, &self.name, &value)?;
+        println!("Stored the value of '{}' in the OS keychain", self.name);
+        Ok(())
+    }
+}
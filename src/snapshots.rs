@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use spin_variables::env;
+use spin_variables::snapshots::{snapshot_names, snapshot_path, snapshots_dir, Snapshot};
+
+use crate::AppOptions;
+
+#[derive(clap::Args)]
+pub struct SnapshotCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// The prefix of the environment variables to capture.
+    #[clap(long = "env-prefix", default_value = env::DEFAULT_PREFIX)]
+    env_prefix: String,
+
+    /// Capture only the environment variables for variables the application declares.
+    #[clap(long = "declared-only", num_args = 0)]
+    declared_only: bool,
+
+    /// List the application's snapshots instead of taking one.
+    #[clap(long = "list", num_args = 0, conflicts_with = "declared_only")]
+    list: bool,
+}
+
+impl SnapshotCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let app_dir = self.app.app_dir()?;
+
+        if self.list {
+            let names = snapshot_names(&app_dir)?;
+            if names.is_empty() {
+                println!("No snapshots found in {}", snapshots_dir(&app_dir).display());
+            }
+            for name in names {
+                println!("{name}");
+            }
+            return Ok(());
+        }
+
+        let declared = match self.declared_only {
+            true => Some(self.app.load_app().await?.variables),
+            false => None,
+        };
+        let snapshot = Snapshot::capture(&self.env_prefix, declared.as_deref());
+        let path = snapshot.save(&app_dir)?;
+        println!("Saved {} environment variable(s) to {}", snapshot.values.len(), path.display());
+        if !snapshot.values.is_empty() {
+            eprintln!("Warning: values are saved as they are, including any secrets. Keep snapshots out of source control");
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct RestoreCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    /// The snapshot to restore, by name (as listed by `snapshot --list`) or path. If
+    /// omitted, this is the latest snapshot.
+    snapshot: Option<String>,
+}
+
+impl RestoreCommand {
+    /// Prints a script rather than changing the environment, since a child process
+    /// can't change its parent shell's environment.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let app_dir = self.app.app_dir()?;
+        let path = match &self.snapshot {
+            Some(snapshot) if snapshot.ends_with(".toml") => PathBuf::from(snapshot),
+            Some(name) => snapshot_path(&app_dir, name),
+            None => match snapshot_names(&app_dir)?.last() {
+                Some(name) => snapshot_path(&app_dir, name),
+                None => anyhow::bail!("No snapshots found in {}. Take one with `spin variables snapshot`", snapshots_dir(&app_dir).display()),
+            },
+        };
+        println!("{}", Snapshot::load(&path)?.script());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use spin_variables::format::REDACTED;
+use spin_variables::schema::Schema;
+use spin_variables::store::{store_path, ValueStore};
+
+use crate::AppOptions;
+
+#[derive(clap::Subcommand)]
+pub enum StoreCommand {
+    /// Store a value for a variable, replacing any stored value.
+    Set(StoreSet),
+    /// Print a variable's stored value.
+    Get(StoreGet),
+    /// List the stored values.
+    List(StoreList),
+    /// Remove a variable's stored value.
+    Remove(StoreGet),
+}
+
+impl StoreCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Set(cmd) => cmd.run().await,
+            Self::Get(cmd) => cmd.get(),
+            Self::List(cmd) => cmd.run().await,
+            Self::Remove(cmd) => cmd.remove(),
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct StoreSet {
+    /// The variable to store a value for. It must be declared by the application.
+    name: String,
+
+    /// The value to store.
+    value: String,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl StoreSet {
+    async fn run(&self) -> anyhow::Result<()> {
+        let app = self.app.load_app().await?;
+        let app_dir = self.app.app_dir()?;
+        if !app.variables.iter().any(|v| v.name == self.name) {
+            anyhow::bail!("Variable '{}' is not declared by the application", self.name);
+        }
+        if let Some(constraints) = Schema::load_for_app(&app_dir, None)?.get(&self.name) {
+            constraints.validate(&self.value).map_err(|e| anyhow::anyhow!("The value for '{}' is invalid: {e}", self.name))?;
+        }
+
+        ValueStore::open(&app_dir)?.set(&self.name, &self.value)?;
+        println!("Stored the value of '{}' in {}", self.name, store_path(&app_dir).display());
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct StoreGet {
+    /// The variable whose stored value to use.
+    name: String,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl StoreGet {
+    fn get(&self) -> anyhow::Result<()> {
+        match ValueStore::open(&self.app.app_dir()?)?.get(&self.name)? {
+            Some(value) => println!("{value}"),
+            None => anyhow::bail!("'{}' has no stored value", self.name),
+        }
+        Ok(())
+    }
+
+    fn remove(&self) -> anyhow::Result<()> {
+        if ValueStore::open(&self.app.app_dir()?)?.remove(&self.name)? {
+            println!("Removed the stored value of '{}'", self.name);
+        } else {
+            println!("'{}' has no stored value", self.name);
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct StoreList {
+    /// Include the values of secret variables. Otherwise they are shown as REDACTED.
+    #[clap(long = "reveal-secrets", num_args = 0)]
+    reveal_secrets: bool,
+
+    #[clap(flatten)]
+    app: AppOptions,
+}
+
+impl StoreList {
+    async fn run(&self) -> anyhow::Result<()> {
+        let app = self.app.load_app().await?;
+        let values = ValueStore::open(&self.app.app_dir()?)?.list()?;
+        if values.is_empty() {
+            println!("No values are stored");
+            return Ok(());
+        }
+
+        let mut table = comfy_table::Table::new();
+        table.set_header(comfy_table::Row::from(vec!["Name", "Value", "Updated"]));
+        table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+        for stored in values {
+            let variable = app.variables.iter().find(|v| v.name == stored.name);
+            let value = match variable {
+                Some(v) if v.secret && !self.reveal_secrets => REDACTED.to_owned(),
+                Some(_) => stored.value,
+                None => format!("{} (not declared)", stored.value),
+            };
+            table.add_row(vec![stored.name, value, stored.updated_at]);
+        }
+        println!("{table}");
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeSet;
+
+use spin_variables::app::{self, VariableInfo};
+use spin_variables::registry::RegistryOptions;
+
+/// A table of which variables exist in each tag of a registry repository, and
+/// whether they are required or secret there.
+pub async fn all_tags_matrix(reference: &str, options: &RegistryOptions, jobs: usize) -> anyhow::Result<impl std::fmt::Display> {
+    use futures::{StreamExt, TryStreamExt};
+
+    let tags = app::registry_tag_references(reference, options).await?;
+    let loads = tags.into_iter().map(|(tag, tag_reference)| async move {
+        let app = app::app_from_registry(&tag_reference, options).await?;
+        anyhow::Ok((tag, app.variables))
+    });
+    let tag_variables: Vec<(String, Vec<VariableInfo>)> = futures::stream::iter(loads).buffered(jobs).try_collect().await?;
+
+    let names: BTreeSet<_> = tag_variables.iter().flat_map(|(_, vars)| vars.iter().map(|v| v.name.as_str())).collect();
+
+    let mut table = comfy_table::Table::new();
+    let mut header = vec!["Name"];
+    header.extend(tag_variables.iter().map(|(tag, _)| tag.as_str()));
+    table.set_header(comfy_table::Row::from(header));
+    table.load_preset(comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED);
+
+    for name in names {
+        let mut row = vec![name.to_owned()];
+        row.extend(tag_variables.iter().map(|(_, vars)| match vars.iter().find(|v| v.name == name) {
+            Some(variable) => status(variable),
+            None => String::new(),
+        }));
+        table.add_row(row);
+    }
+
+    Ok(table)
+}
+
+fn status(variable: &VariableInfo) -> String {
+    let required = if variable.required { "required" } else { "optional" };
+    if variable.secret {
+        format!("{required}, secret")
+    } else {
+        required.to_owned()
+    }
+}
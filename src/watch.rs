@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use spin_variables::app::VariableInfo;
+use spin_variables::{changes, env, format};
+
+use crate::{AppOptions, ValueOptions};
+
+#[derive(clap::Args)]
+pub struct WatchCommand {
+    #[clap(flatten)]
+    app: AppOptions,
+
+    #[clap(flatten)]
+    values: ValueOptions,
+
+    /// Include the values of secret variables in the table. Otherwise they are
+    /// replaced with REDACTED.
+    #[clap(long = "reveal-secrets", num_args = 0)]
+    reveal_secrets: bool,
+
+    /// How often to check for changes, in milliseconds.
+    #[clap(long = "interval", default_value_t = 1000)]
+    interval: u64,
+}
+
+impl WatchCommand {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let Some(manifest_path) = self.app.manifest_path()? else {
+            anyhow::bail!("Watch needs an application manifest file, not a registry reference");
+        };
+
+        let (app_name, mut variables, env_providers) = self.load().await?;
+        let context = format::FormatContext {
+            env_prefix: env_providers.first().map(|p| p.prefix()).unwrap_or(env::DEFAULT_PREFIX).to_owned(),
+            app_name,
+            schema: self.app.schema()?,
+            value_lengths: format::value_lengths(&variables),
+            ..Default::default()
+        };
+        // Changes are found from the real values, so only the table is redacted
+        let mut shown = variables.clone();
+        if !self.reveal_secrets {
+            format::redact_secrets(&mut shown);
+        }
+        println!("{}", format::table(&shown, &context));
+
+        let mut watched = self.watched_files(&manifest_path, &env_providers)?;
+        let mut last_modified = modified_times(&watched);
+        loop {
+            tokio::time::sleep(Duration::from_millis(self.interval)).await;
+
+            let modified = modified_times(&watched);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            // A manifest that is half-saved, or briefly invalid while being edited,
+            // shouldn't end the watch.
+            let (_, updated, env_providers) = match self.load().await {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("Error: {e:#}");
+                    continue;
+                }
+            };
+
+            let changes = changes::changes(&variables, &updated);
+            if changes.is_empty() {
+                println!("No changes to variables");
+            }
+            for change in &changes {
+                println!("{change}");
+            }
+            variables = updated;
+
+            // Editing the runtime config can change which dotenv files are read
+            let now_watched = self.watched_files(&manifest_path, &env_providers)?;
+            if now_watched != watched {
+                watched = now_watched;
+                last_modified = modified_times(&watched);
+            }
+        }
+    }
+
+    async fn load(&self) -> anyhow::Result<(String, Vec<VariableInfo>, Vec<env::EnvProvider>)> {
+        let mut app = self.app.load_app().await?;
+        let env_providers = self.values.resolve(&mut app.variables, &self.app.app_dir()?).await?;
+        Ok((app.name, app.variables, env_providers))
+    }
+
+    /// The manifest and the files values are read from, which are the files users
+    /// are likely to edit while watching.
+    fn watched_files(&self, manifest_path: &Path, env_providers: &[env::EnvProvider]) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = vec![manifest_path.to_owned()];
+        files.extend(self.values.value_files(&self.app.app_dir()?, env_providers)?);
+        Ok(files)
+    }
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files.iter().map(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok()).collect()
+}